@@ -0,0 +1,194 @@
+use rowan::TextRange;
+use yag_template_envdefs::Func;
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+use yag_template_syntax::visit::{self, Visitor};
+
+use crate::Severity;
+use crate::checks::receives_piped_value;
+use crate::lint::{Lint, LintContext, LintMeta};
+
+pub struct ArgumentCount;
+
+impl Lint for ArgumentCount {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "argument-count",
+            default_severity: Severity::Error,
+            description: "Reports calls with too few or too many arguments, and arguments passed to values that \
+                          are not functions.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let root = cx.root.clone();
+        visit::walk_root(&mut ArityChecker { cx }, &root);
+    }
+}
+
+struct ArityChecker<'c, 'a> {
+    cx: &'c mut LintContext<'a>,
+}
+
+/// The range of the arguments after the first `skip`, or `None` if there are
+/// no such arguments.
+fn args_range(args: impl Iterator<Item = ast::Expr>, skip: usize) -> Option<TextRange> {
+    args.skip(skip)
+        .map(|arg| arg.text_range())
+        .reduce(|acc, range| acc.cover(range))
+}
+
+/// The allowed number of arguments to `func`: a minimum, and a maximum unless
+/// the function is variadic.
+fn arity(func: &Func) -> (usize, Option<usize>) {
+    let min = func
+        .params
+        .iter()
+        .filter(|param| !param.is_optional && !param.is_variadic)
+        .count();
+    let max = if func.params.iter().any(|param| param.is_variadic) {
+        None
+    } else {
+        Some(func.params.len())
+    };
+    (min, max)
+}
+
+fn describe_expected(min: usize, max: Option<usize>) -> String {
+    match max {
+        Some(max) if max == min => min.to_string(),
+        Some(max) => format!("{min} to {max}"),
+        None => format!("at least {min}"),
+    }
+}
+
+/// Whether a value of this kind of expression can never be called with
+/// arguments. Field chains may evaluate to methods, so are not included.
+fn is_non_function(expr: &ast::Expr) -> bool {
+    matches!(
+        expr,
+        ast::Expr::Literal(_) | ast::Expr::VarAccess(_) | ast::Expr::ContextAccess(_) | ast::Expr::Parenthesized(_)
+    )
+}
+
+impl ArityChecker<'_, '_> {
+    fn report_non_function(&mut self, callee: &ast::Expr, range: TextRange) {
+        let message = format!("cannot pass arguments to non-function {}", callee.syntax().text());
+        self.cx.report(message, range);
+    }
+}
+
+impl Visitor for ArityChecker<'_, '_> {
+    fn enter_func_call(&mut self, call: &ast::FuncCall) {
+        let Some(func_name) = call.func_name() else {
+            return;
+        };
+        let Some(func) = self.cx.env.funcs.get(func_name.get()) else {
+            return;
+        };
+
+        let num_explicit = call.args().count();
+        let num_args = num_explicit + usize::from(receives_piped_value(call));
+        let (min, max) = arity(func);
+        let expected = describe_expected(min, max);
+        if num_args < min {
+            let message = format!(
+                "too few arguments for {}: expected {expected}, found {num_args}",
+                func.signature()
+            );
+            self.cx.report(message, func_name.text_range());
+        } else if let Some(max) = max.filter(|&max| num_args > max) {
+            // The piped value comes last, so if it is the only extra argument,
+            // point at the function instead.
+            let range = args_range(call.args(), max).unwrap_or(func_name.text_range());
+            let message = format!(
+                "too many arguments for {}: expected {expected}, found {num_args}",
+                func.signature()
+            );
+            self.cx.report(message, range);
+        }
+    }
+
+    fn enter_expr_call(&mut self, call: &ast::ExprCall) {
+        let Some(callee) = call.callee().filter(is_non_function) else {
+            return;
+        };
+        let range = args_range(call.args(), 0).unwrap_or(call.text_range());
+        self.report_non_function(&callee, range);
+    }
+
+    fn enter_pipeline(&mut self, pipeline: &ast::Pipeline) {
+        for stage in pipeline.stages() {
+            if let Some(call_expr) = stage.call_expr().filter(is_non_function) {
+                self.report_non_function(&call_expr, call_expr.text_range());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    const ENVDEFS: &str = "func sendMessage(channel, msg)\nfunc dbGet(userID, key)\nfunc print(args...)\n\
+                          func joinStr(sep, args...)\nfunc seq(start, stop, step?)\n";
+
+    fn problems(source: &str) -> Vec<(String, &str)> {
+        test_util::messages(ENVDEFS, source, "argument-count")
+    }
+
+    #[test]
+    fn calls_within_bounds_are_accepted() {
+        assert!(problems(r#"{{sendMessage nil "hi"}}{{print}}{{print 1 2 3}}{{joinStr ""}}"#).is_empty());
+        assert!(problems("{{seq 0 1}}{{seq 0 10 2}}{{1 | seq 0}}{{nil | sendMessage nil}}").is_empty());
+        assert!(problems("{{.Method 1 2}}{{$x.Method 1}}{{1 | .Method}}").is_empty());
+    }
+
+    #[test]
+    fn too_few_arguments_are_reported() {
+        assert_eq!(
+            problems("{{sendMessage nil}}"),
+            [(
+                "too few arguments for func sendMessage(channel, msg): expected 2, found 1".to_owned(),
+                "sendMessage"
+            )]
+        );
+        assert_eq!(
+            problems("{{joinStr}}"),
+            [(
+                "too few arguments for func joinStr(sep, args...): expected at least 1, found 0".to_owned(),
+                "joinStr"
+            )]
+        );
+    }
+
+    #[test]
+    fn too_many_arguments_are_reported() {
+        assert_eq!(
+            problems(r#"{{dbGet 1 "a" "b" "c"}}"#),
+            [(
+                "too many arguments for func dbGet(userID, key): expected 2, found 4".to_owned(),
+                r#""b" "c""#
+            )]
+        );
+        assert_eq!(
+            problems("{{2 | seq 0 1 1}}"),
+            [(
+                "too many arguments for func seq(start, stop, step?): expected 2 to 3, found 4".to_owned(),
+                "seq"
+            )]
+        );
+    }
+
+    #[test]
+    fn arguments_to_non_functions_are_reported() {
+        assert_eq!(
+            problems("{{$x := 1}}{{$x 2}}{{3 | $x}}{{(print) 4}}"),
+            [
+                ("cannot pass arguments to non-function $x".to_owned(), "2"),
+                ("cannot pass arguments to non-function $x".to_owned(), "$x"),
+                ("cannot pass arguments to non-function (print)".to_owned(), "4"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use foldhash::{HashMap, HashMapExt};
+use rowan::TextRange;
+use smol_str::SmolStr;
+use yag_template_envdefs::{CallLimit, EnvDefs};
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+use yag_template_syntax::{SyntaxKind, SyntaxNode};
+
+use crate::Severity;
+use crate::lint::{Lint, LintContext, LintMeta};
+
+pub struct CallLimitExceeded;
+
+impl Lint for CallLimitExceeded {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "call-limit",
+            default_severity: Severity::Warning,
+            description: "Reports templates that may call functions more often than YAGPDB permits in a single \
+                          execution. Calls in loops are counted once, and the most expensive branch of each \
+                          conditional is assumed to be taken.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let counter = CallCounter { env: cx.env };
+        // Each associated template is counted separately, since whether and
+        // how often it is invoked is not generally known.
+        let templates = std::iter::once(Some(cx.root.actions_with_text())).chain(
+            cx.root
+                .syntax()
+                .descendants()
+                .filter_map(ast::TemplateDefinition::cast)
+                .map(|define| define.template_body().map(|body| body.actions_with_text())),
+        );
+        let mut problems = Vec::new();
+        for elements in templates.flatten() {
+            for (counter_name, calls) in counter.elements(elements) {
+                let max = calls[0].limit.max_for(cx.config.premium()) as usize;
+                if calls.len() > max {
+                    let message = format!(
+                        "this call may exceed the limit of {max} {counter_name} calls per execution: up to {} calls ({})",
+                        calls.len(),
+                        breakdown(&calls)
+                    );
+                    problems.push((calls[max].range, message));
+                }
+            }
+        }
+        problems.sort_by_key(|(range, _)| range.start());
+        for (range, message) in problems {
+            cx.report(message, range);
+        }
+    }
+}
+
+pub struct LimitedCallInLoop;
+
+impl Lint for LimitedCallInLoop {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "limited-call-in-loop",
+            default_severity: Severity::Info,
+            description: "Reports calls in loops to functions that YAGPDB permits to be called only a certain number \
+                          of times per execution, since the number of iterations is not bounded statically.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let calls: Vec<_> = cx.root.syntax().descendants().filter_map(ast::FuncCall::cast).collect();
+        for call in calls {
+            let Some(name) = call.func_name() else {
+                continue;
+            };
+            let Some(limit) = cx.env.funcs.get(name.get()).and_then(|func| func.limit.as_ref()) else {
+                continue;
+            };
+            if is_in_loop(call.syntax()) {
+                let message = format!(
+                    "{} is called in a loop, but YAGPDB permits only {} {} calls per execution",
+                    name.get(),
+                    limit.max_for(cx.config.premium()),
+                    limit.counter
+                );
+                cx.report(message, name.text_range());
+            }
+        }
+    }
+}
+
+/// Report whether `node` may be evaluated repeatedly by an enclosing loop in
+/// the same template.
+fn is_in_loop(node: &SyntaxNode) -> bool {
+    node.ancestors()
+        .take_while(|ancestor| ancestor.kind() != SyntaxKind::TemplateDefinition)
+        .any(|ancestor| match ancestor.kind() {
+            SyntaxKind::WhileClause => true,
+            SyntaxKind::ActionList => ancestor
+                .parent()
+                .is_some_and(|parent| matches!(parent.kind(), SyntaxKind::RangeLoop | SyntaxKind::WhileLoop)),
+            _ => false,
+        })
+}
+
+fn breakdown(calls: &[LimitedCall<'_>]) -> String {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for call in calls {
+        match counts.iter_mut().find(|(func, _)| *func == call.func) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((&call.func, 1)),
+        }
+    }
+    counts
+        .iter()
+        .map(|(func, count)| format!("{count} × {func}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, Clone)]
+struct LimitedCall<'e> {
+    func: SmolStr,
+    /// The range of the function name.
+    range: TextRange,
+    limit: &'e CallLimit,
+}
+
+/// The limited calls made along a path, grouped by counter and in source
+/// order.
+type Usage<'e> = HashMap<&'e str, Vec<LimitedCall<'e>>>;
+
+fn then<'e>(mut first: Usage<'e>, second: Usage<'e>) -> Usage<'e> {
+    for (counter, calls) in second {
+        first.entry(counter).or_default().extend(calls);
+    }
+    first
+}
+
+/// Combine the usage of alternative paths, taking the most calls for each
+/// counter. The result is an upper bound, since the most expensive path may
+/// differ between counters.
+fn either<'e>(mut a: Usage<'e>, b: Usage<'e>) -> Usage<'e> {
+    for (counter, calls) in b {
+        let entry = a.entry(counter).or_default();
+        if calls.len() > entry.len() {
+            *entry = calls;
+        }
+    }
+    a
+}
+
+struct CallCounter<'e> {
+    env: &'e EnvDefs,
+}
+
+impl<'e> CallCounter<'e> {
+    fn elements(&self, elements: ast::ActionsWithText) -> Usage<'e> {
+        elements
+            .filter_map(|element| match element {
+                ast::ActionOrText::Action(action) => Some(self.action(action)),
+                ast::ActionOrText::Text(_) => None,
+            })
+            .fold(Usage::new(), then)
+    }
+
+    fn body(&self, body: Option<ast::ActionList>) -> Usage<'e> {
+        body.map_or_else(Usage::new, |body| self.elements(body.actions_with_text()))
+    }
+
+    fn expr(&self, expr: Option<ast::Expr>) -> Usage<'e> {
+        expr.map_or_else(Usage::new, |expr| self.calls_in(expr.syntax()))
+    }
+
+    fn action(&self, action: ast::Action) -> Usage<'e> {
+        use ast::Action::*;
+        match action {
+            Comment(_) | TemplateDefinition(_) => Usage::new(),
+            TemplateBlock(block) => then(self.expr(block.context_data()), self.body(block.template_body())),
+            If(if_action) => then(
+                self.expr(if_action.condition()),
+                either(
+                    self.body(if_action.body()),
+                    self.else_chain(&if_action.else_branches().collect::<Vec<_>>()),
+                ),
+            ),
+            With(with_action) => then(
+                self.expr(with_action.condition()),
+                either(
+                    self.body(with_action.body()),
+                    self.else_chain(&with_action.else_branches().collect::<Vec<_>>()),
+                ),
+            ),
+            Range(range_loop) => then(
+                self.expr(range_loop.clause().and_then(|clause| clause.expr())),
+                either(
+                    self.body(range_loop.body()),
+                    self.body(range_loop.else_branch().and_then(|branch| branch.body())),
+                ),
+            ),
+            While(while_loop) => then(
+                self.expr(while_loop.condition()),
+                either(
+                    self.body(while_loop.body()),
+                    self.body(while_loop.else_branch().and_then(|branch| branch.body())),
+                ),
+            ),
+            // An error may occur after all the calls in the try body.
+            TryCatch(try_catch) => then(self.body(try_catch.try_body()), self.body(try_catch.catch_body())),
+            _ => self.calls_in(action.syntax()),
+        }
+    }
+
+    /// The usage of a chain of `{{else if}}` and `{{else}}` branches, where
+    /// each condition is evaluated only if the previous ones were false.
+    fn else_chain(&self, branches: &[ast::ElseBranch]) -> Usage<'e> {
+        let Some((branch, rest)) = branches.split_first() else {
+            return Usage::new();
+        };
+        match branch.condition() {
+            Some(condition) => then(
+                self.calls_in(condition.syntax()),
+                either(self.body(branch.body()), self.else_chain(rest)),
+            ),
+            None => self.body(branch.body()),
+        }
+    }
+
+    fn calls_in(&self, node: &SyntaxNode) -> Usage<'e> {
+        let mut usage = Usage::new();
+        for call in node.descendants().filter_map(ast::FuncCall::cast) {
+            let Some(name) = call.func_name() else {
+                continue;
+            };
+            let Some(limit) = self.env.funcs.get(name.get()).and_then(|func| func.limit.as_ref()) else {
+                continue;
+            };
+            usage.entry(limit.counter.as_str()).or_default().push(LimitedCall {
+                func: SmolStr::new(name.get()),
+                range: name.text_range(),
+                limit,
+            });
+        }
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+    use crate::lint::LintConfig;
+
+    const ENVDEFS: &str = "func dbGet(key)\nlimit db 2 premium 4\nfunc dbSet(key, value)\nlimit db 2 premium 4\n\
+                           func sendDM(msg)\nlimit send_dm 1\n";
+
+    fn problems(source: &str) -> Vec<(&'static str, String, &str)> {
+        test_util::problems(ENVDEFS, source, &["call-limit", "limited-call-in-loop"])
+    }
+
+    #[test]
+    fn calls_within_limits_are_accepted() {
+        assert!(problems(r#"{{dbGet "a"}}{{dbSet "a" 1}}{{sendDM "hi"}}"#).is_empty());
+        assert!(problems(r#"{{dbGet "a"}}{{if .}}{{dbGet "b"}}{{else}}{{dbSet "b" 1}}{{end}}"#).is_empty());
+    }
+
+    #[test]
+    fn shared_limits_are_reported_with_a_breakdown() {
+        assert_eq!(
+            problems(r#"{{dbGet "a"}}{{if .}}{{sendDM "x"}}{{else if .}}{{dbSet "a" 1}}{{dbGet "b"}}{{end}}"#),
+            [(
+                "call-limit",
+                "this call may exceed the limit of 2 db calls per execution: up to 3 calls (2 × dbGet, 1 × dbSet)"
+                    .to_owned(),
+                "dbGet"
+            )]
+        );
+    }
+
+    #[test]
+    fn associated_templates_are_counted_separately() {
+        assert!(problems(r#"{{sendDM "a"}}{{define "t"}}{{sendDM "b"}}{{end}}"#).is_empty());
+        assert_eq!(
+            problems(r#"{{define "t"}}{{try}}{{sendDM "a"}}{{catch}}{{sendDM "b"}}{{end}}{{end}}"#).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn premium_limits_apply_on_premium_servers() {
+        let mut config = LintConfig::new();
+        config.set_premium(true);
+        let problems = |source| test_util::diagnostics_with_config(ENVDEFS, source, &["call-limit"], &config).len();
+        assert_eq!(
+            problems(r#"{{sendDM "a"}}{{dbGet "a"}}{{dbGet "b"}}{{dbGet "c"}}{{dbGet "d"}}"#),
+            0
+        );
+        assert_eq!(problems(r#"{{sendDM "a"}}{{sendDM "b"}}"#), 1);
+    }
+
+    #[test]
+    fn calls_in_loops_are_reported() {
+        assert_eq!(
+            problems(r#"{{range .}}{{dbGet .}}{{else}}{{sendDM "none"}}{{end}}{{while dbGet "x"}}{{end}}"#),
+            [
+                (
+                    "limited-call-in-loop",
+                    "dbGet is called in a loop, but YAGPDB permits only 2 db calls per execution".to_owned(),
+                    "dbGet"
+                ),
+                (
+                    "limited-call-in-loop",
+                    "dbGet is called in a loop, but YAGPDB permits only 2 db calls per execution".to_owned(),
+                    "dbGet"
+                ),
+            ]
+        );
+    }
+}
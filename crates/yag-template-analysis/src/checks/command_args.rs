@@ -0,0 +1,197 @@
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+
+use crate::Severity;
+use crate::command_args::ArgType;
+use crate::lint::{Lint, LintContext, LintMeta};
+
+pub struct InvalidCommandArg;
+
+impl Lint for InvalidCommandArg {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "invalid-command-arg",
+            default_severity: Severity::Error,
+            description: "Reports `carg` calls with unknown types or invalid bounds, and `parseArgs` calls requiring \
+                          more arguments than they declare.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let calls: Vec<_> = cx.root.syntax().descendants().filter_map(ast::FuncCall::cast).collect();
+        for call in calls {
+            match call.func_name().as_ref().map(|name| name.get()) {
+                Some("carg") => check_carg(cx, &call),
+                Some("parseArgs") => check_parse_args(cx, &call),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn check_carg(cx: &mut LintContext<'_>, call: &ast::FuncCall) {
+    let mut args = call.args();
+    let Some(ast::Expr::Literal(type_lit)) = args.next() else {
+        return;
+    };
+    let ast::LiteralKind::String(type_name) = type_lit.kind() else {
+        return;
+    };
+    let Some(ty) = ArgType::from_name(&type_name.get()) else {
+        let expected: Vec<_> = ArgType::ALL.iter().map(|ty| ty.name()).collect();
+        let message = format!(
+            "unknown argument type {:?}; expected one of {}",
+            type_name.get(),
+            expected.join(", ")
+        );
+        cx.report(message, type_name.text_range());
+        return;
+    };
+
+    let bounds: Vec<_> = args.skip(1).collect();
+    let (Some(first), Some(last)) = (bounds.first(), bounds.last()) else {
+        return;
+    };
+    if !ty.supports_bounds() {
+        let message = format!("arguments of type {} do not accept bounds", ty.name());
+        cx.report(message, first.text_range().cover(last.text_range()));
+    } else if let [min, max] = bounds.as_slice()
+        && let (Some(min_value), Some(max_value)) = (number_literal(min), number_literal(max))
+        && min_value > max_value
+    {
+        let message = format!(
+            "the minimum {} is greater than the maximum {}",
+            min.syntax().text(),
+            max.syntax().text()
+        );
+        cx.report(message, min.text_range().cover(max.text_range()));
+    }
+}
+
+fn check_parse_args(cx: &mut LintContext<'_>, call: &ast::FuncCall) {
+    let Some(num_required_expr) = call.args().next() else {
+        return;
+    };
+    let Some(num_required) = number_literal(&num_required_expr) else {
+        return;
+    };
+    // Each argument after the error message declares one argument, whether
+    // written as a `carg` call or given by a variable holding its result.
+    let declared = call.args().skip(2).count();
+    if num_required > declared as f64 {
+        let message = format!(
+            "parseArgs requires {} arguments, but only {declared} {} declared",
+            num_required_expr.syntax().text(),
+            if declared == 1 { "is" } else { "are" }
+        );
+        cx.report(message, num_required_expr.text_range());
+    }
+}
+
+fn number_literal(expr: &ast::Expr) -> Option<f64> {
+    match expr {
+        ast::Expr::Literal(lit) => match lit.kind() {
+            ast::LiteralKind::Int(int) => int.get().map(|value| value as f64),
+            ast::LiteralKind::Float(float) => float.get(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub struct ArgIndexOutOfRange;
+
+impl Lint for ArgIndexOutOfRange {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "arg-index-out-of-range",
+            default_severity: Severity::Error,
+            description: "Reports `.Get` and `.IsSet` calls on the result of `parseArgs` with indices beyond the \
+                          arguments it declares.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        for access in cx.command_args.accesses() {
+            let Some(schema) = cx.command_args.schema(access.var) else {
+                continue;
+            };
+            let count = schema.args.len();
+            if usize::try_from(access.index).is_ok_and(|index| index < count) {
+                continue;
+            }
+            let message = format!(
+                "argument index {} is out of range; parseArgs declares only {count} argument{}",
+                access.index,
+                if count == 1 { "" } else { "s" }
+            );
+            cx.report(message, access.index_range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    fn problems(source: &str) -> Vec<(&'static str, String, &str)> {
+        test_util::problems("", source, &["invalid-command-arg", "arg-index-out-of-range"])
+    }
+
+    #[test]
+    fn invalid_cargs_are_reported() {
+        assert_eq!(
+            problems(
+                r#"{{carg "integer" "a"}}{{carg "user" "b" 1 2}}{{carg "int" "c" 10 1}}{{carg "duration" "d" 60}}"#
+            ),
+            [
+                (
+                    "invalid-command-arg",
+                    "unknown argument type \"integer\"; expected one of int, float, string, user, userid, member, \
+                     channel, role, duration"
+                        .to_owned(),
+                    r#""integer""#
+                ),
+                (
+                    "invalid-command-arg",
+                    "arguments of type user do not accept bounds".to_owned(),
+                    "1 2"
+                ),
+                (
+                    "invalid-command-arg",
+                    "the minimum 10 is greater than the maximum 1".to_owned(),
+                    "10 1"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn too_many_required_args_are_reported() {
+        assert_eq!(
+            problems(r#"{{$args := parseArgs 2 "" (carg "int" "n")}}{{$args}}"#),
+            [(
+                "invalid-command-arg",
+                "parseArgs requires 2 arguments, but only 1 is declared".to_owned(),
+                "2"
+            )]
+        );
+        assert_eq!(problems(r#"{{$args := parseArgs 2 "" $def}}{{$args}}"#).len(), 1);
+        assert!(problems(r#"{{$args := parseArgs 2 "" $def (carg "int" "n")}}{{$args}}"#).is_empty());
+    }
+
+    #[test]
+    fn out_of_range_indices_are_reported() {
+        assert_eq!(
+            problems(
+                r#"{{$args := parseArgs 1 "" (carg "int" "n") (carg "user" "u")}}{{$args.Get 0}}{{$args.IsSet 2}}"#
+            ),
+            [(
+                "arg-index-out-of-range",
+                "argument index 2 is out of range; parseArgs declares only 2 arguments".to_owned(),
+                "2"
+            )]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use foldhash::{HashSet, HashSetExt};
+use rowan::TextRange;
+use yag_template_syntax::ast::{self, AstNode};
+
+use crate::control_flow::{ControlFlowGraph, VarEvent, VarEventKind};
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::scope::VarSymbolId;
+use crate::{DiagnosticTag, Severity};
+
+pub struct DeadStore;
+
+impl Lint for DeadStore {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "dead-store",
+            default_severity: Severity::Warning,
+            description: "Reports assignments to variables whose value is overwritten or goes out of scope before \
+                          it is read. Declarations initialized to a literal are exempt, as are variables with \
+                          names ending in `_`.",
+            tags: &[DiagnosticTag::Unnecessary],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let graph = cx.control_flow;
+        let events = graph.var_events(cx.root, cx.scope_info);
+        let live_in = liveness(graph, &events);
+
+        let mut dead_stores = Vec::new();
+        for (id, node) in graph.nodes() {
+            if !graph.is_reachable(id) {
+                continue;
+            }
+            let mut live = live_out(node.successors.iter().map(|succ| &live_in[succ.index()]));
+            for event in events[id.index()].iter().rev() {
+                match event.kind {
+                    VarEventKind::Read => {
+                        live.insert(event.sym);
+                    }
+                    VarEventKind::Store(_) | VarEventKind::Iteration(_) => {
+                        if let Some(store) = reportable_store(event)
+                            && !live.contains(&event.sym)
+                        {
+                            dead_stores.push((event.sym, store));
+                        }
+                        live.remove(&event.sym);
+                    }
+                }
+            }
+        }
+
+        dead_stores.sort_by_key(|&(_, range)| (range.start(), range.end()));
+        for (sym, range) in dead_stores {
+            let Some(v) = cx.scope_info.var_symbol(sym) else {
+                continue;
+            };
+            // Variables that are never read at all are reported as unused instead.
+            if v.used && !v.name.ends_with('_') {
+                cx.report(format!("value assigned to {} is never read", v.name), range);
+            }
+        }
+    }
+}
+
+/// Compute the variables live on entry to each node, by iterating to a fixed
+/// point. Since loops are back edges in the graph, a variable read on a later
+/// iteration is live at the end of the loop body.
+fn liveness(graph: &ControlFlowGraph, events: &[Vec<VarEvent>]) -> Vec<HashSet<VarSymbolId>> {
+    let mut live_in = vec![HashSet::new(); events.len()];
+    let mut worklist: Vec<_> = graph.nodes().map(|(id, _)| id).collect();
+    while let Some(id) = worklist.pop() {
+        let node = graph.node(id);
+        let mut live = live_out(node.successors.iter().map(|succ| &live_in[succ.index()]));
+        for event in events[id.index()].iter().rev() {
+            match event.kind {
+                VarEventKind::Read => live.insert(event.sym),
+                VarEventKind::Store(_) | VarEventKind::Iteration(_) => live.remove(&event.sym),
+            };
+        }
+        if live != live_in[id.index()] {
+            live_in[id.index()] = live;
+            worklist.extend(node.predecessors.iter().copied());
+        }
+    }
+    live_in
+}
+
+fn live_out<'a>(succ_live_in: impl Iterator<Item = &'a HashSet<VarSymbolId>>) -> HashSet<VarSymbolId> {
+    let mut live = HashSet::new();
+    for succ_live in succ_live_in {
+        live.extend(succ_live.iter().copied());
+    }
+    live
+}
+
+/// The range of the declaration or assignment performed by `event`, if it
+/// should be reported when dead.
+fn reportable_store(event: &VarEvent) -> Option<TextRange> {
+    let VarEventKind::Store(stmt) = &event.kind else {
+        return None;
+    };
+    let initializer = ast::VarDecl::cast(stmt.clone()).and_then(|decl| decl.initializer());
+    (!matches!(initializer, Some(ast::Expr::Literal(_)))).then(|| stmt.text_range())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    fn dead_stores(source: &str) -> Vec<&str> {
+        test_util::diagnostics("", source, &["dead-store"])
+            .into_iter()
+            .map(|diag| &source[diag.range])
+            .collect()
+    }
+
+    #[test]
+    fn overwritten_and_trailing_stores_are_reported() {
+        assert_eq!(dead_stores("{{$x := .A}}{{$x = .B}}{{$x}}"), ["$x := .A"]);
+        assert_eq!(dead_stores("{{$x := .A}}{{$x}}{{$x = add $x 1}}"), ["$x = add $x 1"]);
+    }
+
+    #[test]
+    fn stores_read_on_some_path_are_not_reported() {
+        assert!(dead_stores("{{$x := 0}}{{if .}}{{$x = .A}}{{end}}{{$x}}").is_empty());
+        assert!(dead_stores("{{$x := .A}}{{if .}}{{$x = .B}}{{$x}}{{else}}{{$x}}{{end}}").is_empty());
+    }
+
+    #[test]
+    fn stores_read_on_later_loop_iterations_are_not_reported() {
+        assert!(dead_stores("{{$prev := 0}}{{range .}}{{$prev}}{{$prev = .}}{{end}}").is_empty());
+        assert!(dead_stores("{{$i := 0}}{{while lt $i 10}}{{$i = add $i 1}}{{end}}").is_empty());
+        assert!(dead_stores("{{$x := 0}}{{range .}}{{$x = .}}{{break}}{{end}}{{$x}}").is_empty());
+    }
+
+    #[test]
+    fn stores_before_catch_are_not_reported() {
+        assert!(dead_stores("{{$x := .A}}{{try}}{{$x = .B}}{{.C}}{{$x = .D}}{{catch}}{{$x}}{{end}}").is_empty());
+    }
+
+    #[test]
+    fn variables_that_are_never_read() {
+        assert_eq!(dead_stores("{{$x := .A}}{{$x = .B}}"), ["$x := .A", "$x = .B"]);
+        // Reported as unused instead.
+        assert!(dead_stores("{{$x := .A}}").is_empty());
+        assert!(dead_stores("{{$x_ := .A}}{{$x_}}{{$x_ = .B}}").is_empty());
+    }
+}
@@ -1,21 +1,41 @@
-use yag_template_envdefs::EnvDefs;
 use yag_template_syntax::ast;
-use yag_template_syntax::ast::{AstNode, AstToken};
+use yag_template_syntax::ast::AstToken;
+use yag_template_syntax::visit::{self, Visitor};
 
-use crate::AnalysisWarning;
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::{DiagnosticTag, Severity};
 
-pub fn check(env: &EnvDefs, root: &ast::Root) -> Vec<AnalysisWarning> {
-    root.syntax()
-        .descendants()
-        .filter_map(ast::FuncCall::cast)
-        .filter_map(|call| check_func_call(env, call))
-        .collect()
+pub struct DeprecatedFunction;
+
+impl Lint for DeprecatedFunction {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "deprecated-function",
+            default_severity: Severity::Warning,
+            description: "Reports calls to functions that are documented as deprecated.",
+            tags: &[DiagnosticTag::Deprecated],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let root = cx.root.clone();
+        visit::walk_root(&mut DeprecatedFuncChecker { cx }, &root);
+    }
+}
+
+struct DeprecatedFuncChecker<'c, 'a> {
+    cx: &'c mut LintContext<'a>,
 }
 
-fn check_func_call(env: &EnvDefs, call: ast::FuncCall) -> Option<AnalysisWarning> {
-    let func_name_ident = call.func_name()?;
-    let func_name = func_name_ident.get();
-    let func = env.funcs.get(func_name)?;
-    func.is_deprecated
-        .then(|| AnalysisWarning::new_deprecation(format!("{func_name} is deprecated"), func_name_ident.text_range()))
+impl Visitor for DeprecatedFuncChecker<'_, '_> {
+    fn enter_func_call(&mut self, call: &ast::FuncCall) {
+        let Some(func_name_ident) = call.func_name() else {
+            return;
+        };
+        let func_name = func_name_ident.get();
+        if self.cx.env.funcs.get(func_name).is_some_and(|func| func.is_deprecated) {
+            self.cx
+                .report(format!("{func_name} is deprecated"), func_name_ident.text_range());
+        }
+    }
 }
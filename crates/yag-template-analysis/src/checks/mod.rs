@@ -1,2 +1,145 @@
+//! The built-in lint rules.
+
+pub mod arity;
+pub mod call_limits;
+pub mod command_args;
+pub mod dead_stores;
 pub mod deprecated_funcs;
+pub mod placement;
+pub mod printf;
+pub mod rebound_dot;
+pub mod recursion;
+pub mod regex;
+pub mod returns;
+pub mod shadowing;
+pub mod size_limit;
+pub mod taint;
+pub mod templates;
+pub mod triggers;
+pub mod type_mismatch;
 pub mod undefined_funcs;
+pub mod undefined_vars;
+pub mod unreachable;
+pub mod unused_vars;
+
+use yag_template_syntax::ast::{self, AstNode};
+
+use crate::lint::LintRegistry;
+use crate::suppress;
+
+pub(crate) fn register_builtin_lints(registry: &mut LintRegistry) {
+    registry.register(undefined_funcs::UndefinedFunction);
+    registry.register(deprecated_funcs::DeprecatedFunction);
+    registry.register(undefined_vars::UndefinedVariable);
+    registry.register(unused_vars::UnusedVariable);
+    registry.register(rebound_dot::ReboundDot);
+    registry.register(shadowing::RedeclaredVariable);
+    registry.register(shadowing::LostAssignment);
+    registry.register(shadowing::ShadowedVariable);
+    registry.register(dead_stores::DeadStore);
+    registry.register(type_mismatch::TypeMismatch);
+    registry.register(printf::PrintfFormat);
+    registry.register(regex::InvalidRegex);
+    registry.register(regex::UnknownCaptureGroup);
+    registry.register(arity::ArgumentCount);
+    registry.register(command_args::InvalidCommandArg);
+    registry.register(command_args::ArgIndexOutOfRange);
+    registry.register(placement::MisplacedLoopControl);
+    registry.register(placement::NestedTemplateDefinition);
+    registry.register(unreachable::UnreachableCode);
+    registry.register(templates::DuplicateTemplate);
+    registry.register(templates::UnusedTemplate);
+    registry.register(recursion::UnconditionalRecursion);
+    registry.register(recursion::RecursiveTemplate);
+    registry.register(returns::MissingReturn);
+    registry.register(returns::DiscardedReturnValue);
+    registry.register(returns::TopLevelReturn);
+    registry.register(size_limit::SizeLimit);
+    registry.register(call_limits::CallLimitExceeded);
+    registry.register(call_limits::LimitedCallInLoop);
+    registry.register(taint::UnsanitizedUserInput);
+    registry.register(triggers::InvalidForTrigger);
+    registry.register(suppress::UnusedSuppression);
+    registry.register(suppress::UnknownSuppression);
+}
+
+/// Report whether `call` is a stage of a pipeline, and so receives the result
+/// of the previous stage as its final argument.
+pub(crate) fn receives_piped_value(call: &impl AstNode) -> bool {
+    call.syntax().parent().and_then(ast::PipelineStage::cast).is_some()
+}
+
+/// The value of `expr` if it is a string literal.
+pub(crate) fn string_literal(expr: &ast::Expr) -> Option<String> {
+    match expr {
+        ast::Expr::Literal(lit) => match lit.kind() {
+            ast::LiteralKind::String(s) => Some(s.get().into_owned()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Format `n` along with `noun`, pluralized if `n` is not 1.
+pub(crate) fn pluralize(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("{n} {noun}")
+    } else {
+        format!("{n} {noun}s")
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use yag_template_envdefs::EnvDefSource;
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::{SyntaxNode, ast, parser};
+
+    use crate::lint::{LintConfig, LintRegistry};
+    use crate::{Diagnostic, analyze_with};
+
+    /// Analyze `source`, which must parse without errors, against the
+    /// definitions in `ydef`, returning the diagnostics reported by `rules`.
+    pub(crate) fn diagnostics(ydef: &'static str, source: &str, rules: &[&str]) -> Vec<Diagnostic> {
+        diagnostics_with_config(ydef, source, rules, &LintConfig::default())
+    }
+
+    pub(crate) fn diagnostics_with_config(
+        ydef: &'static str,
+        source: &str,
+        rules: &[&str],
+        config: &LintConfig,
+    ) -> Vec<Diagnostic> {
+        let parse = parser::parse(source);
+        assert!(parse.errors.is_empty(), "unexpected errors: {:?}", parse.errors);
+        let root = SyntaxNode::new_root(parse.root).to::<ast::Root>();
+        let env = yag_template_envdefs::parse(&[EnvDefSource::new_static("test.ydef", ydef)]).unwrap();
+        analyze_with(&env, root, &LintRegistry::default(), config)
+            .diagnostics
+            .into_iter()
+            .filter(|diag| rules.contains(&diag.rule))
+            .collect()
+    }
+
+    /// Like [diagnostics], but return the rule and message of each diagnostic
+    /// along with the source it covers.
+    pub(crate) fn problems<'s>(
+        ydef: &'static str,
+        source: &'s str,
+        rules: &[&str],
+    ) -> Vec<(&'static str, String, &'s str)> {
+        diagnostics(ydef, source, rules)
+            .into_iter()
+            .map(|diag| (diag.rule, diag.message, &source[diag.range]))
+            .collect()
+    }
+
+    /// Like [diagnostics] for a single rule, but return the message of each
+    /// diagnostic along with the source it covers.
+    pub(crate) fn messages<'s>(ydef: &'static str, source: &'s str, rule: &str) -> Vec<(String, &'s str)> {
+        diagnostics(ydef, source, &[rule])
+            .into_iter()
+            .map(|diag| (diag.message, &source[diag.range]))
+            .collect()
+    }
+}
@@ -0,0 +1,196 @@
+use foldhash::{HashSet, HashSetExt};
+use rowan::TextRange;
+use yag_template_syntax::SyntaxKind;
+use yag_template_syntax::ast::{self, AstNode};
+use yag_template_syntax::visit::{self, ScopeKind, Visitor};
+
+use crate::Severity;
+use crate::lint::{Lint, LintContext, LintMeta};
+
+pub struct MisplacedLoopControl;
+
+impl Lint for MisplacedLoopControl {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "misplaced-loop-control",
+            default_severity: Severity::Error,
+            description: "Reports `break` and `continue` actions outside the body of a loop in the same template.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let root = cx.root.clone();
+        let mut checker = LoopControlChecker {
+            cx,
+            loop_bodies: HashSet::new(),
+            contexts: Vec::new(),
+        };
+        visit::walk_root(&mut checker, &root);
+    }
+}
+
+/// A region of the template relevant to loop control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    LoopBody,
+    Template,
+    Other,
+}
+
+struct LoopControlChecker<'c, 'a> {
+    cx: &'c mut LintContext<'a>,
+    /// The ranges of the bodies of loops entered so far. Note that the
+    /// `{{else}}` branch of a loop is not part of its body.
+    loop_bodies: HashSet<TextRange>,
+    contexts: Vec<Context>,
+}
+
+impl LoopControlChecker<'_, '_> {
+    fn check_loop_control(&mut self, keyword: &str, range: TextRange) {
+        let mut crosses_template = false;
+        for context in self.contexts.iter().rev() {
+            match context {
+                Context::LoopBody if crosses_template => {
+                    let message =
+                        format!("{{{{{keyword}}}}} cannot refer to a loop outside the enclosing template definition");
+                    self.cx.report(message, range);
+                    return;
+                }
+                Context::LoopBody => return,
+                Context::Template => crosses_template = true,
+                Context::Other => {}
+            }
+        }
+        let message = format!("{{{{{keyword}}}}} outside {{{{range}}}} or {{{{while}}}}");
+        self.cx.report(message, range);
+    }
+}
+
+impl Visitor for LoopControlChecker<'_, '_> {
+    fn enter_scope(&mut self, kind: ScopeKind, range: TextRange) {
+        let context = match kind {
+            ScopeKind::Template => Context::Template,
+            ScopeKind::Body if self.loop_bodies.contains(&range) => Context::LoopBody,
+            _ => Context::Other,
+        };
+        self.contexts.push(context);
+    }
+
+    fn leave_scope(&mut self, _kind: ScopeKind, _range: TextRange) {
+        self.contexts.pop();
+    }
+
+    fn enter_range_loop(&mut self, range_loop: &ast::RangeLoop) {
+        if let Some(body) = range_loop.body() {
+            self.loop_bodies.insert(body.text_range());
+        }
+    }
+
+    fn enter_while_loop(&mut self, while_loop: &ast::WhileLoop) {
+        if let Some(body) = while_loop.body() {
+            self.loop_bodies.insert(body.text_range());
+        }
+    }
+
+    fn enter_loop_break(&mut self, node: &ast::LoopBreak) {
+        self.check_loop_control("break", node.text_range());
+    }
+
+    fn enter_loop_continue(&mut self, node: &ast::LoopContinue) {
+        self.check_loop_control("continue", node.text_range());
+    }
+}
+
+pub struct NestedTemplateDefinition;
+
+impl Lint for NestedTemplateDefinition {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "nested-template-definition",
+            default_severity: Severity::Error,
+            description: "Reports `define` actions that are not at the top level of the template.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let nested_defines = cx
+            .root
+            .syntax()
+            .descendants()
+            .filter_map(ast::TemplateDefinition::cast)
+            .filter(|define| {
+                define
+                    .syntax()
+                    .parent()
+                    .is_some_and(|parent| parent.kind() != SyntaxKind::Root)
+            });
+        for define in nested_defines {
+            let range = define
+                .clause()
+                .map_or(define.text_range(), |clause| clause.text_range());
+            cx.report("template definitions are only permitted at the top level", range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    fn problems(source: &str) -> Vec<(String, &str)> {
+        test_util::diagnostics("", source, &["misplaced-loop-control", "nested-template-definition"])
+            .into_iter()
+            .map(|diag| (diag.message, &source[diag.range]))
+            .collect()
+    }
+
+    #[test]
+    fn loop_control_inside_loops_is_accepted() {
+        assert!(problems("{{range .}}{{if .}}{{break}}{{end}}{{continue}}{{end}}").is_empty());
+        assert!(problems("{{while .}}{{with .}}{{continue}}{{end}}{{end}}").is_empty());
+        assert!(problems(r#"{{define "t"}}{{range .}}{{break}}{{end}}{{end}}"#).is_empty());
+    }
+
+    #[test]
+    fn loop_control_outside_loops_is_reported() {
+        assert_eq!(
+            problems("{{break}}{{if .}}{{ continue }}{{end}}"),
+            [
+                ("{{break}} outside {{range}} or {{while}}".to_owned(), "{{break}}"),
+                (
+                    "{{continue}} outside {{range}} or {{while}}".to_owned(),
+                    "{{ continue }}"
+                ),
+            ]
+        );
+        assert_eq!(
+            problems("{{range .}}{{else}}{{break}}{{end}}"),
+            [("{{break}} outside {{range}} or {{while}}".to_owned(), "{{break}}")]
+        );
+    }
+
+    #[test]
+    fn loop_control_crossing_template_definitions_is_reported() {
+        assert_eq!(
+            problems(r#"{{range .}}{{block "t" .}}{{break}}{{end}}{{end}}"#),
+            [(
+                "{{break}} cannot refer to a loop outside the enclosing template definition".to_owned(),
+                "{{break}}"
+            )]
+        );
+    }
+
+    #[test]
+    fn nested_template_definitions_are_reported() {
+        assert!(problems(r#"{{define "a"}}{{end}}{{if .}}{{block "b" .}}{{end}}{{end}}"#).is_empty());
+        assert_eq!(
+            problems(r#"{{if .}}{{define "a"}}{{end}}{{end}}"#),
+            [(
+                "template definitions are only permitted at the top level".to_owned(),
+                r#"{{define "a"}}"#
+            )]
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use rowan::{TextRange, TextSize};
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+use yag_template_syntax::visit::{self, Visitor};
+
+use crate::Severity;
+use crate::checks::{pluralize, receives_piped_value};
+use crate::lint::{Lint, LintContext, LintMeta};
+
+/// Functions accepting a format string, along with the index of the format
+/// parameter. The arguments to format are taken to follow the format string.
+const FORMAT_FUNCS: &[(&str, usize)] = &[("printf", 0)];
+
+/// The verbs understood by Go's `fmt` package for at least one type of value.
+const KNOWN_VERBS: &str = "bcdeEfFgGoOpqstTUvxX";
+
+pub struct PrintfFormat;
+
+impl Lint for PrintfFormat {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "printf-format",
+            default_severity: Severity::Warning,
+            description: "Reports literal format strings with unknown verbs or that do not match the number of \
+                          arguments given.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let root = cx.root.clone();
+        visit::walk_root(&mut PrintfChecker { cx }, &root);
+    }
+}
+
+struct PrintfChecker<'c, 'a> {
+    cx: &'c mut LintContext<'a>,
+}
+
+impl Visitor for PrintfChecker<'_, '_> {
+    fn enter_func_call(&mut self, call: &ast::FuncCall) {
+        let Some(func_name) = call.func_name() else {
+            return;
+        };
+        let Some(&(_, format_index)) = FORMAT_FUNCS.iter().find(|(name, _)| *name == func_name.get()) else {
+            return;
+        };
+        let args: Vec<_> = call.args().collect();
+        let Some(ast::Expr::Literal(format_lit)) = args.get(format_index) else {
+            return;
+        };
+        let ast::LiteralKind::String(format_str) = format_lit.kind() else {
+            return;
+        };
+
+        let is_piped = receives_piped_value(call);
+        let format_args = &args[format_index + 1..];
+        let num_args = format_args.len() + usize::from(is_piped);
+
+        let format = format_str.get();
+        let result = check_format(&format, num_args);
+        for (range, message) in result.problems {
+            self.cx.report(message, format_str.source_range(range));
+        }
+        if let Some(used) = result.unused_args_after {
+            // The piped argument comes last, so if it is the only extra
+            // argument, point at the function instead.
+            let extra = format_args.get(used..).unwrap_or_default();
+            let range = match (extra.first(), extra.last()) {
+                (Some(first), Some(last)) => first.text_range().cover(last.text_range()),
+                _ => func_name.text_range(),
+            };
+            self.cx.report(
+                format!(
+                    "extra arguments to {}: format string uses {}, but {num_args} were given",
+                    func_name.get(),
+                    pluralize(used, "argument")
+                ),
+                range,
+            );
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct FormatCheck {
+    /// Problems with individual verbs, with ranges relative to the format
+    /// string.
+    problems: Vec<(TextRange, String)>,
+    /// If some arguments are not used by the format string, the number of
+    /// arguments that are.
+    unused_args_after: Option<usize>,
+}
+
+/// Check `format` against `num_args` arguments, following the rules of Go's
+/// `fmt.Sprintf`.
+fn check_format(format: &str, num_args: usize) -> FormatCheck {
+    let mut check = FormatCheck::default();
+    let mut p = FormatParser {
+        format,
+        pos: 0,
+        num_args,
+        arg_num: 0,
+        reordered: false,
+    };
+
+    while let Some(offset) = p.format[p.pos..].find('%') {
+        let start = p.pos + offset;
+        p.pos = start + 1;
+        let directive = p.parse_directive();
+        let range = TextRange::new(TextSize::from(start as u32), TextSize::from(p.pos as u32));
+        let text = &format[start..p.pos];
+        match directive {
+            Directive::Literal | Directive::Verb => {}
+            Directive::UnknownVerb => {
+                check.problems.push((range, format!("unknown verb {text}")));
+            }
+            Directive::MissingVerb => {
+                check.problems.push((range, format!("missing verb in {text}")));
+            }
+            Directive::BadIndex => {
+                check
+                    .problems
+                    .push((range, format!("invalid argument index in {text}")));
+            }
+            Directive::MissingArg => {
+                check.problems.push((range, format!("too few arguments for {text}")));
+            }
+        }
+    }
+
+    if !p.reordered && p.arg_num < num_args {
+        check.unused_args_after = Some(p.arg_num);
+    }
+    check
+}
+
+enum Directive {
+    /// `%%`, producing a literal percent sign.
+    Literal,
+    Verb,
+    UnknownVerb,
+    MissingVerb,
+    BadIndex,
+    MissingArg,
+}
+
+struct FormatParser<'s> {
+    format: &'s str,
+    pos: usize,
+    num_args: usize,
+    /// The index of the next argument to be used.
+    arg_num: usize,
+    /// Whether explicit argument indices have been used.
+    reordered: bool,
+}
+
+impl FormatParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.format[self.pos..].chars().next()
+    }
+
+    fn eat_if(&mut self, c: char) -> bool {
+        let matches = self.peek() == Some(c);
+        if matches {
+            self.pos += c.len_utf8();
+        }
+        matches
+    }
+
+    fn eat_digits(&mut self) -> bool {
+        let len = self.format[self.pos..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.format.len() - self.pos);
+        self.pos += len;
+        len > 0
+    }
+
+    /// Parse the remainder of a directive, after the `%`. Mirrors `doPrintf`
+    /// in Go's `fmt/print.go`.
+    fn parse_directive(&mut self) -> Directive {
+        while self.peek().is_some_and(|c| matches!(c, '#' | '0' | '+' | '-' | ' ')) {
+            self.pos += 1;
+        }
+
+        let mut good_arg_num = true;
+        let mut after_index = self.parse_arg_index(&mut good_arg_num);
+
+        // Width.
+        if self.eat_if('*') {
+            // A missing width or precision argument is reported at the verb, which
+            // must then also be missing its argument.
+            self.use_arg();
+            after_index = false;
+        } else if self.eat_digits() && after_index {
+            // An index must directly precede the value it applies to.
+            good_arg_num = false;
+        }
+
+        // Precision. As in Go, a trailing `.` is treated as the verb.
+        if self.peek() == Some('.') && self.pos + 1 < self.format.len() {
+            self.pos += 1;
+            if after_index {
+                good_arg_num = false;
+            }
+            after_index = self.parse_arg_index(&mut good_arg_num);
+            if self.eat_if('*') {
+                self.use_arg();
+                after_index = false;
+            } else {
+                self.eat_digits();
+            }
+        }
+
+        if !after_index {
+            self.parse_arg_index(&mut good_arg_num);
+        }
+
+        let Some(verb) = self.peek() else {
+            return Directive::MissingVerb;
+        };
+        self.pos += verb.len_utf8();
+        if verb == '%' {
+            Directive::Literal
+        } else if !good_arg_num {
+            Directive::BadIndex
+        } else if !KNOWN_VERBS.contains(verb) {
+            // Unknown verbs still consume an argument.
+            self.use_arg();
+            Directive::UnknownVerb
+        } else if !self.use_arg() {
+            Directive::MissingArg
+        } else {
+            Directive::Verb
+        }
+    }
+
+    /// Consume the next argument, reporting whether there was one.
+    fn use_arg(&mut self) -> bool {
+        if self.arg_num < self.num_args {
+            self.arg_num += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parse an explicit argument index such as `[2]`, if present, returning
+    /// whether one was found. Invalid indices clear `good_arg_num`.
+    fn parse_arg_index(&mut self, good_arg_num: &mut bool) -> bool {
+        if self.peek() != Some('[') {
+            return false;
+        }
+        self.reordered = true;
+        let rest = &self.format[self.pos..];
+        let index = rest.find(']').and_then(|close| {
+            self.pos += close + 1;
+            let digits = &rest[1..close];
+            digits
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+                .then(|| digits.parse::<usize>().ok())?
+        });
+        match index {
+            Some(index) if index >= 1 && index <= self.num_args => {
+                self.arg_num = index - 1;
+                true
+            }
+            Some(_) => {
+                *good_arg_num = false;
+                true
+            }
+            None => {
+                if !rest.contains(']') {
+                    self.pos += 1;
+                }
+                *good_arg_num = false;
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    const ENVDEFS: &str = "func printf(format string, args...) string\n";
+
+    fn problems(source: &str) -> Vec<(String, &str)> {
+        test_util::messages(ENVDEFS, source, "printf-format")
+    }
+
+    #[test]
+    fn well_formed_formats_are_accepted() {
+        assert!(problems(r#"{{printf "%d%% of %s" 1 "x"}}"#).is_empty());
+        assert!(problems(r#"{{printf "%-*.*f" 10 2 1.5}}"#).is_empty());
+        assert!(problems(r#"{{printf "%[2]s %[1]s" "a" "b"}}"#).is_empty());
+        assert!(problems(r#"{{printf "%[1]s %[1]q" "a"}}"#).is_empty());
+        assert!(problems(r#"{{"a" | printf "%s"}}"#).is_empty());
+        assert!(problems(r#"{{printf .Format 1 2}}"#).is_empty());
+    }
+
+    #[test]
+    fn too_few_arguments_are_reported_at_the_verb() {
+        assert_eq!(
+            problems(r#"{{printf "%s and %5.2f" "x"}}"#),
+            [("too few arguments for %5.2f".to_owned(), "%5.2f")]
+        );
+        assert_eq!(
+            problems(r#"{{printf "%*d" 5}}"#),
+            [("too few arguments for %*d".to_owned(), "%*d")]
+        );
+        assert_eq!(
+            problems(r#"{{printf "%*d"}}"#),
+            [("too few arguments for %*d".to_owned(), "%*d")]
+        );
+    }
+
+    #[test]
+    fn extra_arguments_are_reported() {
+        assert_eq!(
+            problems(r#"{{printf "%s" "a" "b" 3}}"#),
+            [(
+                "extra arguments to printf: format string uses 1 argument, but 3 were given".to_owned(),
+                r#""b" 3"#
+            )]
+        );
+        assert_eq!(
+            problems(r#"{{2 | printf "%d" 1}}"#),
+            [(
+                "extra arguments to printf: format string uses 1 argument, but 2 were given".to_owned(),
+                "printf"
+            )]
+        );
+    }
+
+    #[test]
+    fn unknown_verbs_and_bad_indices_are_reported() {
+        assert_eq!(
+            problems(r#"{{printf "%z %[3]d %" 1 2}}"#),
+            [
+                ("unknown verb %z".to_owned(), "%z"),
+                ("invalid argument index in %[3]d".to_owned(), "%[3]d"),
+                ("missing verb in %".to_owned(), "%"),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranges_account_for_escape_sequences() {
+        assert_eq!(
+            problems(r#"{{printf "\té %y"}}"#),
+            [("unknown verb %y".to_owned(), "%y")]
+        );
+        assert_eq!(
+            problems(r#"{{printf "\x25q"}}"#),
+            [("too few arguments for %q".to_owned(), r"\x25q")]
+        );
+        assert_eq!(problems("{{printf `%w`}}"), [("unknown verb %w".to_owned(), "%w")]);
+    }
+}
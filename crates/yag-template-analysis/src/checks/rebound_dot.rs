@@ -0,0 +1,152 @@
+use rowan::{NodeOrToken, TextRange};
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::scope::DotContext;
+use crate::types::{Type, TypeInfo};
+use crate::{Fix, Severity, TextEdit};
+
+pub struct ReboundDot;
+
+impl Lint for ReboundDot {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "rebound-dot",
+            default_severity: Severity::Warning,
+            description: "Reports accesses to fields of the custom command's context data, such as `.User`, where \
+                          `.` has been rebound by `with`, `range`, or `catch` to a value known to have no fields.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let chains: Vec<_> = cx
+            .root
+            .syntax()
+            .descendants()
+            .filter_map(ast::ContextFieldChain::cast)
+            .collect();
+        for chain in chains {
+            let Some(field) = chain.fields().next() else {
+                continue;
+            };
+            let Some(name) = field.name().filter(|name| cx.env.fields.contains_key(*name)) else {
+                continue;
+            };
+            // Only report accesses where `.` is known to have no such field;
+            // dicts and values of unknown type may well have one.
+            let what = match cx.scope_info.dot_at(chain.syntax().text_range().start()) {
+                DotContext::Root | DotContext::TemplateData => continue,
+                DotContext::WithValue(range) if lacks_fields(expr_type(cx, range, TypeInfo::type_of)) => {
+                    "the value of the enclosing `with`"
+                }
+                DotContext::RangeElement(range) if lacks_fields(expr_type(cx, range, TypeInfo::elem_type_of)) => {
+                    "the current element of the enclosing `range`"
+                }
+                DotContext::WithValue(_) | DotContext::RangeElement(_) => continue,
+                DotContext::CaughtError => "the error caught by the enclosing `catch`",
+            };
+            let message = format!("`.` refers to {what} here; did you mean `$.{name}`?");
+            let start = chain.syntax().text_range().start();
+            let fix = Fix::new(
+                format!("Change to `$.{name}`"),
+                vec![TextEdit::replace(TextRange::empty(start), "$")],
+            );
+            cx.report_with_fix(message, field.text_range(), fix);
+        }
+    }
+}
+
+/// The type of the expression at `range`, as given by `type_of`.
+fn expr_type(cx: &LintContext<'_>, range: TextRange, type_of: fn(&TypeInfo, &ast::Expr) -> Type) -> Type {
+    let node = match cx.root.syntax().covering_element(range) {
+        NodeOrToken::Node(node) => Some(node),
+        NodeOrToken::Token(token) => token.parent(),
+    };
+    // Several nested expressions may share the range; the outermost one gives
+    // the value.
+    node.into_iter()
+        .flat_map(|node| node.ancestors())
+        .take_while(|node| node.text_range() == range)
+        .filter_map(ast::Expr::cast)
+        .last()
+        .map_or(Type::Any, |expr| type_of(cx.types, &expr))
+}
+
+/// Report whether values of type `ty` are known to have no fields.
+fn lacks_fields(ty: Type) -> bool {
+    matches!(
+        ty,
+        Type::Nil | Type::Bool | Type::Int | Type::Float | Type::String | Type::Slice
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    const ENVDEFS: &str = "func sdict(values...) sdict\nfunc getMember(member)\nfunc dbTopEntries(pattern, amount, skip)\n\
+                           field Args []string\nfield StrippedMsg string\nfield User\nfield Channel\n\
+                           field Member\nfield ExecData\nfield Interaction\nfield Guild\n";
+
+    fn problems(source: &str) -> Vec<(String, &str)> {
+        test_util::messages(ENVDEFS, source, "rebound-dot")
+    }
+
+    #[test]
+    fn root_fields_on_rebound_dot_are_reported() {
+        assert_eq!(
+            problems(
+                "{{range .Args}}{{.User.Name}}{{else}}{{.User}}{{end}}{{try}}{{.Channel}}{{catch}}{{.Channel}}{{end}}"
+            ),
+            [
+                (
+                    "`.` refers to the current element of the enclosing `range` here; did you mean `$.User`?"
+                        .to_owned(),
+                    ".User"
+                ),
+                (
+                    "`.` refers to the error caught by the enclosing `catch` here; did you mean `$.Channel`?"
+                        .to_owned(),
+                    ".Channel"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn values_known_to_lack_fields_are_reported() {
+        assert_eq!(
+            problems(
+                "{{with .StrippedMsg}}{{.User}}{{end}}{{with $n := 5}}{{.Channel}}{{end}}{{range 3}}{{.User}}{{end}}"
+            )
+            .len(),
+            3
+        );
+        assert_eq!(
+            problems("{{$args := .CmdArgs}}{{range $args}}{{.User}}{{end}}").len(),
+            0
+        );
+    }
+
+    #[test]
+    fn values_that_may_have_the_field_are_accepted() {
+        assert!(problems("{{with .Member}}{{.User}}{{end}}{{with getMember 1}}{{.User.ID}}{{end}}").is_empty());
+        assert!(problems(r#"{{define "t"}}{{.User}}{{end}}{{with .Foo}}{{.Bar}}{{end}}"#).is_empty());
+        assert!(problems(r#"{{$entries := dbTopEntries "xp" 10 0}}{{range $entries}}{{.User}}{{end}}"#).is_empty());
+        assert!(problems("{{with .ExecData}}{{.User}}{{end}}{{with .Guild}}{{.User}}{{end}}").is_empty());
+        assert!(problems("{{$sdict := sdict}}{{with $sdict}}{{.User}}{{end}}").is_empty());
+        assert!(problems("{{with .Interaction}}{{.Member}}{{end}}").is_empty());
+    }
+
+    #[test]
+    fn only_fields_of_the_context_data_are_reported() {
+        assert_eq!(
+            problems("{{with 5}}{{.Interaction}}{{.Foo}}{{end}}"),
+            [(
+                "`.` refers to the value of the enclosing `with` here; did you mean `$.Interaction`?".to_owned(),
+                ".Interaction"
+            )]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+
+use foldhash::{HashMap, HashMapExt};
+use smol_str::SmolStr;
+
+use crate::Severity;
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::templates::{TemplateTable, TemplateUse};
+
+pub struct UnconditionalRecursion;
+
+impl Lint for UnconditionalRecursion {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "unconditional-recursion",
+            default_severity: Severity::Error,
+            description: "Reports associated templates that always invoke themselves, directly or through other \
+                          templates, and so recurse until the nesting limit is reached.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let graph = CallGraph::new(cx.templates);
+        for template_use in cx.templates.uses() {
+            if let Some(cycle) = graph.cycle_through(template_use, true) {
+                let message = format!(
+                    "template {:?} always invokes itself: {}",
+                    cycle[0],
+                    format_cycle(&cycle)
+                );
+                cx.report(message, template_use.name_range);
+            }
+        }
+    }
+}
+
+pub struct RecursiveTemplate;
+
+impl Lint for RecursiveTemplate {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "recursive-template",
+            default_severity: Severity::Warning,
+            description: "Reports associated templates that may invoke themselves, directly or through other \
+                          templates, under some condition.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let graph = CallGraph::new(cx.templates);
+        for template_use in cx.templates.uses() {
+            // Unconditional recursion is reported separately.
+            if graph.cycle_through(template_use, true).is_some() {
+                continue;
+            }
+            if let Some(cycle) = graph.cycle_through(template_use, false) {
+                let message = format!("template {:?} may invoke itself: {}", cycle[0], format_cycle(&cycle));
+                cx.report(message, template_use.name_range);
+            }
+        }
+    }
+}
+
+fn format_cycle(cycle: &[&str]) -> String {
+    cycle
+        .iter()
+        .map(|name| format!("{name:?}"))
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+/// The templates invoked by each associated template.
+struct CallGraph<'a> {
+    calls: HashMap<&'a str, Vec<&'a TemplateUse>>,
+}
+
+impl<'a> CallGraph<'a> {
+    fn new(templates: &'a TemplateTable) -> Self {
+        let mut calls: HashMap<&str, Vec<&TemplateUse>> = HashMap::new();
+        for template_use in templates.uses() {
+            if let Some(caller) = &template_use.caller
+                && templates.resolve(&template_use.name).is_some()
+            {
+                calls.entry(caller.as_str()).or_default().push(template_use);
+            }
+        }
+        Self { calls }
+    }
+
+    /// Find the shortest cycle of invocations starting with `first`, returning
+    /// the names of the templates involved, beginning and ending with the
+    /// template containing `first`. If `unconditional` is set, only consider
+    /// invocations that are always executed.
+    fn cycle_through(&self, first: &'a TemplateUse, unconditional: bool) -> Option<Vec<&'a str>> {
+        let start = first.caller.as_deref()?;
+        if unconditional && first.is_conditional {
+            return None;
+        }
+        let mut prev: HashMap<&str, &str> = HashMap::new();
+        let mut queue = VecDeque::from([first.name.as_str()]);
+        prev.insert(first.name.as_str(), start);
+        while let Some(name) = queue.pop_front() {
+            if name == start {
+                let mut cycle = vec![start];
+                let mut cur = prev[start];
+                while cur != start {
+                    cycle.push(cur);
+                    cur = prev[cur];
+                }
+                cycle.push(start);
+                cycle.reverse();
+                return Some(cycle);
+            }
+            for callee in self.calls.get(name).into_iter().flatten() {
+                if unconditional && callee.is_conditional {
+                    continue;
+                }
+                let callee_name: &SmolStr = &callee.name;
+                if !prev.contains_key(callee_name.as_str()) {
+                    prev.insert(callee_name, name);
+                    queue.push_back(callee_name);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    fn problems(source: &str) -> Vec<(&'static str, String, &str)> {
+        test_util::problems("", source, &["unconditional-recursion", "recursive-template"])
+    }
+
+    #[test]
+    fn unconditional_recursion_is_reported_at_each_invocation() {
+        assert_eq!(
+            problems(
+                r#"{{define "a"}}{{template "b"}}{{end}}{{define "b"}}{{execTemplate "a"}}{{end}}{{template "a"}}"#
+            ),
+            [
+                (
+                    "unconditional-recursion",
+                    r#"template "a" always invokes itself: "a" → "b" → "a""#.to_owned(),
+                    r#""b""#
+                ),
+                (
+                    "unconditional-recursion",
+                    r#"template "b" always invokes itself: "b" → "a" → "b""#.to_owned(),
+                    r#""a""#
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn guarded_recursion_is_a_warning() {
+        assert_eq!(
+            problems(r#"{{define "f"}}{{if .}}{{template "f" slice . 1}}{{end}}{{end}}"#),
+            [(
+                "recursive-template",
+                r#"template "f" may invoke itself: "f" → "f""#.to_owned(),
+                r#""f""#
+            )]
+        );
+        assert_eq!(
+            problems(
+                r#"{{define "fact"}}{{if le . 1}}{{return 1}}{{end}}{{return mult . (execTemplate "fact" (sub . 1))}}{{end}}"#
+            )[0]
+            .0,
+            "recursive-template"
+        );
+    }
+
+    #[test]
+    fn non_recursive_templates_are_accepted() {
+        assert!(problems(r#"{{define "a"}}{{template "b"}}{{end}}{{define "b"}}{{end}}{{template "a"}}"#).is_empty());
+        assert!(problems(r#"{{define "a"}}{{template "missing"}}{{end}}"#).is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+use yag_template_syntax::ast::{self, AstNode};
+
+use crate::checks::pluralize;
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::{Severity, regex};
+
+pub struct InvalidRegex;
+
+impl Lint for InvalidRegex {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "invalid-regex",
+            default_severity: Severity::Error,
+            description: "Reports literal patterns passed to `reFind` and similar functions that are not valid RE2 \
+                          syntax, and so fail at runtime.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let calls: Vec<_> = cx.root.syntax().descendants().filter_map(ast::FuncCall::cast).collect();
+        for call in calls {
+            let Some(pattern) = regex::regex_literal(&call) else {
+                continue;
+            };
+            if let Err(err) = regex::parse(&pattern.get()) {
+                cx.report(
+                    format!("invalid regular expression: {}", err.message),
+                    pattern.source_range(err.range),
+                );
+            }
+        }
+    }
+}
+
+pub struct UnknownCaptureGroup;
+
+impl Lint for UnknownCaptureGroup {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "unknown-capture-group",
+            default_severity: Severity::Warning,
+            description: "Reports references in `reReplace` replacements to capture groups that do not exist in the \
+                          pattern, which are replaced with the empty string.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let calls: Vec<_> = cx.root.syntax().descendants().filter_map(ast::FuncCall::cast).collect();
+        for call in calls {
+            let (Some(pattern), Some(replacement)) = (regex::regex_literal(&call), regex::replacement_literal(&call))
+            else {
+                continue;
+            };
+            let Ok(regex) = regex::parse(&pattern.get()) else {
+                continue;
+            };
+            for capture_ref in regex::capture_refs(&replacement.get()) {
+                let name = &capture_ref.name;
+                let message = match capture_ref.index() {
+                    Some(index) if index <= regex.capture_count() => continue,
+                    Some(index) => format!(
+                        "capture group {index} does not exist; the pattern has {}",
+                        pluralize(regex.capture_count(), "capture group")
+                    ),
+                    None if regex.has_capture_named(name) => continue,
+                    None => {
+                        // `$1x` refers to a group named `1x`, not to group 1.
+                        let digits = name.len() - name.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                        if digits > 0 && !capture_ref.braced {
+                            let (index, rest) = name.split_at(digits);
+                            format!(
+                                "`${name}` refers to a capture group named `{name}`; write `${{{index}}}{rest}` to \
+                                 refer to capture group {index}"
+                            )
+                        } else {
+                            format!("no capture group is named `{name}`")
+                        }
+                    }
+                };
+                cx.report(message, replacement.source_range(capture_ref.range));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    const ENVDEFS: &str = "func reFind(regex, text)\nfunc reReplace(regex, text, replacement)\n";
+
+    fn problems(source: &str) -> Vec<(&'static str, String, &str)> {
+        test_util::problems(ENVDEFS, source, &["invalid-regex", "unknown-capture-group"])
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported_inside_the_literal() {
+        assert_eq!(
+            problems(r#"{{reFind "\\d+(" "a"}}{{reFind `[z-a]` "b"}}{{reFind "\t\\p{Nope}" "c"}}{{reFind $x "d"}}"#),
+            [
+                (
+                    "invalid-regex",
+                    "invalid regular expression: missing closing `)`".to_owned(),
+                    "("
+                ),
+                (
+                    "invalid-regex",
+                    "invalid regular expression: invalid character class range `z-a`".to_owned(),
+                    "z-a"
+                ),
+                (
+                    "invalid-regex",
+                    "invalid regular expression: unknown Unicode class `Nope`".to_owned(),
+                    r"\\p{Nope}"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_capture_groups_are_reported() {
+        assert_eq!(
+            problems(r#"{{reReplace `(\w+) (?P<last>\w+)` "a b" "$2 ${last} $0 $$3 $3 ${first} $1st"}}"#),
+            [
+                (
+                    "unknown-capture-group",
+                    "capture group 3 does not exist; the pattern has 2 capture groups".to_owned(),
+                    "$3"
+                ),
+                (
+                    "unknown-capture-group",
+                    "no capture group is named `first`".to_owned(),
+                    "${first}"
+                ),
+                (
+                    "unknown-capture-group",
+                    "`$1st` refers to a capture group named `1st`; write `${1}st` to refer to capture group 1"
+                        .to_owned(),
+                    "$1st"
+                ),
+            ]
+        );
+        assert_eq!(
+            problems(r#"{{reReplace `a(b` "ab" "$5"}}{{"ab" | reReplace `a` "$1"}}"#).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use foldhash::{HashMap, HashMapExt};
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+use yag_template_syntax::{SyntaxKind, SyntaxNode};
+
+use crate::Severity;
+use crate::control_flow::{NodeId, NodeKind};
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::templates::TemplateUseKind;
+
+pub struct MissingReturn;
+
+impl Lint for MissingReturn {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "missing-return",
+            default_severity: Severity::Warning,
+            description: "Reports templates whose result is used via `execTemplate`, but that may finish without \
+                          reaching a `return` action, in which case the result is nil.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let calls: Vec<_> = cx.root.syntax().descendants().filter_map(ast::FuncCall::cast).collect();
+        // Whether each template, by the range of its definition, always returns.
+        let mut always_returns = HashMap::new();
+        for call in calls {
+            if call.func_name().is_none_or(|name| name.get() != "execTemplate") || !is_value_used(call.syntax()) {
+                continue;
+            }
+            let Some(ast::Expr::Literal(lit)) = call.args().next() else {
+                continue;
+            };
+            let ast::LiteralKind::String(name) = lit.kind() else {
+                continue;
+            };
+            let Some(def) = cx.templates.resolve(&name.get()) else {
+                continue;
+            };
+            let returns = *always_returns.entry(def.range).or_insert_with(|| {
+                cx.control_flow
+                    .template_exit(def.range)
+                    .is_none_or(|exit| returns_on_every_path(cx, exit))
+            });
+            if !returns {
+                let message = format!(
+                    "template {:?} may finish without returning a value, in which case execTemplate returns nil",
+                    def.name
+                );
+                cx.report(message, name.text_range());
+            }
+        }
+    }
+}
+
+/// Report whether the result of `call` is used, rather than simply written
+/// to the output.
+fn is_value_used(call: &SyntaxNode) -> bool {
+    call.parent()
+        .is_none_or(|parent| parent.kind() != SyntaxKind::ExprAction)
+}
+
+/// Report whether every path to the template exit `exit` passes through a
+/// `return` action.
+fn returns_on_every_path(cx: &LintContext<'_>, exit: NodeId) -> bool {
+    cx.control_flow
+        .node(exit)
+        .predecessors
+        .iter()
+        .filter(|&&pred| cx.control_flow.is_reachable(pred))
+        .all(|&pred| match &cx.control_flow.node(pred).kind {
+            NodeKind::Action(ptr) => ptr.kind() == SyntaxKind::ReturnAction,
+            _ => false,
+        })
+}
+
+pub struct DiscardedReturnValue;
+
+impl Lint for DiscardedReturnValue {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "discarded-return-value",
+            default_severity: Severity::Warning,
+            description: "Reports `return` actions with a value in templates that are only ever invoked with \
+                          `template` or `block`, which discard the value.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        if cx.templates.has_dynamic_uses() {
+            return;
+        }
+        let returns: Vec<_> = cx
+            .root
+            .syntax()
+            .descendants()
+            .filter_map(ast::ReturnAction::cast)
+            .collect();
+        for return_action in returns {
+            let Some(value) = return_action.expr() else {
+                continue;
+            };
+            let Some(Some(name)) = enclosing_template(return_action.syntax()) else {
+                continue;
+            };
+            let mut uses = cx.templates.uses_of(&name).peekable();
+            // Unused templates are reported separately.
+            if uses.peek().is_some() && uses.all(|template_use| template_use.kind != TemplateUseKind::Exec) {
+                let message =
+                    format!("the return value is discarded, since template {name:?} is only invoked for its output");
+                cx.report(message, value.text_range());
+            }
+        }
+    }
+}
+
+pub struct TopLevelReturn;
+
+impl Lint for TopLevelReturn {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "top-level-return",
+            default_severity: Severity::Info,
+            description: "Reports `return` actions outside associated templates, which end the execution of the \
+                          entire custom command.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let returns: Vec<_> = cx
+            .root
+            .syntax()
+            .descendants()
+            .filter_map(ast::ReturnAction::cast)
+            .collect();
+        for return_action in returns {
+            if enclosing_template(return_action.syntax()).is_some() {
+                continue;
+            }
+            let message = match return_action.expr() {
+                Some(_) => "return at the top level ends the entire custom command, discarding the value",
+                None => "return at the top level ends the entire custom command",
+            };
+            cx.report(message, return_action.text_range());
+        }
+    }
+}
+
+/// Find the associated template containing `node`, returning its name (or
+/// `None` if it is missing), or `None` if `node` is in the root template.
+fn enclosing_template(node: &SyntaxNode) -> Option<Option<String>> {
+    node.ancestors()
+        .find_map(|ancestor| match ast::Action::cast(ancestor)? {
+            ast::Action::TemplateDefinition(define) => Some(
+                define
+                    .clause()
+                    .and_then(|clause| clause.template_name())
+                    .map(|name| name.get().into_owned()),
+            ),
+            ast::Action::TemplateBlock(block) => Some(
+                block
+                    .clause()
+                    .and_then(|clause| clause.template_name())
+                    .map(|name| name.get().into_owned()),
+            ),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    fn problems(source: &str) -> Vec<(&'static str, String, &str)> {
+        test_util::problems(
+            "",
+            source,
+            &["missing-return", "discarded-return-value", "top-level-return"],
+        )
+    }
+
+    #[test]
+    fn missing_returns_are_reported() {
+        assert_eq!(
+            problems(r#"{{define "f"}}{{if .}}{{return 1}}{{end}}{{end}}{{$x := execTemplate "f" 1}}{{$x}}"#),
+            [(
+                "missing-return",
+                r#"template "f" may finish without returning a value, in which case execTemplate returns nil"#
+                    .to_owned(),
+                r#""f""#
+            )]
+        );
+        assert!(
+            problems(r#"{{define "f"}}{{if .}}{{return 1}}{{else}}{{return 2}}{{end}}{{end}}{{$x := execTemplate "f" 1}}{{$x}}"#)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn output_only_exec_is_accepted() {
+        assert!(problems(r#"{{define "f"}}hi{{end}}{{execTemplate "f"}}"#).is_empty());
+    }
+
+    #[test]
+    fn discarded_return_values_are_reported() {
+        assert_eq!(
+            problems(r#"{{define "f"}}{{return 1}}{{end}}{{template "f"}}"#),
+            [(
+                "discarded-return-value",
+                r#"the return value is discarded, since template "f" is only invoked for its output"#.to_owned(),
+                "1"
+            )]
+        );
+        assert!(
+            problems(r#"{{define "f"}}{{return 1}}{{end}}{{template "f"}}{{print (execTemplate "f")}}"#).is_empty()
+        );
+    }
+
+    #[test]
+    fn top_level_returns_are_reported() {
+        assert_eq!(
+            problems(r#"{{if not .}}{{return}}{{end}}{{define "f"}}{{return}}{{end}}{{execTemplate "f"}}"#),
+            [(
+                "top-level-return",
+                "return at the top level ends the entire custom command".to_owned(),
+                "{{return}}"
+            )]
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use rowan::TextRange;
+use yag_template_syntax::SyntaxKind;
+use yag_template_syntax::ast::{self, AstNode};
+
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::scope::{ScopeInfo, VarSymbol};
+use crate::{Fix, Severity, TextEdit};
+
+pub struct RedeclaredVariable;
+
+impl Lint for RedeclaredVariable {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "redeclared-variable",
+            default_severity: Severity::Warning,
+            description: "Reports variables declared with `:=` that are already declared in the same scope.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        report_shadowing(cx, Shadowing::Redeclaration, |v| {
+            format!("{} is already declared in this scope", v.name)
+        });
+    }
+}
+
+pub struct LostAssignment;
+
+impl Lint for LostAssignment {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "lost-assignment",
+            default_severity: Severity::Warning,
+            description: "Reports variables declared with `:=` in a nested body that shadow an outer variable \
+                          read after the body, which usually indicates that `=` was intended.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        report_shadowing(cx, Shadowing::LostAssignment, |v| {
+            format!(
+                "{} := declares a new variable, so the outer {} read after this block is not updated",
+                v.name, v.name
+            )
+        });
+    }
+}
+
+pub struct ShadowedVariable;
+
+impl Lint for ShadowedVariable {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "shadowed-variable",
+            default_severity: Severity::Info,
+            description: "Reports variable declarations that hide a variable of the same name in an outer scope.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        report_shadowing(cx, Shadowing::Shadow, |v| {
+            format!("declaration of {} shadows a variable in an outer scope", v.name)
+        });
+    }
+}
+
+/// How a variable declaration relates to the variable it hides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shadowing {
+    /// The hidden variable is declared in the same scope.
+    Redeclaration,
+    /// The hidden variable is declared in an outer scope and read after the
+    /// scope of the new declaration ends.
+    LostAssignment,
+    /// Any other declaration hiding a variable in an outer scope.
+    Shadow,
+}
+
+fn classify(scope_info: &ScopeInfo, v: &VarSymbol) -> Option<Shadowing> {
+    let outer = scope_info.var_symbol(v.shadows?)?;
+    // The predefined `$` is implicitly declared and cannot be assigned to.
+    outer.decl_range?;
+    if outer.scope == v.scope {
+        return Some(Shadowing::Redeclaration);
+    }
+    let scope_end = scope_info.scope(v.scope)?.range.end();
+    if scope_info
+        .find_uses(outer, false)
+        .any(|use_range| use_range.start() >= scope_end)
+    {
+        Some(Shadowing::LostAssignment)
+    } else {
+        Some(Shadowing::Shadow)
+    }
+}
+
+fn report_shadowing(cx: &mut LintContext<'_>, kind: Shadowing, message: impl Fn(&VarSymbol) -> String) {
+    let scope_info = cx.scope_info;
+    let mut problems: Vec<_> = scope_info
+        .var_symbols()
+        .filter(|v| classify(scope_info, v) == Some(kind))
+        .map(|v| (v, message(v)))
+        .collect();
+    problems.sort_by_key(|(v, _)| v.visible_from);
+    for (v, message) in problems {
+        let Some(name_range) = v.name_range else {
+            continue;
+        };
+        // Only a lost assignment clearly intends to update the outer variable;
+        // otherwise, changing the declaration to an assignment would change
+        // what the template does.
+        let op_range = v
+            .decl_range
+            .filter(|_| kind == Shadowing::LostAssignment)
+            .and_then(|decl_range| define_op_range(cx.root, decl_range));
+        match op_range {
+            Some(op_range) => {
+                let fix = Fix::new("Change `:=` to `=`", vec![TextEdit::replace(op_range, "=")]);
+                cx.report_with_fix(message, name_range, fix);
+            }
+            None => cx.report(message, name_range),
+        }
+    }
+}
+
+/// The range of the `:=` operator in the variable declaration at `decl_range`.
+/// Returns `None` for declarations other than `$x := ...`, such as those in
+/// `range` clauses.
+fn define_op_range(root: &ast::Root, decl_range: TextRange) -> Option<TextRange> {
+    let decl = root
+        .syntax()
+        .covering_element(decl_range)
+        .into_node()
+        .and_then(ast::VarDecl::cast)?;
+    decl.syntax()
+        .children_with_tokens()
+        .filter_map(|el| el.into_token())
+        .find(|token| token.kind() == SyntaxKind::ColonEq)
+        .map(|token| token.text_range())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    fn problems(source: &str) -> Vec<(&'static str, &str, Option<String>)> {
+        test_util::diagnostics(
+            "",
+            source,
+            &["redeclared-variable", "lost-assignment", "shadowed-variable"],
+        )
+        .into_iter()
+        .map(|diag| {
+            let fixed = diag.fix.map(|fix| {
+                let edit = &fix.edits[0];
+                let mut fixed = source.to_owned();
+                fixed.replace_range(std::ops::Range::<usize>::from(edit.range), &edit.new_text);
+                fixed
+            });
+            (diag.rule, &source[diag.range], fixed)
+        })
+        .collect()
+    }
+
+    #[test]
+    fn redeclaration_in_the_same_scope() {
+        assert_eq!(
+            problems("{{$x := 1}}{{$x := 2}}{{$x}}"),
+            [("redeclared-variable", "$x", None)]
+        );
+    }
+
+    #[test]
+    fn declaration_in_body_with_later_read() {
+        assert_eq!(
+            problems("{{$n := 0}}{{range .}}{{$n := add $n 1}}{{end}}{{$n}}"),
+            [(
+                "lost-assignment",
+                "$n",
+                Some("{{$n := 0}}{{range .}}{{$n = add $n 1}}{{end}}{{$n}}".to_owned())
+            )]
+        );
+    }
+
+    #[test]
+    fn shadowing_without_later_read_is_not_fixed() {
+        assert_eq!(
+            problems("{{$x := 0}}{{$x}}{{if .}}{{$x := 1}}{{$x}}{{end}}"),
+            [("shadowed-variable", "$x", None)]
+        );
+        assert_eq!(
+            problems("{{$i := 0}}{{range $i, $v := .}}{{$i}}{{$v}}{{end}}"),
+            [("shadowed-variable", "$i", None)]
+        );
+    }
+
+    #[test]
+    fn variables_in_separate_templates_do_not_shadow() {
+        assert!(problems(r#"{{$x := 1}}{{$x}}{{define "t"}}{{$x := 2}}{{$x}}{{end}}"#).is_empty());
+        assert!(problems("{{if .}}{{$x := 1}}{{$x}}{{else}}{{$x := 2}}{{$x}}{{end}}").is_empty());
+    }
+}
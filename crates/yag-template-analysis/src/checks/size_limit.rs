@@ -0,0 +1,80 @@
+use rowan::{TextRange, TextSize};
+use yag_template_syntax::ast::AstNode;
+
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::{Severity, size};
+
+/// The fraction of the limit past which a warning is given.
+const NEAR_LIMIT_RATIO: f64 = 0.9;
+
+pub struct SizeLimit;
+
+impl Lint for SizeLimit {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "size-limit",
+            default_severity: Severity::Warning,
+            description: "Reports templates that exceed or come within 10% of the custom command character limit.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let limit = cx.config.char_limit();
+        let total = size::measure(cx.root).total;
+        let src = cx.root.syntax().to_string();
+        if total > limit {
+            // Highlight the part of the template that does not fit.
+            let start = size::nth_char_offset(&src, limit).unwrap_or_default();
+            let message = format!(
+                "template is {total} characters long, exceeding the limit of {limit} by {}",
+                total - limit
+            );
+            cx.report(message, TextRange::new(start, TextSize::of(src.as_str())));
+        } else if total as f64 >= limit as f64 * NEAR_LIMIT_RATIO {
+            let message = format!(
+                "template is {total} characters long, within {} of the limit of {limit}",
+                limit - total
+            );
+            cx.report(message, TextRange::empty(TextSize::of(src.as_str())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+    use crate::lint::LintConfig;
+
+    fn problems(source: &str, limit: usize) -> Vec<(String, &str)> {
+        let mut config = LintConfig::new();
+        config.set_char_limit(limit);
+        test_util::diagnostics_with_config("", source, &["size-limit"], &config)
+            .into_iter()
+            .map(|diag| (diag.message, &source[diag.range]))
+            .collect()
+    }
+
+    #[test]
+    fn templates_over_the_limit_are_reported() {
+        assert_eq!(
+            problems("ééé{{.}}", 5),
+            [(
+                "template is 8 characters long, exceeding the limit of 5 by 3".to_owned(),
+                ".}}"
+            )]
+        );
+    }
+
+    #[test]
+    fn templates_near_the_limit_are_reported() {
+        assert!(problems("0123456789", 12).is_empty());
+        assert_eq!(
+            problems("0123456789", 11),
+            [(
+                "template is 10 characters long, within 1 of the limit of 11".to_owned(),
+                ""
+            )]
+        );
+    }
+}
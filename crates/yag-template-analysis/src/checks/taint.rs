@@ -0,0 +1,515 @@
+use foldhash::{HashMap, HashMapExt, HashSet};
+use rowan::{NodeOrToken, TextRange, TextSize};
+use yag_template_envdefs::{EnvDefs, Taint, Type};
+use yag_template_syntax::SyntaxNode;
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+
+use crate::checks::receives_piped_value;
+use crate::control_flow::{ControlFlowGraph, NodeId, VarEvent, VarEventKind};
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::scope::{DotContext, ScopeInfo, VarSymbolId};
+use crate::types::TypeInfo;
+use crate::{RelatedInfo, Severity};
+
+pub struct UnsanitizedUserInput;
+
+impl Lint for UnsanitizedUserInput {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "unsanitized-user-input",
+            default_severity: Severity::Warning,
+            description: "Reports user-controlled data, such as the content of the triggering message, that reaches a \
+                          function where it is a security risk without passing through a sanitizer: for instance, \
+                          passing it to `sendMessageNoEscape` lets any user ping everyone. Sources, sinks, and \
+                          sanitizers are declared in the environment definitions.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let events = cx.control_flow.var_events(cx.root, cx.scope_info);
+        let (writes, reaching) = reaching_writes(cx.control_flow, &events);
+        let mut tracker = TaintTracker {
+            env: cx.env,
+            root: cx.root,
+            scope_info: cx.scope_info,
+            types: cx.types,
+            write_taint: vec![None; writes.len()],
+            writes,
+            reaching,
+        };
+        tracker.propagate_to_writes();
+        for (sink, range, flow) in tracker.sink_paths() {
+            let mut related = vec![RelatedInfo::new(
+                flow.source_range,
+                "the user-controlled data originates here",
+            )];
+            related.extend(flow.steps);
+            cx.report_with_related(
+                format!("user-controlled data from {} reaches `{sink}`", flow.source),
+                range,
+                related,
+            );
+        }
+    }
+}
+
+/// User-controlled data held by a value.
+#[derive(Debug, Clone)]
+struct Flow {
+    /// A description of the source of the data, such as `` `.StrippedMsg` ``.
+    source: String,
+    source_range: TextRange,
+    /// The fields of the value holding the data, or empty if the value itself
+    /// does.
+    fields: Vec<String>,
+    /// The variables the data was stored in on its way from the source.
+    steps: Vec<RelatedInfo>,
+}
+
+impl Flow {
+    fn new(source: String, source_range: TextRange, fields: Vec<String>) -> Self {
+        Self {
+            source,
+            source_range,
+            fields,
+            steps: Vec::new(),
+        }
+    }
+
+    fn access_field(self, name: &str) -> Option<Flow> {
+        if self.fields.is_empty() {
+            Some(self)
+        } else if self.fields.iter().any(|field| field == name) {
+            Some(Flow {
+                fields: Vec::new(),
+                ..self
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// What `.` refers to, for the purposes of taint tracking.
+enum Dot {
+    /// The context data of the custom command, whose fields may be sources.
+    Root,
+    Value(Option<Flow>),
+}
+
+/// A declaration or assignment of a variable.
+struct Write {
+    sym: VarSymbolId,
+    var: ast::Var,
+    /// The expression giving the value written. Elements of a collection share
+    /// its taint, so the element variable of a `range` loop is given the
+    /// expression ranged over.
+    value: Option<ast::Expr>,
+}
+
+impl Write {
+    fn new(event: &VarEvent) -> Option<Self> {
+        let value = match &event.kind {
+            VarEventKind::Read => return None,
+            VarEventKind::Store(stmt) => match ast::VarDecl::cast(stmt.clone()) {
+                Some(decl) => decl.initializer(),
+                None => ast::VarAssign::cast(stmt.clone()).and_then(|assign| assign.assign_expr()),
+            },
+            VarEventKind::Iteration(clause) => clause
+                .iteration_vars()
+                .last()
+                .filter(|last| last.syntax() == event.var.syntax())
+                .and_then(|_| clause.expr()),
+        };
+        Some(Self {
+            sym: event.sym,
+            var: event.var.clone(),
+            value,
+        })
+    }
+}
+
+/// The writes to variables in the graph, along with the writes that may reach
+/// each read, keyed by the range of the variable read and indexing into the
+/// writes. A write reaches a read if some path leads from it to the read
+/// without passing through another write to the same variable.
+fn reaching_writes(graph: &ControlFlowGraph, events: &[Vec<VarEvent>]) -> (Vec<Write>, HashMap<TextRange, Vec<usize>>) {
+    let mut writes = Vec::new();
+    let write_ids: Vec<Vec<Option<usize>>> = events
+        .iter()
+        .map(|node_events| {
+            node_events
+                .iter()
+                .map(|event| {
+                    let write = Write::new(event)?;
+                    writes.push(write);
+                    Some(writes.len() - 1)
+                })
+                .collect()
+        })
+        .collect();
+
+    let transfer = |reaching: &mut HashSet<usize>, event: &VarEvent, id: Option<usize>| {
+        if let Some(id) = id {
+            reaching.retain(|&other| writes[other].sym != event.sym);
+            reaching.insert(id);
+        }
+    };
+    let reaching_in = |reaching_out: &[HashSet<usize>], id: NodeId| {
+        let mut reaching = HashSet::default();
+        for pred in &graph.node(id).predecessors {
+            reaching.extend(reaching_out[pred.index()].iter().copied());
+        }
+        reaching
+    };
+
+    let mut reaching_out = vec![HashSet::default(); events.len()];
+    let mut worklist: Vec<_> = graph.nodes().map(|(id, _)| id).collect();
+    while let Some(id) = worklist.pop() {
+        let mut reaching = reaching_in(&reaching_out, id);
+        for (event, &write) in events[id.index()].iter().zip(&write_ids[id.index()]) {
+            transfer(&mut reaching, event, write);
+        }
+        if reaching != reaching_out[id.index()] {
+            reaching_out[id.index()] = reaching;
+            worklist.extend(graph.node(id).successors.iter().copied());
+        }
+    }
+
+    let mut reads = HashMap::new();
+    for (id, _) in graph.nodes() {
+        let mut reaching = reaching_in(&reaching_out, id);
+        for (event, &write) in events[id.index()].iter().zip(&write_ids[id.index()]) {
+            if let VarEventKind::Read = event.kind {
+                let mut ids: Vec<_> = reaching
+                    .iter()
+                    .copied()
+                    .filter(|&write| writes[write].sym == event.sym)
+                    .collect();
+                ids.sort_unstable();
+                reads.insert(event.var.text_range(), ids);
+            }
+            transfer(&mut reaching, event, write);
+        }
+    }
+    (writes, reads)
+}
+
+struct TaintTracker<'a> {
+    env: &'a EnvDefs,
+    root: &'a ast::Root,
+    scope_info: &'a ScopeInfo,
+    types: &'a TypeInfo,
+    writes: Vec<Write>,
+    /// The writes that may reach each read, as found by [reaching_writes].
+    reaching: HashMap<TextRange, Vec<usize>>,
+    /// The user-controlled data held by the value of each write.
+    write_taint: Vec<Option<Flow>>,
+}
+
+impl TaintTracker<'_> {
+    /// Find the writes of user-controlled data to variables. Since writes in
+    /// loops may reach reads earlier in the loop, the writes are revisited
+    /// until no more are found.
+    fn propagate_to_writes(&mut self) {
+        loop {
+            let mut changed = false;
+            for id in 0..self.writes.len() {
+                if self.write_taint[id].is_some() {
+                    continue;
+                }
+                let write = &self.writes[id];
+                if let Some(mut flow) = write.value.as_ref().and_then(|value| self.taint_of(value)) {
+                    flow.steps.push(RelatedInfo::new(
+                        write.var.text_range(),
+                        format!("the data is stored in `{}` here", write.var.name()),
+                    ));
+                    self.write_taint[id] = Some(flow);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    /// The user-controlled data held by `var` where it is read. Reads outside
+    /// the control flow graph, as in unreachable code, may see any write.
+    fn var_taint(&self, var: ast::Var) -> Option<Flow> {
+        match self.reaching.get(&var.text_range()) {
+            Some(ids) => ids.iter().find_map(|&id| self.write_taint[id].clone()),
+            None => {
+                let sym = self.scope_info.resolve_var(var)?;
+                self.writes
+                    .iter()
+                    .zip(&self.write_taint)
+                    .filter(|(write, _)| write.sym == sym.id)
+                    .find_map(|(_, flow)| flow.clone())
+            }
+        }
+    }
+
+    /// The arguments of calls to sinks that hold user-controlled data, along
+    /// with the name of the sink.
+    fn sink_paths(&self) -> Vec<(String, TextRange, Flow)> {
+        let mut paths = Vec::new();
+        for call in self.root.syntax().descendants().filter_map(ast::FuncCall::cast) {
+            let Some(name) = call.func_name() else {
+                continue;
+            };
+            let Some(func) = self.env.funcs.get(name.get()) else {
+                continue;
+            };
+            let Some(Taint::Sink { params }) = &func.taint else {
+                continue;
+            };
+            let mut args: Vec<_> = call.args().map(|arg| (arg.text_range(), self.taint_of(&arg))).collect();
+            if receives_piped_value(&call) {
+                args.push((name.text_range(), self.piped_taint(&call)));
+            }
+            for (index, (range, flow)) in args.into_iter().enumerate() {
+                let Some(flow) = flow else {
+                    continue;
+                };
+                let param = func
+                    .params
+                    .get(index)
+                    .or_else(|| func.params.last().filter(|param| param.is_variadic));
+                if params.is_empty() || param.is_some_and(|param| params.contains(&param.name)) {
+                    paths.push((func.name.clone(), range, flow));
+                }
+            }
+        }
+        paths
+    }
+
+    fn taint_of(&self, expr: &ast::Expr) -> Option<Flow> {
+        self.value_taint(expr, None)
+    }
+
+    /// The user-controlled data held by the value of `expr`, which receives
+    /// `piped` as its final argument if it is a call in a pipeline.
+    fn value_taint(&self, expr: &ast::Expr, piped: Option<Flow>) -> Option<Flow> {
+        use ast::Expr::*;
+
+        // Numbers and the like cannot carry harmful text.
+        if matches!(
+            self.types.type_of(expr),
+            Type::Nil | Type::Bool | Type::Int | Type::Float | Type::Duration
+        ) {
+            return None;
+        }
+        match expr {
+            FuncCall(call) => self.call_taint(call, piped),
+            ExprCall(call) => call.callee().and_then(|callee| self.taint_of(&callee)),
+            Parenthesized(p) => p.inner_expr().and_then(|inner| self.taint_of(&inner)),
+            Pipeline(p) => self.pipeline_taint(p, None),
+            ContextAccess(access) => match self.dot_at(access.syntax().text_range().start()) {
+                Dot::Root => None,
+                Dot::Value(flow) => flow,
+            },
+            ContextFieldChain(chain) => {
+                let dot = self.dot_at(chain.syntax().text_range().start());
+                self.field_chain_taint(chain.syntax(), dot, chain.fields())
+            }
+            ExprFieldChain(chain) => {
+                let base = match chain.base_expr()? {
+                    VarAccess(access) if access.var().is_some_and(|var| self.scope_info.is_root_data_var(var)) => {
+                        Dot::Root
+                    }
+                    base => Dot::Value(self.taint_of(&base)),
+                };
+                self.field_chain_taint(chain.syntax(), base, chain.fields())
+            }
+            VarAccess(access) => self.var_taint(access.var()?),
+            VarDecl(decl) => decl.initializer().and_then(|init| self.taint_of(&init)),
+            VarAssign(assign) => assign.assign_expr().and_then(|value| self.taint_of(&value)),
+            Literal(_) => None,
+        }
+    }
+
+    fn call_taint(&self, call: &ast::FuncCall, piped: Option<Flow>) -> Option<Flow> {
+        let name = call.func_name()?;
+        match self.env.funcs.get(name.get()).and_then(|func| func.taint.as_ref()) {
+            Some(Taint::Sanitizer) => None,
+            Some(Taint::Source { fields }) => Some(Flow::new(
+                format!("`{}`", name.get()),
+                call.syntax().text_range(),
+                fields.clone(),
+            )),
+            // Otherwise, assume that the result is derived from the arguments.
+            _ => call.args().find_map(|arg| self.taint_of(&arg)).or(piped),
+        }
+    }
+
+    /// The user-controlled data held by the result of `pipeline`, or by the
+    /// value piped into `until` if given.
+    fn pipeline_taint(&self, pipeline: &ast::Pipeline, until: Option<&ast::PipelineStage>) -> Option<Flow> {
+        let mut flow = pipeline.init_expr().and_then(|init| self.taint_of(&init));
+        for stage in pipeline.stages() {
+            if until.is_some_and(|until| until.syntax() == stage.syntax()) {
+                break;
+            }
+            flow = stage.call_expr().and_then(|call| self.value_taint(&call, flow));
+        }
+        flow
+    }
+
+    fn piped_taint(&self, call: &ast::FuncCall) -> Option<Flow> {
+        let stage = call.syntax().parent().and_then(ast::PipelineStage::cast)?;
+        let pipeline = stage.syntax().parent().and_then(ast::Pipeline::cast)?;
+        self.pipeline_taint(&pipeline, Some(&stage))
+    }
+
+    fn field_chain_taint(
+        &self,
+        chain: &SyntaxNode,
+        base: Dot,
+        mut fields: impl Iterator<Item = ast::Field>,
+    ) -> Option<Flow> {
+        let mut flow = match base {
+            Dot::Root => {
+                let name = fields.next()?.name()?.to_owned();
+                let Some(Taint::Source { fields }) = &self.env.fields.get(&name)?.taint else {
+                    return None;
+                };
+                Flow::new(format!("`{}`", chain.text()), chain.text_range(), fields.clone())
+            }
+            Dot::Value(flow) => flow?,
+        };
+        for field in fields {
+            flow = flow.access_field(field.name()?)?;
+        }
+        Some(flow)
+    }
+
+    fn dot_at(&self, offset: TextSize) -> Dot {
+        match self.scope_info.dot_at(offset) {
+            DotContext::Root => Dot::Root,
+            DotContext::WithValue(range) | DotContext::RangeElement(range) => {
+                Dot::Value(self.expr_at(range).and_then(|expr| self.taint_of(&expr)))
+            }
+            DotContext::TemplateData | DotContext::CaughtError => Dot::Value(None),
+        }
+    }
+
+    fn expr_at(&self, range: TextRange) -> Option<ast::Expr> {
+        let node = match self.root.syntax().covering_element(range) {
+            NodeOrToken::Node(node) => Some(node),
+            NodeOrToken::Token(token) => token.parent(),
+        };
+        node?.ancestors().find_map(ast::Expr::cast)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    const ENVDEFS: &str = "func sendMessageNoEscape(channel, message)\ntaint sink message\nfunc execAdmin(command, args...)\n\
+                          taint sink\nfunc parseArgs(numRequired, errorMsg, argDefs...)\ntaint source\n\
+                          func carg(type, name)\nfunc getMessage(channel, messageID)\ntaint source Content\n\
+                          func sanitizeText(s string) string\ntaint sanitizer\nfunc lower(s string) string\n\
+                          func len(item) int\nfunc print(args...) string\nfield StrippedMsg\ntaint source\nfield Message\n\
+                          taint source Content\nfield User\n";
+
+    /// A diagnostic message, the source it covers, and its related locations.
+    type Problem<'a> = (String, &'a str, Vec<(&'a str, String)>);
+
+    fn problems(source: &str) -> Vec<Problem<'_>> {
+        test_util::diagnostics(ENVDEFS, source, &["unsanitized-user-input"])
+            .into_iter()
+            .map(|diag| {
+                let related = diag
+                    .related
+                    .into_iter()
+                    .map(|info| (&source[info.range], info.message))
+                    .collect();
+                (diag.message, &source[diag.range], related)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sources_reaching_sinks_are_reported() {
+        assert_eq!(
+            problems("{{$msg := lower .StrippedMsg}}{{$x := $msg}}{{sendMessageNoEscape nil $x}}"),
+            [(
+                "user-controlled data from `.StrippedMsg` reaches `sendMessageNoEscape`".to_owned(),
+                "$x",
+                vec![
+                    (".StrippedMsg", "the user-controlled data originates here".to_owned()),
+                    ("$msg", "the data is stored in `$msg` here".to_owned()),
+                    ("$x", "the data is stored in `$x` here".to_owned()),
+                ]
+            )]
+        );
+        let messages: Vec<_> = problems(
+            "{{$args := parseArgs 1 \"\" (carg \"string\" \"cmd\")}}{{execAdmin \"warn\" ($args.Get 0)}}\
+             {{.Message.Content | print | sendMessageNoEscape nil}}\
+             {{with getMessage nil 1}}{{sendMessageNoEscape nil .Content}}{{end}}",
+        )
+        .into_iter()
+        .map(|(message, range, _)| (message, range))
+        .collect();
+        assert_eq!(
+            messages,
+            [
+                (
+                    "user-controlled data from `parseArgs` reaches `execAdmin`".to_owned(),
+                    "($args.Get 0)"
+                ),
+                (
+                    "user-controlled data from `.Message.Content` reaches `sendMessageNoEscape`".to_owned(),
+                    "sendMessageNoEscape"
+                ),
+                (
+                    "user-controlled data from `getMessage` reaches `sendMessageNoEscape`".to_owned(),
+                    ".Content"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn sanitized_and_unrelated_data_is_not_reported() {
+        assert!(problems("{{sendMessageNoEscape nil (sanitizeText .StrippedMsg)}}").is_empty());
+        assert!(problems("{{sendMessageNoEscape nil (print \"count: \" (len .StrippedMsg))}}").is_empty());
+        assert!(
+            problems("{{sendMessageNoEscape nil .Message.ID}}{{sendMessageNoEscape .StrippedMsg \"hi\"}}").is_empty()
+        );
+        assert!(
+            problems("{{sendMessageNoEscape nil .User}}{{with .User}}{{sendMessageNoEscape nil .}}{{end}}").is_empty()
+        );
+    }
+
+    #[test]
+    fn dollar_in_associated_templates_is_not_the_context_data() {
+        assert!(problems(r#"{{define "t"}}{{sendMessageNoEscape nil $.StrippedMsg}}{{end}}"#).is_empty());
+        assert_eq!(
+            problems(r#"{{define "t"}}{{end}}{{sendMessageNoEscape nil $.StrippedMsg}}"#).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn sanitizing_assignments_clear_the_taint_of_later_uses() {
+        assert!(problems("{{$m := .StrippedMsg}}{{$m = sanitizeText $m}}{{sendMessageNoEscape nil $m}}").is_empty());
+        assert_eq!(
+            problems("{{$m := .StrippedMsg}}{{sendMessageNoEscape nil $m}}{{$m = sanitizeText $m}}").len(),
+            1
+        );
+        assert_eq!(
+            problems("{{$m := .StrippedMsg}}{{if .User}}{{$m = sanitizeText $m}}{{end}}{{sendMessageNoEscape nil $m}}")
+                .len(),
+            1
+        );
+        // The data stored on one iteration reaches the send on the next.
+        assert_eq!(
+            problems("{{$m := \"\"}}{{range 3}}{{sendMessageNoEscape nil $m}}{{$m = $.StrippedMsg}}{{end}}").len(),
+            1
+        );
+    }
+}
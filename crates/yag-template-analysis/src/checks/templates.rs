@@ -0,0 +1,84 @@
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::templates::TemplateDefKind;
+use crate::{DiagnosticTag, Severity};
+
+pub struct DuplicateTemplate;
+
+impl Lint for DuplicateTemplate {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "duplicate-template",
+            default_severity: Severity::Error,
+            description: "Reports templates that are defined more than once; only the last definition takes effect.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let templates = cx.templates;
+        for (i, def) in templates.defs().iter().enumerate() {
+            if templates.defs()[..i].iter().any(|prev| prev.name == def.name) {
+                cx.report(format!("template {:?} is already defined", def.name), def.name_range);
+            }
+        }
+    }
+}
+
+pub struct UnusedTemplate;
+
+impl Lint for UnusedTemplate {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "unused-template",
+            default_severity: Severity::Warning,
+            description: "Reports templates that are defined but never invoked.",
+            tags: &[DiagnosticTag::Unnecessary],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let templates = cx.templates;
+        if templates.has_dynamic_uses() {
+            return;
+        }
+        for def in templates.defs() {
+            if def.kind == TemplateDefKind::Define && templates.uses_of(&def.name).next().is_none() {
+                cx.report(format!("template {:?} is never used", def.name), def.name_range);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    fn problems(source: &str) -> Vec<(&'static str, String, &str)> {
+        test_util::problems("", source, &["duplicate-template", "unused-template"])
+    }
+
+    #[test]
+    fn duplicate_and_unused_definitions_are_reported() {
+        assert_eq!(
+            problems(r#"{{define "a"}}{{end}}{{define "a"}}{{end}}{{define "b"}}{{end}}{{template "a"}}"#),
+            [
+                (
+                    "duplicate-template",
+                    "template \"a\" is already defined".to_owned(),
+                    r#""a""#
+                ),
+                ("unused-template", "template \"b\" is never used".to_owned(), r#""b""#),
+            ]
+        );
+    }
+
+    #[test]
+    fn blocks_and_exec_template_count_as_uses() {
+        assert!(problems(r#"{{define "a"}}{{end}}{{block "b" .}}{{end}}{{execTemplate "a"}}"#).is_empty());
+    }
+
+    #[test]
+    fn unused_definitions_are_not_reported_with_dynamic_uses() {
+        assert!(problems(r#"{{define "a"}}{{end}}{{execTemplate $name}}"#).is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use yag_template_envdefs::TriggerKind;
+use yag_template_syntax::SyntaxNode;
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::scope::{DotContext, ScopeInfo};
+use crate::{Severity, triggers};
+
+pub struct InvalidForTrigger;
+
+impl Lint for InvalidForTrigger {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "invalid-for-trigger",
+            default_severity: Severity::Warning,
+            description: "Reports uses of functions and context fields that are unavailable for the kinds of trigger \
+                          the custom command runs for.",
+            tags: &[],
+        }
+    }
+
+    /// Check the functions and context fields used against the kinds of
+    /// trigger the template runs for: those declared by a directive if there
+    /// is one, and the configured fallback otherwise.
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let (env, root, scope_info, config) = (cx.env, cx.root, cx.scope_info, cx.config);
+        let directive = triggers::directive(root);
+        let kinds = match &directive {
+            Some(directive) => {
+                for (name, range) in &directive.unknown {
+                    let expected: Vec<_> = TriggerKind::ALL.iter().map(|kind| kind.name()).collect();
+                    cx.report(
+                        format!("unknown trigger kind {name:?}; expected one of {}", expected.join(", ")),
+                        *range,
+                    );
+                }
+                &directive.kinds[..]
+            }
+            None => config.fallback_triggers(),
+        };
+        if kinds.is_empty() {
+            return;
+        }
+
+        for node in root.syntax().descendants() {
+            let (what, allowed, range) = if let Some(call) = ast::FuncCall::cast(node.clone()) {
+                let Some(name) = call.func_name() else {
+                    continue;
+                };
+                let Some(allowed) = env.funcs.get(name.get()).and_then(|func| func.triggers.as_ref()) else {
+                    continue;
+                };
+                (format!("`{}`", name.get()), allowed, name.text_range())
+            } else if let Some(field) = root_field(scope_info, &node) {
+                let Some(allowed) = field.name().and_then(|name| env.fields.get(name)?.triggers.as_ref()) else {
+                    continue;
+                };
+                (format!("`.{}`", field.name().unwrap()), allowed, field.text_range())
+            } else {
+                continue;
+            };
+            if allowed.iter().any(|kind| kinds.contains(kind)) {
+                continue;
+            }
+            cx.report(
+                format!(
+                    "{what} is only available for {} triggers, but this custom command runs for {} triggers",
+                    join(allowed),
+                    join(kinds)
+                ),
+                range,
+            );
+        }
+    }
+}
+
+/// The field of the custom command's context data accessed by `node`, either
+/// through `.` where it has not been rebound, or through `$` outside associated
+/// templates, where it holds the data passed to the template instead.
+fn root_field(scope_info: &ScopeInfo, node: &SyntaxNode) -> Option<ast::Field> {
+    if let Some(chain) = ast::ContextFieldChain::cast(node.clone()) {
+        let is_root = scope_info.dot_at(chain.syntax().text_range().start()) == DotContext::Root;
+        is_root.then(|| chain.fields().next()).flatten()
+    } else if let Some(chain) = ast::ExprFieldChain::cast(node.clone())
+        && let Some(ast::Expr::VarAccess(access)) = chain.base_expr()
+        && access.var().is_some_and(|var| scope_info.is_root_data_var(var))
+    {
+        chain.fields().next()
+    } else {
+        None
+    }
+}
+
+fn join(kinds: &[TriggerKind]) -> String {
+    let names: Vec<_> = kinds.iter().map(|kind| kind.name()).collect();
+    names.join(" or ")
+}
+
+#[cfg(test)]
+mod tests {
+    use yag_template_envdefs::TriggerKind;
+
+    use crate::checks::test_util;
+    use crate::lint::LintConfig;
+
+    const ENVDEFS: &str = "func updateMessage(msg)\ntriggers component modal\nfield Reaction\ntriggers reaction\n\
+                           field ExecData\ntriggers exec\nfield User\n";
+
+    fn problems<'a>(source: &'a str, fallback: &[TriggerKind]) -> Vec<(String, &'a str)> {
+        let mut config = LintConfig::new();
+        config.set_fallback_triggers(fallback.to_vec());
+        test_util::diagnostics_with_config(ENVDEFS, source, &["invalid-for-trigger"], &config)
+            .into_iter()
+            .map(|diag| (diag.message, &source[diag.range]))
+            .collect()
+    }
+
+    #[test]
+    fn uses_invalid_for_the_declared_triggers_are_reported() {
+        assert_eq!(
+            problems(
+                "{{/* yag-trigger message, exec */}}{{.User}}{{.ExecData}}{{$.Reaction.Emoji}}{{updateMessage 1}}",
+                &[]
+            ),
+            [
+                (
+                    "`.Reaction` is only available for reaction triggers, but this custom command runs for message or \
+                     exec triggers"
+                        .to_owned(),
+                    ".Reaction"
+                ),
+                (
+                    "`updateMessage` is only available for component or modal triggers, but this custom command runs \
+                     for message or exec triggers"
+                        .to_owned(),
+                    "updateMessage"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn directives_take_precedence_over_the_fallback() {
+        let source = "{{/* yag-trigger reaction buttons */}}{{.Reaction}}{{.ExecData}}";
+        assert_eq!(
+            problems(source, &[TriggerKind::Exec]),
+            [
+                (
+                    "unknown trigger kind \"buttons\"; expected one of message, reaction, interval, component, modal, \
+                     exec"
+                        .to_owned(),
+                    "buttons"
+                ),
+                (
+                    "`.ExecData` is only available for exec triggers, but this custom command runs for reaction \
+                     triggers"
+                        .to_owned(),
+                    ".ExecData"
+                ),
+            ]
+        );
+        assert_eq!(problems("{{.Reaction}}", &[TriggerKind::Message]).len(), 1);
+        assert!(problems("{{.Reaction}}", &[]).is_empty());
+        assert!(problems("{{with .User}}{{.Reaction}}{{end}}", &[TriggerKind::Message]).is_empty());
+    }
+
+    #[test]
+    fn dollar_in_associated_templates_is_not_the_context_data() {
+        let source = r#"{{define "t"}}{{$.Reaction}}{{.Reaction}}{{end}}{{$.Reaction}}"#;
+        let mut config = LintConfig::new();
+        config.set_fallback_triggers(vec![TriggerKind::Message]);
+        let starts: Vec<usize> = test_util::diagnostics_with_config(ENVDEFS, source, &["invalid-for-trigger"], &config)
+            .iter()
+            .map(|diag| diag.range.start().into())
+            .collect();
+        assert_eq!(starts, [source.rfind(".Reaction").unwrap()]);
+    }
+}
@@ -0,0 +1,99 @@
+use yag_template_envdefs::{Func, Param};
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+use yag_template_syntax::visit::{self, Visitor};
+
+use crate::Severity;
+use crate::checks::receives_piped_value;
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::types::Type;
+
+pub struct TypeMismatch;
+
+impl Lint for TypeMismatch {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "type-mismatch",
+            default_severity: Severity::Warning,
+            description: "Reports function arguments whose inferred type is incompatible with the parameter.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let root = cx.root.clone();
+        visit::walk_root(&mut TypeMismatchChecker { cx }, &root);
+    }
+}
+
+struct TypeMismatchChecker<'c, 'a> {
+    cx: &'c mut LintContext<'a>,
+}
+
+/// An argument passed to a function: either an expression in the argument
+/// list, or the value piped in from the previous stage of a pipeline.
+enum Arg {
+    Expr(ast::Expr),
+    Piped(Type),
+}
+
+impl TypeMismatchChecker<'_, '_> {
+    fn check_call(&mut self, call: &ast::FuncCall, piped: Option<Type>) {
+        let Some(func_name) = call.func_name() else {
+            return;
+        };
+        let Some(func) = self.cx.env.funcs.get(func_name.get()) else {
+            return;
+        };
+        let args = call.args().map(Arg::Expr).chain(piped.map(Arg::Piped));
+        for (i, arg) in args.enumerate() {
+            let Some(param) = param_at(func, i) else {
+                break;
+            };
+            let (ty, range, suffix) = match &arg {
+                Arg::Expr(expr) => (self.cx.types.type_of(expr), expr.text_range(), ""),
+                Arg::Piped(ty) => (*ty, func_name.text_range(), " from pipeline"),
+            };
+            if !ty.is_assignable_to(param.ty) {
+                let message = format!(
+                    "expected {} for parameter {} of {}, found {ty}{suffix}",
+                    param.ty, param.name, func.name
+                );
+                self.cx.report(message, range);
+            }
+        }
+    }
+}
+
+/// The parameter receiving the `i`th argument, accounting for a trailing
+/// variadic parameter.
+fn param_at(func: &Func, i: usize) -> Option<&Param> {
+    func.params
+        .get(i)
+        .or_else(|| func.params.last().filter(|param| param.is_variadic))
+}
+
+impl Visitor for TypeMismatchChecker<'_, '_> {
+    fn enter_func_call(&mut self, call: &ast::FuncCall) {
+        // Calls in pipeline stages are checked along with their piped argument
+        // in `enter_pipeline`.
+        if !receives_piped_value(call) {
+            self.check_call(call, None);
+        }
+    }
+
+    fn enter_pipeline(&mut self, pipeline: &ast::Pipeline) {
+        let mut piped = pipeline
+            .init_expr()
+            .map_or(Type::Any, |expr| self.cx.types.type_of(&expr));
+        for stage in pipeline.stages() {
+            let Some(call_expr) = stage.call_expr() else {
+                piped = Type::Any;
+                continue;
+            };
+            if let ast::Expr::FuncCall(call) = &call_expr {
+                self.check_call(call, Some(piped));
+            }
+            piped = self.cx.types.type_of(&call_expr);
+        }
+    }
+}
@@ -1,26 +1,41 @@
-use yag_template_envdefs::EnvDefs;
 use yag_template_syntax::ast;
-use yag_template_syntax::ast::{AstNode, AstToken};
+use yag_template_syntax::ast::AstToken;
+use yag_template_syntax::visit::{self, Visitor};
 
-use crate::AnalysisError;
+use crate::Severity;
+use crate::lint::{Lint, LintContext, LintMeta};
 
-pub fn check(env: &EnvDefs, root: &ast::Root) -> Vec<AnalysisError> {
-    root.syntax()
-        .descendants()
-        .filter_map(ast::FuncCall::cast)
-        .filter_map(|call| check_func_call(env, call))
-        .collect()
+pub struct UndefinedFunction;
+
+impl Lint for UndefinedFunction {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "undefined-function",
+            default_severity: Severity::Error,
+            description: "Reports calls to functions that are not defined.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let root = cx.root.clone();
+        visit::walk_root(&mut UndefinedFuncChecker { cx }, &root);
+    }
+}
+
+struct UndefinedFuncChecker<'c, 'a> {
+    cx: &'c mut LintContext<'a>,
 }
 
-fn check_func_call(env: &EnvDefs, call: ast::FuncCall) -> Option<AnalysisError> {
-    let func_name_ident = call.func_name()?;
-    let func_name = func_name_ident.get();
-    if env.funcs.contains_key(func_name) {
-        None
-    } else {
-        Some(AnalysisError::new(
-            format!("unknown function {func_name}"),
-            func_name_ident.text_range(),
-        ))
+impl Visitor for UndefinedFuncChecker<'_, '_> {
+    fn enter_func_call(&mut self, call: &ast::FuncCall) {
+        let Some(func_name_ident) = call.func_name() else {
+            return;
+        };
+        let func_name = func_name_ident.get();
+        if !self.cx.env.funcs.contains_key(func_name) {
+            self.cx
+                .report(format!("unknown function {func_name}"), func_name_ident.text_range());
+        }
     }
 }
@@ -0,0 +1,22 @@
+use crate::Severity;
+use crate::lint::{Lint, LintContext, LintMeta};
+
+pub struct UndefinedVariable;
+
+impl Lint for UndefinedVariable {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "undefined-variable",
+            default_severity: Severity::Error,
+            description: "Reports uses of variables that are not declared in an enclosing scope.",
+            tags: &[],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let scope_info = cx.scope_info;
+        for (name, range) in scope_info.undefined_var_uses() {
+            cx.report(format!("undefined variable {name}"), *range);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use rowan::{TextRange, TextSize};
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+
+use crate::control_flow::ControlFlowGraph;
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::{DiagnosticTag, Severity};
+
+pub struct UnreachableCode;
+
+impl Lint for UnreachableCode {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "unreachable-code",
+            default_severity: Severity::Warning,
+            description: "Reports text and actions that can never be executed, such as those following a \
+                          `return` or `break` in the same body.",
+            tags: &[DiagnosticTag::Unnecessary],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let lists = std::iter::once(cx.root.actions_with_text()).chain(
+            cx.root
+                .syntax()
+                .descendants()
+                .filter_map(ast::ActionList::cast)
+                .map(|list| list.actions_with_text()),
+        );
+        let mut unreachable = Vec::new();
+        for elements in lists {
+            unreachable_runs(cx.control_flow, elements, &mut unreachable);
+        }
+        for range in unreachable {
+            cx.report("unreachable code", range);
+        }
+    }
+}
+
+/// Find the runs of unreachable elements in a list that directly follow a
+/// reachable element. Unreachable runs at the start of a list are not
+/// reported, since they are only unreachable if the enclosing action is.
+fn unreachable_runs(graph: &ControlFlowGraph, elements: ast::ActionsWithText, out: &mut Vec<TextRange>) {
+    let mut prev_reachable = false;
+    let mut run: Option<TextRange> = None;
+    for element in elements {
+        let (node, range) = match &element {
+            // Whitespace, comments, and template definitions (which are not
+            // executed in place) neither start nor end a run.
+            ast::ActionOrText::Text(text) if text.get().trim().is_empty() => continue,
+            ast::ActionOrText::Action(ast::Action::Comment(_) | ast::Action::TemplateDefinition(_)) => continue,
+            ast::ActionOrText::Text(text) => (graph.text_node(text), trimmed_range(text)),
+            ast::ActionOrText::Action(action) => (graph.action_node(action), action.text_range()),
+        };
+        let Some(node) = node else {
+            continue;
+        };
+        if graph.is_reachable(node) {
+            out.extend(run.take());
+            prev_reachable = true;
+        } else if let Some(run) = &mut run {
+            *run = run.cover(range);
+        } else if prev_reachable {
+            run = Some(range);
+        }
+    }
+    out.extend(run);
+}
+
+fn trimmed_range(text: &ast::Text) -> TextRange {
+    let s = text.get();
+    let range = text.text_range();
+    let leading = TextSize::of(&s[..s.len() - s.trim_start().len()]);
+    let trailing = TextSize::of(&s[s.trim_end().len()..]);
+    TextRange::new(range.start() + leading, range.end() - trailing)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checks::test_util;
+
+    fn unreachable(source: &str) -> Vec<&str> {
+        test_util::diagnostics("", source, &["unreachable-code"])
+            .into_iter()
+            .map(|diag| &source[diag.range])
+            .collect()
+    }
+
+    #[test]
+    fn code_after_return_is_reported_once() {
+        assert_eq!(
+            unreachable("{{return}}\n  text {{if .}}{{.}}{{end}}\n{{/* note */}}"),
+            ["text {{if .}}{{.}}{{end}}"]
+        );
+    }
+
+    #[test]
+    fn code_after_loop_control_is_reported() {
+        assert_eq!(
+            unreachable("{{range .}}{{if .}}{{continue}} a {{end}}{{break}}\n{{.}}{{end}}"),
+            ["a", "{{.}}"]
+        );
+    }
+
+    #[test]
+    fn whitespace_and_definitions_after_return_are_not_reported() {
+        assert!(unreachable("{{if .}}{{return}}\n{{end}}\n").is_empty());
+        assert!(unreachable("{{return}}\n{{define \"t\"}}{{.}}{{end}}").is_empty());
+    }
+}
@@ -0,0 +1,40 @@
+use crate::lint::{Lint, LintContext, LintMeta};
+use crate::{DiagnosticTag, Fix, Severity, TextEdit};
+
+pub struct UnusedVariable;
+
+impl Lint for UnusedVariable {
+    fn meta(&self) -> &'static LintMeta {
+        &LintMeta {
+            id: "unused-variable",
+            default_severity: Severity::Warning,
+            description: "Reports variables that are declared but never used. \
+                          Variables with names ending in `_` are exempt.",
+            tags: &[DiagnosticTag::Unnecessary],
+        }
+    }
+
+    fn check(&self, cx: &mut LintContext<'_>) {
+        let scope_info = cx.scope_info;
+        for v in scope_info.var_symbols() {
+            if v.used || v.name.ends_with('_') {
+                continue;
+            }
+            let Some(decl_range) = v.decl_range else {
+                continue;
+            };
+            let message = format!("unused variable {}", v.name);
+            match v.name_range {
+                Some(name_range) => {
+                    let new_name = format!("{}_", v.name);
+                    let fix = Fix::new(
+                        format!("Rename to {new_name}"),
+                        vec![TextEdit::replace(name_range, new_name)],
+                    );
+                    cx.report_with_fix(message, decl_range, fix);
+                }
+                None => cx.report(message, decl_range),
+            }
+        }
+    }
+}
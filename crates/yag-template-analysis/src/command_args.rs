@@ -0,0 +1,298 @@
+//! The arguments of custom commands, as declared with `parseArgs` and `carg`.
+//!
+//! A command declares its arguments with, e.g.,
+//!
+//! ```txt
+//! {{$args := parseArgs 1 "" (carg "int" "amount") (carg "user" "target")}}
+//! ```
+//!
+//! and later reads them with `$args.Get 0` and `$args.IsSet 1`. When the
+//! declaration uses literal arguments, the schema is recorded on the declared
+//! variable so that each such access can be resolved to an argument.
+
+use foldhash::HashMap;
+use rowan::TextRange;
+use smol_str::SmolStr;
+use yag_template_syntax::ast::{self, AstNode};
+use yag_template_syntax::visit::{self, Visitor};
+
+use crate::checks::{receives_piped_value, string_literal};
+use crate::scope::{ScopeInfo, VarSymbolId};
+use crate::types::Type;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgType {
+    Int,
+    Float,
+    String,
+    User,
+    UserId,
+    Member,
+    Channel,
+    Role,
+    Duration,
+}
+
+impl ArgType {
+    pub const ALL: &[ArgType] = &[
+        ArgType::Int,
+        ArgType::Float,
+        ArgType::String,
+        ArgType::User,
+        ArgType::UserId,
+        ArgType::Member,
+        ArgType::Channel,
+        ArgType::Role,
+        ArgType::Duration,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|ty| ty.name() == name)
+    }
+
+    /// The name of the type, as passed to `carg`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ArgType::Int => "int",
+            ArgType::Float => "float",
+            ArgType::String => "string",
+            ArgType::User => "user",
+            ArgType::UserId => "userid",
+            ArgType::Member => "member",
+            ArgType::Channel => "channel",
+            ArgType::Role => "role",
+            ArgType::Duration => "duration",
+        }
+    }
+
+    /// Report whether arguments of this type accept minimum and maximum
+    /// bounds.
+    pub fn supports_bounds(self) -> bool {
+        matches!(self, ArgType::Int | ArgType::Float | ArgType::Duration)
+    }
+
+    /// The type of the value that `.Get` returns for arguments of this type.
+    pub fn value_type(self) -> Type {
+        match self {
+            ArgType::Int | ArgType::UserId => Type::Int,
+            ArgType::Float => Type::Float,
+            ArgType::String => Type::String,
+            ArgType::Duration => Type::Duration,
+            ArgType::User | ArgType::Member | ArgType::Channel | ArgType::Role => Type::Any,
+        }
+    }
+}
+
+/// An argument declared by a `carg` call with literal type and name.
+#[derive(Debug, Clone)]
+pub struct ArgDef {
+    pub name: SmolStr,
+    /// The type of the argument, or `None` if the type name is invalid.
+    pub ty: Option<ArgType>,
+    /// The range of the `carg` call.
+    pub range: TextRange,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArgSchema {
+    /// The number of required arguments, if given by a literal.
+    pub num_required: Option<i64>,
+    /// The arguments in order, or `None` for those not declared by a `carg`
+    /// call with literal type and name.
+    pub args: Vec<Option<ArgDef>>,
+    /// The range of the `parseArgs` call.
+    pub range: TextRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgMethod {
+    Get,
+    IsSet,
+}
+
+/// An access to an argument with a literal index, such as `$args.Get 0`.
+#[derive(Debug, Clone)]
+pub struct ArgAccess {
+    /// The variable holding the parsed arguments.
+    pub var: VarSymbolId,
+    pub method: ArgMethod,
+    pub index: i64,
+    /// The range of the index literal.
+    pub index_range: TextRange,
+    /// The range of the entire call.
+    pub range: TextRange,
+}
+
+#[derive(Debug, Default)]
+pub struct CommandArgs {
+    schemas: HashMap<VarSymbolId, ArgSchema>,
+    accesses: Vec<ArgAccess>,
+}
+
+impl CommandArgs {
+    /// The schema of the arguments held by the variable, if it is declared
+    /// with a call to `parseArgs`.
+    pub fn schema(&self, var: VarSymbolId) -> Option<&ArgSchema> {
+        self.schemas.get(&var)
+    }
+
+    pub fn schemas(&self) -> impl Iterator<Item = &ArgSchema> {
+        self.schemas.values()
+    }
+
+    /// All accesses to arguments with literal indices, in source order.
+    pub fn accesses(&self) -> &[ArgAccess] {
+        &self.accesses
+    }
+
+    /// The access given by the call at `range`, if any.
+    pub fn access_at(&self, range: TextRange) -> Option<&ArgAccess> {
+        self.accesses.iter().find(|access| access.range == range)
+    }
+
+    /// The argument read by `access`, or `None` if the index is out of range
+    /// or the argument is not declared with literals.
+    pub fn resolve(&self, access: &ArgAccess) -> Option<&ArgDef> {
+        let index = usize::try_from(access.index).ok()?;
+        self.schema(access.var)?.args.get(index)?.as_ref()
+    }
+}
+
+pub fn collect(root: &ast::Root, scope_info: &ScopeInfo) -> CommandArgs {
+    let mut collector = ArgCollector {
+        scope_info,
+        args: CommandArgs::default(),
+    };
+    visit::walk_root(&mut collector, root);
+    collector.args
+}
+
+struct ArgCollector<'a> {
+    scope_info: &'a ScopeInfo,
+    args: CommandArgs,
+}
+
+impl Visitor for ArgCollector<'_> {
+    fn enter_var_decl(&mut self, decl: &ast::VarDecl) {
+        let Some(ast::Expr::FuncCall(call)) = decl.initializer() else {
+            return;
+        };
+        if call.func_name().is_none_or(|name| name.get() != "parseArgs") || receives_piped_value(&call) {
+            return;
+        }
+        let Some(sym) = decl.var().and_then(|var| self.scope_info.resolve_var(var)) else {
+            return;
+        };
+        let schema = ArgSchema {
+            num_required: call.args().next().and_then(|arg| int_literal(&arg)),
+            args: call.args().skip(2).map(|arg| arg_def(&arg)).collect(),
+            range: call.text_range(),
+        };
+        self.args.schemas.insert(sym.id, schema);
+    }
+
+    fn enter_expr_call(&mut self, call: &ast::ExprCall) {
+        let Some(ast::Expr::ExprFieldChain(chain)) = call.callee() else {
+            return;
+        };
+        let mut fields = chain.fields();
+        let method = match fields
+            .next()
+            .and_then(|field| field.name().map(str::to_owned))
+            .as_deref()
+        {
+            Some("Get") => ArgMethod::Get,
+            Some("IsSet") => ArgMethod::IsSet,
+            _ => return,
+        };
+        if fields.next().is_some() {
+            return;
+        }
+        let Some(ast::Expr::VarAccess(access)) = chain.base_expr() else {
+            return;
+        };
+        let Some(sym) = access.var().and_then(|var| self.scope_info.resolve_var(var)) else {
+            return;
+        };
+        if !self.args.schemas.contains_key(&sym.id) {
+            return;
+        }
+        let Some(index_expr) = call.args().next() else {
+            return;
+        };
+        if let Some(index) = int_literal(&index_expr) {
+            self.args.accesses.push(ArgAccess {
+                var: sym.id,
+                method,
+                index,
+                index_range: index_expr.text_range(),
+                range: call.text_range(),
+            });
+        }
+    }
+}
+
+/// The argument declared by `expr`, if it is a `carg` call with literal type
+/// and name, possibly parenthesized.
+pub fn arg_def(expr: &ast::Expr) -> Option<ArgDef> {
+    let call = carg_call(expr)?;
+    let mut args = call.args();
+    let ty = string_literal(&args.next()?)?;
+    let name = string_literal(&args.next()?)?;
+    Some(ArgDef {
+        name: SmolStr::new(name),
+        ty: ArgType::from_name(&ty),
+        range: call.text_range(),
+    })
+}
+
+/// The `carg` call that is `expr`, possibly parenthesized.
+pub fn carg_call(expr: &ast::Expr) -> Option<ast::FuncCall> {
+    match expr {
+        ast::Expr::Parenthesized(p) => carg_call(&p.inner_expr()?),
+        ast::Expr::FuncCall(call) if call.func_name().is_some_and(|name| name.get() == "carg") => Some(call.clone()),
+        _ => None,
+    }
+}
+
+fn int_literal(expr: &ast::Expr) -> Option<i64> {
+    match expr {
+        ast::Expr::Literal(lit) => match lit.kind() {
+            ast::LiteralKind::Int(int) => int.get(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::{SyntaxNode, ast, parser};
+
+    use super::{ArgMethod, ArgType, collect};
+    use crate::scope;
+
+    #[test]
+    fn schema_and_accesses_are_collected() {
+        let source = r#"{{$args := parseArgs 1 "" (carg "int" "amount" 1 10) (carg "bogus" "x") $dyn}}{{$args.Get 0}}{{if $args.IsSet 2}}{{end}}{{$other.Get 0}}"#;
+        let parse = parser::parse(source);
+        let root = SyntaxNode::new_root(parse.root).to::<ast::Root>();
+        let scope_info = scope::analyze(root.clone());
+        let args = collect(&root, &scope_info);
+
+        let schema = args.schemas().next().unwrap();
+        assert_eq!(schema.num_required, Some(1));
+        let defs: Vec<_> = schema
+            .args
+            .iter()
+            .map(|def| def.as_ref().map(|def| (def.name.as_str(), def.ty)))
+            .collect();
+        assert_eq!(defs, [Some(("amount", Some(ArgType::Int))), Some(("x", None)), None]);
+
+        let accesses: Vec<_> = args.accesses().iter().map(|a| (a.method, a.index)).collect();
+        assert_eq!(accesses, [(ArgMethod::Get, 0), (ArgMethod::IsSet, 2)]);
+        assert_eq!(args.resolve(&args.accesses()[0]).unwrap().name, "amount");
+        assert!(args.resolve(&args.accesses()[1]).is_none());
+    }
+}
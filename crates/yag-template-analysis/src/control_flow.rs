@@ -0,0 +1,508 @@
+//! Control-flow graphs over templates.
+//!
+//! The graph has a node for each piece of text and each action in the
+//! template, along with a few synthetic nodes marking the entry and exit of
+//! each template and the points where branches rejoin. An edge from one node
+//! to another means that control may pass directly between them.
+//!
+//! Conditions are not evaluated, so both branches of an `{{if}}` are always
+//! considered possible, and any action within a `{{try}}` may transfer control
+//! to the corresponding `{{catch}}`.
+//!
+//! Nodes refer to the syntax tree by pointer, so that the graph can be shared
+//! between threads; use [SyntaxNodePtr::to_node] with the root of the tree to
+//! recover the syntax node.
+
+use foldhash::HashMap;
+use rowan::ast::SyntaxNodePtr;
+use rowan::{TextRange, TextSize};
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+use yag_template_syntax::{SyntaxKind, SyntaxNode, YagTemplateLanguage};
+
+use crate::scope::{ScopeInfo, VarSymbolId};
+
+type NodePtr = SyntaxNodePtr<YagTemplateLanguage>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// The position of the node in [ControlFlowGraph::nodes].
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NodeKind {
+    /// The start of the root template or of an associated template.
+    Entry,
+    /// The end of the root template or of an associated template.
+    Exit,
+    /// Text, given by its range.
+    Text(TextRange),
+    /// An action. For compound actions, this node evaluates the condition (of
+    /// an `{{if}}`, `{{with}}` or `{{while}}`) or the value to iterate over
+    /// (of a `{{range}}`).
+    Action(NodePtr),
+    /// An `{{else}}` or `{{else if}}` branch, evaluating the condition if any.
+    ElseBranch(NodePtr),
+    /// The start of an iteration of a `{{range}}` loop, which assigns the
+    /// iteration variables.
+    RangeIteration(NodePtr),
+    /// A point at which several paths rejoin.
+    Join,
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub successors: Vec<NodeId>,
+    pub predecessors: Vec<NodeId>,
+}
+
+/// A read or write of a variable by a node of the graph.
+#[derive(Debug, Clone)]
+pub struct VarEvent {
+    pub sym: VarSymbolId,
+    pub var: ast::Var,
+    pub kind: VarEventKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum VarEventKind {
+    Read,
+    /// A declaration or assignment, given by its [ast::VarDecl] or
+    /// [ast::VarAssign] node, which takes effect once the value is evaluated.
+    Store(SyntaxNode),
+    /// The assignment of an iteration variable of a `{{range}}` loop at the
+    /// start of each iteration.
+    Iteration(ast::RangeClause),
+}
+
+#[derive(Debug, Default)]
+pub struct ControlFlowGraph {
+    nodes: Vec<Node>,
+    /// The entry nodes of the root template and of each associated template.
+    entries: Vec<NodeId>,
+    /// The nodes for the text and actions in the template, indexed by their
+    /// text ranges.
+    element_nodes: HashMap<TextRange, NodeId>,
+    /// The exit nodes of the associated templates, indexed by the text ranges
+    /// of the `{{define}}` or `{{block}}` actions.
+    template_exits: HashMap<TextRange, NodeId>,
+    reachable: Vec<bool>,
+}
+
+impl ControlFlowGraph {
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.index()]
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes.iter().enumerate().map(|(i, node)| (NodeId(i as u32), node))
+    }
+
+    pub fn entries(&self) -> &[NodeId] {
+        &self.entries
+    }
+
+    /// The node for the action, or, for compound actions, the node evaluating
+    /// its condition.
+    pub fn action_node(&self, action: &ast::Action) -> Option<NodeId> {
+        self.element_nodes.get(&action.text_range()).copied()
+    }
+
+    pub fn text_node(&self, text: &ast::Text) -> Option<NodeId> {
+        self.element_nodes.get(&text.text_range()).copied()
+    }
+
+    /// The exit node of the associated template defined by the `{{define}}`
+    /// or `{{block}}` action with the given range.
+    pub fn template_exit(&self, range: TextRange) -> Option<NodeId> {
+        self.template_exits.get(&range).copied()
+    }
+
+    /// Report whether control can reach the node from the entry of some
+    /// template.
+    pub fn is_reachable(&self, id: NodeId) -> bool {
+        self.reachable[id.index()]
+    }
+
+    /// The variable reads and writes performed by each node, indexed by
+    /// [NodeId::index] and in order of evaluation within a node.
+    pub fn var_events(&self, root: &ast::Root, scope_info: &ScopeInfo) -> Vec<Vec<VarEvent>> {
+        self.nodes
+            .iter()
+            .map(|node| node_events(root, scope_info, &node.kind))
+            .collect()
+    }
+}
+
+fn node_events(root: &ast::Root, scope_info: &ScopeInfo, kind: &NodeKind) -> Vec<VarEvent> {
+    let mut events = Vec::new();
+    match kind {
+        NodeKind::Action(ptr) => {
+            let Some(action) = ast::Action::cast(ptr.to_node(root.syntax())) else {
+                return events;
+            };
+            if let Some(region) = evaluated_region(&action) {
+                events = region_events(&region, scope_info);
+            }
+        }
+        NodeKind::ElseBranch(ptr) => {
+            if let Some(clause) = ast::ElseBranch::cast(ptr.to_node(root.syntax())).and_then(|branch| branch.clause()) {
+                events = region_events(clause.syntax(), scope_info);
+            }
+        }
+        NodeKind::RangeIteration(ptr) => {
+            let Some(clause) = ast::RangeLoop::cast(ptr.to_node(root.syntax())).and_then(|range| range.clause()) else {
+                return events;
+            };
+            if clause.declares_vars() || clause.assigns_vars() {
+                for var in clause.iteration_vars() {
+                    if let Some(sym) = scope_info.resolve_var(var.clone()) {
+                        events.push(VarEvent {
+                            sym: sym.id,
+                            var,
+                            kind: VarEventKind::Iteration(clause.clone()),
+                        });
+                    }
+                }
+            }
+        }
+        NodeKind::Entry | NodeKind::Exit | NodeKind::Text(_) | NodeKind::Join => {}
+    }
+    events
+}
+
+/// The part of the action evaluated at its node in the graph.
+fn evaluated_region(action: &ast::Action) -> Option<SyntaxNode> {
+    use ast::Action::*;
+    match action {
+        Comment(_) | TemplateDefinition(_) | TryCatch(_) => None,
+        If(if_action) => if_action.clause().map(|clause| clause.syntax().clone()),
+        With(with_action) => with_action.clause().map(|clause| clause.syntax().clone()),
+        While(while_loop) => while_loop.clause().map(|clause| clause.syntax().clone()),
+        // The iteration variables are assigned at the start of each iteration.
+        Range(range_loop) => range_loop.clause()?.expr().map(|expr| expr.syntax().clone()),
+        TemplateBlock(block) => block.context_data().map(|expr| expr.syntax().clone()),
+        _ => Some(action.syntax().clone()),
+    }
+}
+
+fn region_events(region: &SyntaxNode, scope_info: &ScopeInfo) -> Vec<VarEvent> {
+    let mut events: Vec<(TextSize, VarEvent)> = Vec::new();
+    for var in region
+        .descendants_with_tokens()
+        .filter_map(|el| el.into_token())
+        .filter_map(ast::Var::cast)
+    {
+        let Some(sym) = scope_info.resolve_var(var.clone()) else {
+            continue;
+        };
+        let Some(parent) = var.syntax().parent() else {
+            continue;
+        };
+        // Writes take effect after the right-hand side is evaluated.
+        let (offset, kind) = match parent.kind() {
+            SyntaxKind::VarDecl | SyntaxKind::VarAssign => (parent.text_range().end(), VarEventKind::Store(parent)),
+            _ => (var.text_range().start(), VarEventKind::Read),
+        };
+        events.push((offset, VarEvent { sym: sym.id, var, kind }));
+    }
+    events.sort_by_key(|(offset, _)| *offset);
+    events.into_iter().map(|(_, event)| event).collect()
+}
+
+pub fn build(root: &ast::Root) -> ControlFlowGraph {
+    let mut builder = Builder {
+        graph: ControlFlowGraph::default(),
+        loops: Vec::new(),
+        catch_entries: Vec::new(),
+        template_exit: NodeId(0),
+    };
+    builder.template(root.actions_with_text(), None);
+    builder.finish()
+}
+
+/// Where `{{break}}` and `{{continue}}` within a loop body transfer control.
+struct LoopTargets {
+    break_to: NodeId,
+    continue_to: NodeId,
+}
+
+struct Builder {
+    graph: ControlFlowGraph,
+    loops: Vec<LoopTargets>,
+    /// The entries of the `{{catch}}` bodies of the enclosing `{{try}}`
+    /// actions.
+    catch_entries: Vec<NodeId>,
+    template_exit: NodeId,
+}
+
+impl Builder {
+    fn finish(mut self) -> ControlFlowGraph {
+        let mut reachable = vec![false; self.graph.nodes.len()];
+        let mut stack = self.graph.entries.clone();
+        while let Some(id) = stack.pop() {
+            if !std::mem::replace(&mut reachable[id.index()], true) {
+                stack.extend(self.graph.node(id).successors.iter().copied());
+            }
+        }
+        self.graph.reachable = reachable;
+        self.graph
+    }
+
+    fn add_node(&mut self, kind: NodeKind) -> NodeId {
+        let id = NodeId(self.graph.nodes.len() as u32);
+        self.graph.nodes.push(Node {
+            kind,
+            successors: Vec::new(),
+            predecessors: Vec::new(),
+        });
+        if let Some(&catch_entry) = self.catch_entries.last() {
+            self.add_edge(id, catch_entry);
+        }
+        id
+    }
+
+    fn add_edge(&mut self, from: NodeId, to: NodeId) {
+        self.graph.nodes[from.index()].successors.push(to);
+        self.graph.nodes[to.index()].predecessors.push(from);
+    }
+
+    fn connect(&mut self, from: Option<NodeId>, to: NodeId) {
+        if let Some(from) = from {
+            self.add_edge(from, to);
+        }
+    }
+
+    /// Build the graph for a template (the root template or an associated
+    /// template) consisting of the elements given. `owner` is the range of the
+    /// action defining an associated template.
+    fn template(&mut self, elements: ast::ActionsWithText, owner: Option<TextRange>) {
+        // Control never crosses a template boundary except by invocation.
+        let outer_loops = std::mem::take(&mut self.loops);
+        let outer_catch_entries = std::mem::take(&mut self.catch_entries);
+
+        let entry = self.add_node(NodeKind::Entry);
+        let exit = self.add_node(NodeKind::Exit);
+        self.graph.entries.push(entry);
+        if let Some(owner) = owner {
+            self.graph.template_exits.insert(owner, exit);
+        }
+        let outer_exit = std::mem::replace(&mut self.template_exit, exit);
+        let end = self.elements(elements, Some(entry));
+        self.connect(end, exit);
+
+        self.template_exit = outer_exit;
+        self.loops = outer_loops;
+        self.catch_entries = outer_catch_entries;
+    }
+
+    /// Add the elements in sequence after `pred`, returning the node from
+    /// which control continues afterward, or `None` if it never does.
+    fn elements(&mut self, elements: ast::ActionsWithText, pred: Option<NodeId>) -> Option<NodeId> {
+        let mut cur = pred;
+        for element in elements {
+            cur = match element {
+                ast::ActionOrText::Action(action) => self.action(action, cur),
+                ast::ActionOrText::Text(text) => {
+                    let range = text.text_range();
+                    let id = self.add_node(NodeKind::Text(range));
+                    self.graph.element_nodes.insert(range, id);
+                    self.connect(cur, id);
+                    Some(id)
+                }
+            };
+        }
+        cur
+    }
+
+    fn body(&mut self, body: Option<ast::ActionList>, pred: Option<NodeId>) -> Option<NodeId> {
+        match body {
+            Some(body) => self.elements(body.actions_with_text(), pred),
+            None => pred,
+        }
+    }
+
+    fn action(&mut self, action: ast::Action, pred: Option<NodeId>) -> Option<NodeId> {
+        use ast::Action::*;
+
+        let id = self.add_node(NodeKind::Action(NodePtr::new(action.syntax())));
+        self.graph.element_nodes.insert(action.text_range(), id);
+        self.connect(pred, id);
+
+        match action {
+            Comment(_) | TemplateInvocation(_) | ExprAction(_) => Some(id),
+            TemplateDefinition(define) => {
+                if let Some(body) = define.template_body() {
+                    self.template(body.actions_with_text(), Some(define.text_range()));
+                }
+                Some(id)
+            }
+            TemplateBlock(block) => {
+                if let Some(body) = block.template_body() {
+                    self.template(body.actions_with_text(), Some(block.text_range()));
+                }
+                Some(id)
+            }
+            Return(_) => {
+                self.add_edge(id, self.template_exit);
+                None
+            }
+            // Misplaced loop control is reported elsewhere; treat it as a
+            // no-op here.
+            Break(_) => match self.loops.last() {
+                Some(targets) => {
+                    self.add_edge(id, targets.break_to);
+                    None
+                }
+                None => Some(id),
+            },
+            Continue(_) => match self.loops.last() {
+                Some(targets) => {
+                    self.add_edge(id, targets.continue_to);
+                    None
+                }
+                None => Some(id),
+            },
+            If(if_action) => Some(self.conditional(id, if_action.body(), if_action.else_branches())),
+            With(with_action) => Some(self.conditional(id, with_action.body(), with_action.else_branches())),
+            Range(range_loop) => {
+                let after = self.add_node(NodeKind::Join);
+                let iteration = self.add_node(NodeKind::RangeIteration(NodePtr::new(range_loop.syntax())));
+                self.add_edge(id, iteration);
+                self.add_edge(iteration, after);
+                self.loop_body(range_loop.body(), iteration, after);
+                self.else_body(range_loop.else_branch(), id, after);
+                Some(after)
+            }
+            While(while_loop) => {
+                let after = self.add_node(NodeKind::Join);
+                self.add_edge(id, after);
+                self.loop_body(while_loop.body(), id, after);
+                self.else_body(while_loop.else_branch(), id, after);
+                Some(after)
+            }
+            TryCatch(try_catch) => {
+                let after = self.add_node(NodeKind::Join);
+                let catch_entry = self.add_node(NodeKind::Join);
+                // An error may occur before the first action in the body
+                // completes, in which case no part of it takes effect.
+                self.add_edge(id, catch_entry);
+                self.catch_entries.push(catch_entry);
+                let try_end = self.body(try_catch.try_body(), Some(id));
+                self.catch_entries.pop();
+                self.connect(try_end, after);
+                let catch_end = self.body(try_catch.catch_body(), Some(catch_entry));
+                self.connect(catch_end, after);
+                Some(after)
+            }
+        }
+    }
+
+    /// Add the body and else branches of an `{{if}}` or `{{with}}` action
+    /// whose condition is evaluated at `cond`, returning the join node after.
+    fn conditional(
+        &mut self,
+        cond: NodeId,
+        body: Option<ast::ActionList>,
+        else_branches: impl Iterator<Item = ast::ElseBranch>,
+    ) -> NodeId {
+        let after = self.add_node(NodeKind::Join);
+        let body_end = self.body(body, Some(cond));
+        self.connect(body_end, after);
+
+        // The node from which control passes to the next branch if the
+        // previous condition is false, or `None` after an unconditional else.
+        let mut prev_cond = Some(cond);
+        for else_branch in else_branches {
+            let has_condition = else_branch.condition().is_some();
+            let branch = self.add_node(NodeKind::ElseBranch(NodePtr::new(else_branch.syntax())));
+            self.connect(prev_cond, branch);
+            let branch_end = self.body(else_branch.body(), Some(branch));
+            self.connect(branch_end, after);
+            prev_cond = has_condition.then_some(branch);
+        }
+        self.connect(prev_cond, after);
+        after
+    }
+
+    fn loop_body(&mut self, body: Option<ast::ActionList>, head: NodeId, after: NodeId) {
+        self.loops.push(LoopTargets {
+            break_to: after,
+            continue_to: head,
+        });
+        let body_end = self.body(body, Some(head));
+        self.loops.pop();
+        self.connect(body_end, head);
+    }
+
+    /// Add the `{{else}}` branch of a loop, taken from `head` if there are no
+    /// iterations.
+    fn else_body(&mut self, else_branch: Option<ast::ElseBranch>, head: NodeId, after: NodeId) {
+        if let Some(else_branch) = else_branch {
+            let branch = self.add_node(NodeKind::ElseBranch(NodePtr::new(else_branch.syntax())));
+            self.add_edge(head, branch);
+            let branch_end = self.body(else_branch.body(), Some(branch));
+            self.connect(branch_end, after);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::ast::{self, AstNode};
+    use yag_template_syntax::{SyntaxNode, parser};
+
+    use super::build;
+
+    /// The source text of the reachable and unreachable actions in `source`.
+    fn reachability(source: &str) -> (Vec<String>, Vec<String>) {
+        let parse = parser::parse(source);
+        assert!(parse.errors.is_empty(), "unexpected errors: {:?}", parse.errors);
+        let root = SyntaxNode::new_root(parse.root).to::<ast::Root>();
+        let graph = build(&root);
+        let (reachable, unreachable): (Vec<_>, Vec<_>) = root
+            .syntax()
+            .descendants()
+            .filter_map(ast::ExprAction::cast)
+            .partition(|action| {
+                let id = graph.action_node(&ast::Action::ExprAction(action.clone())).unwrap();
+                graph.is_reachable(id)
+            });
+        let text = |actions: Vec<ast::ExprAction>| actions.iter().map(|a| a.syntax().to_string()).collect();
+        (text(reachable), text(unreachable))
+    }
+
+    #[test]
+    fn return_ends_the_template() {
+        let (_, unreachable) = reachability(r#"{{1}}{{return}}{{2}}{{define "t"}}{{3}}{{return}}{{4}}{{end}}{{5}}"#);
+        assert_eq!(unreachable, ["{{2}}", "{{4}}", "{{5}}"]);
+    }
+
+    #[test]
+    fn branches_rejoin_unless_all_return() {
+        let (_, unreachable) = reachability("{{if .}}{{return}}{{else if .}}{{1}}{{end}}{{2}}");
+        assert!(unreachable.is_empty());
+        let (_, unreachable) = reachability("{{if .}}{{return}}{{else}}{{return}}{{end}}{{1}}");
+        assert_eq!(unreachable, ["{{1}}"]);
+    }
+
+    #[test]
+    fn loop_control_skips_the_rest_of_the_body() {
+        let (reachable, unreachable) =
+            reachability("{{range .}}{{break}}{{1}}{{end}}{{2}}{{while .}}{{continue}}{{3}}{{else}}{{4}}{{end}}");
+        assert_eq!(reachable, ["{{2}}", "{{4}}"]);
+        assert_eq!(unreachable, ["{{1}}", "{{3}}"]);
+    }
+
+    #[test]
+    fn catch_is_reachable_from_try() {
+        let (reachable, _) = reachability("{{try}}{{1}}{{return}}{{catch}}{{2}}{{end}}{{3}}");
+        assert_eq!(reachable, ["{{1}}", "{{2}}", "{{3}}"]);
+    }
+}
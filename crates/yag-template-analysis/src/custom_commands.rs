@@ -0,0 +1,301 @@
+//! Executions of custom commands by ID, as with `execCC`, and the data passed
+//! to them.
+//!
+//! The IDs are only meaningful given a [Manifest] listing the custom commands
+//! in the project. As with database keys, the checks here span several
+//! templates and are run by callers that know about the whole workspace.
+
+use rowan::TextRange;
+use smol_str::SmolStr;
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+
+use crate::checks::string_literal;
+use crate::lint::{LintConfig, LintMeta};
+use crate::manifest::{MANIFEST_FILE_NAME, Manifest};
+use crate::scope::{DotContext, ScopeInfo};
+use crate::{Diagnostic, Severity};
+
+pub const UNKNOWN_CC_ID: LintMeta = LintMeta {
+    id: "unknown-cc-id",
+    default_severity: Severity::Warning,
+    description: "Reports executions of custom commands whose IDs are not listed in the project manifest.",
+    tags: &[],
+};
+
+pub const UNKNOWN_EXEC_DATA_FIELD: LintMeta = LintMeta {
+    id: "unknown-exec-data-field",
+    default_severity: Severity::Warning,
+    description: "Reports fields of `.ExecData` that no execution of the custom command passes in its data.",
+    tags: &[],
+};
+
+/// Functions executing custom commands, along with the index of the parameter
+/// giving the data, if any. The ID is always the first argument.
+const CC_FUNCS: &[(&str, Option<usize>)] = &[
+    ("execCC", Some(3)),
+    ("scheduleUniqueCC", Some(4)),
+    ("cancelScheduledUniqueCC", None),
+];
+
+/// The data passed to a custom command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecData {
+    Nil,
+    /// A dictionary with the given keys, built by `sdict` or `dict`.
+    Dict(Vec<String>),
+    /// A value that is not a dictionary.
+    Other,
+    /// A value that is not known statically.
+    Unknown,
+}
+
+/// A call to a function executing a custom command.
+#[derive(Debug, Clone)]
+pub struct CcCall {
+    pub func: SmolStr,
+    /// The ID of the custom command, if given by a literal.
+    pub id: Option<i64>,
+    /// The range of the ID argument.
+    pub id_range: TextRange,
+    /// The data passed, or `None` if the function does not pass any.
+    pub data: Option<ExecData>,
+}
+
+/// An access to a field of the data the custom command was executed with,
+/// such as `.ExecData.amount`.
+#[derive(Debug, Clone)]
+pub struct ExecDataAccess {
+    pub field: SmolStr,
+    /// The range of the field.
+    pub range: TextRange,
+}
+
+#[derive(Debug, Default)]
+pub struct CustomCommandUsage {
+    /// Executions of other custom commands, in source order.
+    pub calls: Vec<CcCall>,
+    /// Accesses to fields of `.ExecData`, in source order.
+    pub exec_data: Vec<ExecDataAccess>,
+}
+
+impl CustomCommandUsage {
+    /// The call whose ID argument contains `offset`, if any.
+    pub fn call_at(&self, offset: rowan::TextSize) -> Option<&CcCall> {
+        self.calls.iter().find(|call| call.id_range.contains_inclusive(offset))
+    }
+}
+
+pub fn collect(root: &ast::Root, scope_info: &ScopeInfo) -> CustomCommandUsage {
+    let mut usage = CustomCommandUsage::default();
+    for node in root.syntax().descendants() {
+        if let Some(call) = ast::FuncCall::cast(node.clone()) {
+            usage.calls.extend(cc_call(&call));
+        } else if let Some(chain) = ast::ContextFieldChain::cast(node.clone()) {
+            if scope_info.dot_at(chain.syntax().text_range().start()) == DotContext::Root {
+                usage.exec_data.extend(exec_data_access(chain.fields()));
+            }
+        } else if let Some(chain) = ast::ExprFieldChain::cast(node)
+            && let Some(ast::Expr::VarAccess(access)) = chain.base_expr()
+            && access.var().is_some_and(|var| scope_info.is_root_data_var(var))
+        {
+            usage.exec_data.extend(exec_data_access(chain.fields()));
+        }
+    }
+    usage
+}
+
+fn cc_call(call: &ast::FuncCall) -> Option<CcCall> {
+    let func_name = call.func_name()?;
+    let &(func, data_index) = CC_FUNCS.iter().find(|(name, _)| *name == func_name.get())?;
+    let id_expr = call.args().next()?;
+    let id = match &id_expr {
+        ast::Expr::Literal(lit) => match lit.kind() {
+            ast::LiteralKind::Int(int) => int.get(),
+            _ => None,
+        },
+        _ => None,
+    };
+    let data = data_index.map(|index| {
+        call.args()
+            .nth(index)
+            .map_or(ExecData::Unknown, |expr| exec_data(&expr))
+    });
+    Some(CcCall {
+        func: SmolStr::new(func),
+        id,
+        id_range: id_expr.text_range(),
+        data,
+    })
+}
+
+fn exec_data(expr: &ast::Expr) -> ExecData {
+    match expr {
+        ast::Expr::Parenthesized(p) => p.inner_expr().map_or(ExecData::Unknown, |inner| exec_data(&inner)),
+        ast::Expr::Literal(lit) => match lit.kind() {
+            ast::LiteralKind::Nil(_) => ExecData::Nil,
+            _ => ExecData::Other,
+        },
+        ast::Expr::FuncCall(call)
+            if call
+                .func_name()
+                .is_some_and(|name| matches!(name.get(), "sdict" | "dict")) =>
+        {
+            let args: Vec<_> = call.args().collect();
+            if args.len() % 2 != 0 {
+                return ExecData::Unknown;
+            }
+            let keys: Option<Vec<_>> = args.iter().step_by(2).map(string_literal).collect();
+            keys.map_or(ExecData::Unknown, ExecData::Dict)
+        }
+        _ => ExecData::Unknown,
+    }
+}
+
+fn exec_data_access(mut fields: impl Iterator<Item = ast::Field>) -> Option<ExecDataAccess> {
+    fields.next().filter(|field| field.name() == Some("ExecData"))?;
+    let field = fields.next()?;
+    Some(ExecDataAccess {
+        field: SmolStr::new(field.name()?),
+        range: field.text_range(),
+    })
+}
+
+/// Check that the custom commands executed by `calls` are listed in the
+/// manifest.
+pub fn check_calls(calls: &[CcCall], manifest: &Manifest, config: &LintConfig) -> Vec<Diagnostic> {
+    let Some(severity) = config.severity(&UNKNOWN_CC_ID) else {
+        return Vec::new();
+    };
+    calls
+        .iter()
+        .filter_map(|call| {
+            let id = call.id?;
+            manifest.command(id).is_none().then(|| Diagnostic {
+                rule: UNKNOWN_CC_ID.id,
+                severity,
+                message: format!("no custom command with ID {id} is listed in {MANIFEST_FILE_NAME}"),
+                range: call.id_range,
+                tags: UNKNOWN_CC_ID.tags,
+                fix: None,
+                related: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// Check the fields of `.ExecData` accessed by the custom command with the
+/// given ID against the data passed by `callers`, which should include every
+/// call executing it in the workspace.
+pub fn check_exec_data<'a>(
+    id: i64,
+    accesses: &[ExecDataAccess],
+    callers: impl Iterator<Item = &'a CcCall>,
+    config: &LintConfig,
+) -> Vec<Diagnostic> {
+    let Some(severity) = config.severity(&UNKNOWN_EXEC_DATA_FIELD) else {
+        return Vec::new();
+    };
+    let data: Vec<_> = callers
+        // Executions with dynamic IDs may also execute this command.
+        .filter(|call| call.id.is_none_or(|call_id| call_id == id))
+        .filter_map(|call| call.data.as_ref())
+        .collect();
+    if data.is_empty() || data.contains(&&ExecData::Unknown) {
+        return Vec::new();
+    }
+    accesses
+        .iter()
+        .filter(|access| {
+            !data
+                .iter()
+                .any(|data| matches!(data, ExecData::Dict(keys) if keys.iter().any(|key| *key == access.field)))
+        })
+        .map(|access| Diagnostic {
+            rule: UNKNOWN_EXEC_DATA_FIELD.id,
+            severity,
+            message: format!(
+                "no execution of custom command {id} passes {:?} in its data",
+                access.field.as_str()
+            ),
+            range: access.range,
+            tags: UNKNOWN_EXEC_DATA_FIELD.tags,
+            fix: None,
+            related: Vec::new(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::{SyntaxNode, ast, parser};
+
+    use super::{CustomCommandUsage, ExecData, check_calls, check_exec_data, collect};
+    use crate::lint::LintConfig;
+    use crate::manifest::{CustomCommand, Manifest, TriggerType};
+    use crate::scope;
+
+    fn usage(source: &str) -> CustomCommandUsage {
+        let parse = parser::parse(source);
+        assert!(parse.errors.is_empty(), "unexpected errors: {:?}", parse.errors);
+        let root = SyntaxNode::new_root(parse.root).to::<ast::Root>();
+        let scope_info = scope::analyze(root.clone());
+        collect(&root, &scope_info)
+    }
+
+    #[test]
+    fn calls_and_exec_data_are_collected() {
+        let usage = usage(
+            r#"{{execCC 12 nil 0 (sdict "a" 1 "b" 2)}}{{scheduleUniqueCC $id nil 10 "k" $data}}{{cancelScheduledUniqueCC 12 "k"}}{{.ExecData.a}}{{$.ExecData.b}}{{with .Foo}}{{.ExecData.c}}{{end}}{{define "t"}}{{$.ExecData.d}}{{end}}"#,
+        );
+        let calls: Vec<_> = usage.calls.iter().map(|call| (call.id, call.data.clone())).collect();
+        assert_eq!(
+            calls,
+            [
+                (Some(12), Some(ExecData::Dict(vec!["a".to_owned(), "b".to_owned()]))),
+                (None, Some(ExecData::Unknown)),
+                (Some(12), None),
+            ]
+        );
+        let fields: Vec<_> = usage.exec_data.iter().map(|access| access.field.as_str()).collect();
+        assert_eq!(fields, ["a", "b"]);
+    }
+
+    #[test]
+    fn unknown_ids_are_reported() {
+        let manifest = Manifest::new(
+            PathBuf::from("/project/yagcc.toml"),
+            vec![CustomCommand {
+                id: 12,
+                file: PathBuf::from("/project/a.yag"),
+                trigger_type: TriggerType::None,
+                trigger: None,
+            }],
+        );
+        let usage = usage("{{execCC 12 nil 0 nil}}{{execCC 13 nil 0 nil}}");
+        let messages: Vec<_> = check_calls(&usage.calls, &manifest, &LintConfig::default())
+            .into_iter()
+            .map(|diag| diag.message)
+            .collect();
+        assert_eq!(messages, ["no custom command with ID 13 is listed in yagcc.toml"]);
+    }
+
+    #[test]
+    fn exec_data_fields_are_checked_against_callers() {
+        let target = usage("{{.ExecData.amount}}{{.ExecData.amout}}");
+        let callers = usage(r#"{{execCC 12 nil 0 (sdict "amount" 1)}}{{execCC 12 nil 0 nil}}{{execCC 13 nil 0 $x}}"#);
+        let messages: Vec<_> = check_exec_data(12, &target.exec_data, callers.calls.iter(), &LintConfig::default())
+            .into_iter()
+            .map(|diag| diag.message)
+            .collect();
+        assert_eq!(
+            messages,
+            [r#"no execution of custom command 12 passes "amout" in its data"#]
+        );
+
+        let dynamic = usage("{{execCC 12 nil 0 $data}}");
+        assert!(check_exec_data(12, &target.exec_data, dynamic.calls.iter(), &LintConfig::default()).is_empty());
+    }
+}
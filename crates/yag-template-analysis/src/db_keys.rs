@@ -0,0 +1,452 @@
+//! Database keys read and written by a template.
+//!
+//! Custom commands often communicate through the database, so a typo in a key
+//! breaks features silently. Keys given by string literals are recorded
+//! exactly, and those built with `printf` or `joinStr` from a literal format
+//! or separator are recorded as patterns in which the parts that are not known
+//! statically may be anything. Since these checks span several templates, they
+//! are not lints: callers gather the keys of every template in a workspace into
+//! [WorkspaceKeys] and check each template against it.
+
+use std::fmt;
+
+use foldhash::HashSet;
+use rowan::TextRange;
+use smol_str::SmolStr;
+use yag_template_syntax::ast::{self, AstNode};
+
+use crate::checks::string_literal;
+use crate::lint::{LintConfig, LintMeta};
+use crate::{Diagnostic, Severity};
+
+pub const UNWRITTEN_DB_KEY: LintMeta = LintMeta {
+    id: "unwritten-db-key",
+    default_severity: Severity::Warning,
+    description: "Reports database keys that are read but never written by any template in the workspace.",
+    tags: &[],
+};
+
+pub const UNREAD_DB_KEY: LintMeta = LintMeta {
+    id: "unread-db-key",
+    default_severity: Severity::Warning,
+    description: "Reports database keys that are written but never read by any template in the workspace.",
+    tags: &[],
+};
+
+/// Database functions, along with how they access the key and the index of
+/// the parameter giving it.
+const DB_FUNCS: &[(&str, DbAccess, usize)] = &[
+    ("dbGet", DbAccess::Read, 1),
+    ("dbSet", DbAccess::Write, 1),
+    ("dbSetExpire", DbAccess::Write, 1),
+    ("dbIncr", DbAccess::ReadWrite, 1),
+    ("dbDel", DbAccess::Delete, 1),
+    ("dbGetPattern", DbAccess::ReadPattern, 1),
+    ("dbGetPatternReverse", DbAccess::ReadPattern, 1),
+    ("dbTopEntries", DbAccess::ReadPattern, 0),
+    ("dbBottomEntries", DbAccess::ReadPattern, 0),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbAccess {
+    Read,
+    /// A read of all keys matching a pattern in the syntax of SQL `LIKE`.
+    ReadPattern,
+    Write,
+    /// An access that both reads and writes the key, as with `dbIncr`.
+    ReadWrite,
+    Delete,
+}
+
+impl DbAccess {
+    pub fn reads(self) -> bool {
+        matches!(self, DbAccess::Read | DbAccess::ReadPattern | DbAccess::ReadWrite)
+    }
+
+    pub fn writes(self) -> bool {
+        matches!(self, DbAccess::Write | DbAccess::ReadWrite)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KeyPart {
+    Text(String),
+    /// A part of the key that is not known statically.
+    Hole,
+}
+
+/// A database key, possibly with parts that are not known statically.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DbKey {
+    parts: Vec<KeyPart>,
+}
+
+impl DbKey {
+    fn new(parts: Vec<KeyPart>) -> Self {
+        let mut merged: Vec<KeyPart> = Vec::with_capacity(parts.len());
+        for part in parts {
+            match (merged.last_mut(), part) {
+                (_, KeyPart::Text(text)) if text.is_empty() => {}
+                (Some(KeyPart::Text(prev)), KeyPart::Text(text)) => prev.push_str(&text),
+                (Some(KeyPart::Hole), KeyPart::Hole) => {}
+                (_, part) => merged.push(part),
+            }
+        }
+        Self { parts: merged }
+    }
+
+    pub fn parts(&self) -> &[KeyPart] {
+        &self.parts
+    }
+
+    /// The key, if it is known exactly.
+    pub fn literal(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [] => Some(""),
+            [KeyPart::Text(text)] => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Report whether some key is matched by both `self` and `other`.
+    pub fn may_overlap(&self, other: &DbKey) -> bool {
+        let a = self.glob();
+        let b = other.glob();
+        // overlap[i][j] is whether a[i..] and b[j..] have a common match.
+        let mut overlap = vec![vec![false; b.len() + 1]; a.len() + 1];
+        for i in (0..=a.len()).rev() {
+            for j in (0..=b.len()).rev() {
+                overlap[i][j] = match (a.get(i), b.get(j)) {
+                    (None, None) => true,
+                    (Some(None), _) => overlap[i + 1][j] || (j < b.len() && overlap[i][j + 1]),
+                    (_, Some(None)) => overlap[i][j + 1] || (i < a.len() && overlap[i + 1][j]),
+                    (Some(Some(x)), Some(Some(y))) => x == y && overlap[i + 1][j + 1],
+                    _ => false,
+                };
+            }
+        }
+        overlap[0][0]
+    }
+
+    /// The key as a sequence of characters, with `None` for holes.
+    fn glob(&self) -> Vec<Option<char>> {
+        self.parts
+            .iter()
+            .flat_map(|part| match part {
+                KeyPart::Text(text) => text.chars().map(Some).collect(),
+                KeyPart::Hole => vec![None],
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for DbKey {
+    /// Format the key, writing holes as `*`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                KeyPart::Text(text) => f.write_str(text)?,
+                KeyPart::Hole => f.write_str("*")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DbKeyUse {
+    /// The key, or `None` if it is not known statically at all.
+    pub key: Option<DbKey>,
+    pub access: DbAccess,
+    /// The name of the database function called.
+    pub func: SmolStr,
+    /// The range of the key argument.
+    pub range: TextRange,
+    /// Whether the key argument is a string literal.
+    pub is_literal: bool,
+}
+
+/// Collect the database keys used in `root`, in source order.
+pub fn collect(root: &ast::Root) -> Vec<DbKeyUse> {
+    root.syntax()
+        .descendants()
+        .filter_map(ast::FuncCall::cast)
+        .filter_map(|call| {
+            let func_name = call.func_name()?;
+            let &(func, access, key_index) = DB_FUNCS.iter().find(|(name, ..)| *name == func_name.get())?;
+            let key_expr = call.args().nth(key_index)?;
+            let key = if access == DbAccess::ReadPattern {
+                string_literal(&key_expr).map(|pattern| like_pattern(&pattern))
+            } else {
+                key_of(&key_expr)
+            };
+            Some(DbKeyUse {
+                key,
+                access,
+                func: SmolStr::new(func),
+                range: key_expr.text_range(),
+                is_literal: string_literal(&key_expr).is_some(),
+            })
+        })
+        .collect()
+}
+
+fn key_of(expr: &ast::Expr) -> Option<DbKey> {
+    match expr {
+        ast::Expr::Parenthesized(p) => key_of(&p.inner_expr()?),
+        ast::Expr::FuncCall(call) => match call.func_name()?.get() {
+            "printf" => {
+                let format = string_literal(&call.args().next()?)?;
+                Some(printf_key(&format))
+            }
+            "joinStr" => {
+                let mut args = call.args();
+                let sep = string_literal(&args.next()?)?;
+                let mut parts = Vec::new();
+                for (i, arg) in args.enumerate() {
+                    if i > 0 {
+                        parts.push(KeyPart::Text(sep.clone()));
+                    }
+                    parts.push(string_literal(&arg).map_or(KeyPart::Hole, KeyPart::Text));
+                }
+                Some(DbKey::new(parts))
+            }
+            _ => None,
+        },
+        _ => string_literal(expr).map(|key| DbKey::new(vec![KeyPart::Text(key)])),
+    }
+}
+
+/// The key produced by `printf` with the given format, in which each verb is
+/// a hole.
+fn printf_key(format: &str) -> DbKey {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+        // Skip flags, width, precision, and argument indices up to the verb.
+        match chars.find(|c| c.is_ascii_alphabetic() || *c == '%') {
+            Some('%') => text.push('%'),
+            _ => {
+                parts.push(KeyPart::Text(std::mem::take(&mut text)));
+                parts.push(KeyPart::Hole);
+            }
+        }
+    }
+    parts.push(KeyPart::Text(text));
+    DbKey::new(parts)
+}
+
+/// The keys matched by a pattern in the syntax of SQL `LIKE`, approximating
+/// `_` (any single character) by a hole.
+fn like_pattern(pattern: &str) -> DbKey {
+    let parts = pattern
+        .chars()
+        .map(|c| match c {
+            '%' | '_' => KeyPart::Hole,
+            c => KeyPart::Text(c.to_string()),
+        })
+        .collect();
+    DbKey::new(parts)
+}
+
+/// The database keys used by all templates in a workspace, along with those
+/// that are read but never written or vice versa. Finding these compares keys
+/// pairwise, so it is done once for the workspace rather than per template.
+#[derive(Debug, Default)]
+pub struct WorkspaceKeys {
+    unwritten: HashSet<DbKey>,
+    unread: HashSet<DbKey>,
+}
+
+impl WorkspaceKeys {
+    pub fn new<'a>(uses: impl IntoIterator<Item = &'a DbKeyUse>) -> Self {
+        let mut reads = KeySet::default();
+        let mut writes = KeySet::default();
+        let mut read_keys = HashSet::default();
+        let mut written_keys = HashSet::default();
+        for u in uses {
+            if u.access.reads() {
+                reads.insert(u.key.as_ref());
+            }
+            if u.access.writes() {
+                writes.insert(u.key.as_ref());
+            }
+            match (&u.key, u.access) {
+                (Some(key), DbAccess::Read) => read_keys.insert(key.clone()),
+                (Some(key), DbAccess::Write) => written_keys.insert(key.clone()),
+                _ => false,
+            };
+        }
+        Self {
+            unwritten: read_keys.into_iter().filter(|key| !writes.may_contain(key)).collect(),
+            unread: written_keys.into_iter().filter(|key| !reads.may_contain(key)).collect(),
+        }
+    }
+
+    /// Check the database keys used by one template, which should be among
+    /// those the workspace keys were built from.
+    pub fn check(&self, uses: &[DbKeyUse], config: &LintConfig) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for u in uses {
+            let Some(key) = &u.key else {
+                continue;
+            };
+            let (meta, message) = match u.access {
+                DbAccess::Read if self.unwritten.contains(key) => (
+                    UNWRITTEN_DB_KEY,
+                    format!(
+                        "database key {:?} is read but never written in the workspace",
+                        key.to_string()
+                    ),
+                ),
+                DbAccess::Write if self.unread.contains(key) => (
+                    UNREAD_DB_KEY,
+                    format!(
+                        "database key {:?} is written but never read in the workspace",
+                        key.to_string()
+                    ),
+                ),
+                _ => continue,
+            };
+            let Some(severity) = config.severity(&meta) else {
+                continue;
+            };
+            diagnostics.push(Diagnostic {
+                rule: meta.id,
+                severity,
+                message,
+                range: u.range,
+                tags: meta.tags,
+                fix: None,
+                related: Vec::new(),
+            });
+        }
+        diagnostics
+    }
+}
+
+/// A set of database keys that may contain patterns.
+#[derive(Debug, Default)]
+struct KeySet {
+    /// Whether the set contains a key that is not known statically, and so
+    /// may be any key.
+    has_dynamic: bool,
+    literals: HashSet<String>,
+    patterns: Vec<DbKey>,
+}
+
+impl KeySet {
+    fn insert(&mut self, key: Option<&DbKey>) {
+        match key {
+            None => self.has_dynamic = true,
+            Some(key) => match key.literal() {
+                Some(literal) => {
+                    self.literals.insert(literal.to_owned());
+                }
+                None => self.patterns.push(key.clone()),
+            },
+        }
+    }
+
+    /// Report whether some key matched by `key` may be in the set.
+    fn may_contain(&self, key: &DbKey) -> bool {
+        if self.has_dynamic || self.patterns.iter().any(|pattern| key.may_overlap(pattern)) {
+            return true;
+        }
+        match key.literal() {
+            // Distinct literals never overlap.
+            Some(literal) => self.literals.contains(literal),
+            None => self
+                .literals
+                .iter()
+                .any(|literal| key.may_overlap(&DbKey::new(vec![KeyPart::Text(literal.clone())]))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::{SyntaxNode, ast, parser};
+
+    use super::{DbAccess, DbKeyUse, WorkspaceKeys, collect};
+    use crate::lint::LintConfig;
+
+    fn uses(source: &str) -> Vec<DbKeyUse> {
+        let parse = parser::parse(source);
+        assert!(parse.errors.is_empty(), "unexpected errors: {:?}", parse.errors);
+        collect(&SyntaxNode::new_root(parse.root).to::<ast::Root>())
+    }
+
+    #[test]
+    fn keys_are_collected() {
+        let uses = uses(
+            r#"{{dbSet 0 "a" 1}}{{dbGet .User.ID (printf "xp_%d_%%" .User.ID)}}{{dbIncr 0 (joinStr "_" "n" $x) 1}}{{dbDel 0 $key}}{{dbTopEntries "xp:%" 10 0}}"#,
+        );
+        let keys: Vec<_> = uses
+            .iter()
+            .map(|u| (u.key.as_ref().map(|key| key.to_string()), u.access, u.is_literal))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                (Some("a".to_owned()), DbAccess::Write, true),
+                (Some("xp_*_%".to_owned()), DbAccess::Read, false),
+                (Some("n_*".to_owned()), DbAccess::ReadWrite, false),
+                (None, DbAccess::Delete, false),
+                (Some("xp:*".to_owned()), DbAccess::ReadPattern, true),
+            ]
+        );
+        assert_eq!(uses[0].key.as_ref().unwrap().literal(), Some("a"));
+    }
+
+    #[test]
+    fn keys_overlap_with_matching_patterns() {
+        let uses =
+            uses(r#"{{dbGet 0 "xp_1"}}{{dbGet 0 (printf "xp_%d" 1)}}{{dbGet 0 (printf "%s_1" "a")}}{{dbGet 0 "cd"}}"#);
+        let key = |i: usize| uses[i].key.clone().unwrap();
+        assert!(key(0).may_overlap(&key(1)));
+        assert!(key(1).may_overlap(&key(2)));
+        assert!(key(0).may_overlap(&key(2)));
+        assert!(!key(0).may_overlap(&key(3)));
+    }
+
+    #[test]
+    fn unmatched_keys_are_reported_across_templates() {
+        let first = uses(r#"{{dbSet 0 "score" 1}}{{dbSet 0 "typo" 1}}{{dbGet 0 (printf "xp_%d" 1)}}"#);
+        let second = uses(r#"{{dbGet 0 "score"}}{{dbSet 0 "xp_1" 2}}{{dbGet 0 "nope"}}"#);
+        let workspace = WorkspaceKeys::new(first.iter().chain(second.iter()));
+        let messages: Vec<_> = workspace
+            .check(&first, &LintConfig::default())
+            .into_iter()
+            .chain(workspace.check(&second, &LintConfig::default()))
+            .map(|diag| (diag.rule, diag.message))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (
+                    "unread-db-key",
+                    r#"database key "typo" is written but never read in the workspace"#.to_owned()
+                ),
+                (
+                    "unwritten-db-key",
+                    r#"database key "nope" is read but never written in the workspace"#.to_owned()
+                ),
+            ]
+        );
+
+        let dynamic = uses(r#"{{dbGet 0 $key}}"#);
+        assert!(
+            WorkspaceKeys::new(first.iter().chain(dynamic.iter()))
+                .check(&first, &LintConfig::default())
+                .iter()
+                .all(|diag| diag.rule != "unread-db-key")
+        );
+    }
+}
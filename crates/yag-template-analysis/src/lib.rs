@@ -1,75 +1,194 @@
 use core::fmt;
-use std::error::Error;
 
+use command_args::CommandArgs;
+use control_flow::ControlFlowGraph;
+use custom_commands::CustomCommandUsage;
+use db_keys::DbKeyUse;
+use lint::{LintConfig, LintContext, LintRegistry};
 use rowan::TextRange;
 use scope::ScopeInfo;
+use templates::TemplateTable;
+use types::{Type, TypeInfo};
 use yag_template_envdefs::EnvDefs;
 use yag_template_syntax::ast;
 
 pub mod checks;
+pub mod command_args;
+pub mod control_flow;
+pub mod custom_commands;
+pub mod db_keys;
+pub mod lint;
+pub mod manifest;
+pub mod regex;
 pub mod scope;
+pub mod size;
+pub mod suppress;
+pub mod templates;
+pub mod triggers;
+pub mod types;
 
 pub struct Analysis {
     pub scope_info: ScopeInfo,
-    pub errors: Vec<AnalysisError>,
-    pub warnings: Vec<AnalysisWarning>,
+    pub types: TypeInfo,
+    pub control_flow: ControlFlowGraph,
+    pub templates: TemplateTable,
+    pub command_args: CommandArgs,
+    /// The database keys used, in source order.
+    pub db_keys: Vec<DbKeyUse>,
+    pub custom_commands: CustomCommandUsage,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
-pub fn analyze(env: &EnvDefs, root: ast::Root) -> Analysis {
-    let (scope_info, mut errors, mut warnings) = scope::analyze(root.clone());
-    errors.extend(checks::undefined_funcs::check(env, &root));
-    warnings.extend(checks::deprecated_funcs::check(env, &root));
-    Analysis {
-        scope_info,
-        errors,
-        warnings,
+impl Analysis {
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diag| diag.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|diag| diag.severity != Severity::Error)
+    }
+
+    /// The inferred type of `expr`, or [Type::Any] if it is unknown.
+    pub fn type_of(&self, expr: &ast::Expr) -> Type {
+        self.types.type_of(expr)
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct AnalysisError {
-    pub message: String,
-    pub range: TextRange,
+/// Analyze `root`, running the built-in lints with their default severities.
+pub fn analyze(env: &EnvDefs, root: ast::Root) -> Analysis {
+    analyze_with(env, root, &LintRegistry::default(), &LintConfig::default())
 }
 
-impl AnalysisError {
-    pub fn new(message: impl Into<String>, range: TextRange) -> Self {
-        Self {
-            message: message.into(),
-            range,
-        }
+/// Analyze `root`, running the lints in `registry` that are enabled by `config`.
+pub fn analyze_with(env: &EnvDefs, root: ast::Root, registry: &LintRegistry, config: &LintConfig) -> Analysis {
+    let scope_info = scope::analyze(root.clone());
+    let command_args = command_args::collect(&root, &scope_info);
+    let types = types::infer(env, &root, &scope_info, &command_args);
+    let control_flow = control_flow::build(&root);
+    let templates = templates::build(&root);
+    let db_keys = db_keys::collect(&root);
+    let custom_commands = custom_commands::collect(&root, &scope_info);
+    let mut analysis = Analysis {
+        scope_info,
+        types,
+        control_flow,
+        templates,
+        command_args,
+        db_keys,
+        custom_commands,
+        diagnostics: Vec::new(),
+    };
+
+    let mut diagnostics = Vec::new();
+    for lint in registry.lints() {
+        let meta = lint.meta();
+        let Some(severity) = config.severity(meta) else {
+            continue;
+        };
+        let mut cx = LintContext::new(env, &root, &analysis, config, meta, severity);
+        lint.check(&mut cx);
+        diagnostics.extend(cx.finish());
     }
+    let mut diagnostics = suppress::apply(&root, registry, config, diagnostics);
+    diagnostics.sort_by_key(|diag| (diag.range.start(), diag.range.end()));
+    analysis.diagnostics = diagnostics;
+    analysis
 }
 
-impl fmt::Display for AnalysisError {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.message.fmt(f)
+        f.write_str(match self {
+            Severity::Hint => "hint",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
     }
 }
 
-impl Error for AnalysisError {}
+/// Additional information about a diagnostic that editors may use to render it
+/// specially.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticTag {
+    /// The code is deprecated, e.g., it calls a deprecated function.
+    Deprecated,
+    /// The code is unnecessary, e.g., it declares an unused variable.
+    Unnecessary,
+}
 
 #[derive(Debug, Clone)]
-pub struct AnalysisWarning {
+pub struct Diagnostic {
+    /// The id of the lint that produced the diagnostic.
+    pub rule: &'static str,
+    pub severity: Severity,
     pub message: String,
     pub range: TextRange,
-    pub is_deprecation: bool,
+    pub tags: &'static [DiagnosticTag],
+    pub fix: Option<Fix>,
+    /// Other locations relevant to the diagnostic, such as the origin of a
+    /// value it concerns.
+    pub related: Vec<RelatedInfo>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+/// A location related to a diagnostic, with a message explaining its
+/// relevance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatedInfo {
+    pub range: TextRange,
+    pub message: String,
 }
 
-impl AnalysisWarning {
-    pub fn new(message: impl Into<String>, range: TextRange) -> Self {
+impl RelatedInfo {
+    pub fn new(range: TextRange, message: impl Into<String>) -> Self {
         Self {
-            message: message.into(),
             range,
-            is_deprecation: false,
+            message: message.into(),
+        }
+    }
+}
+
+/// A suggested edit resolving a diagnostic.
+#[derive(Debug, Clone)]
+pub struct Fix {
+    /// A short description of the fix, suitable for display in a menu.
+    pub title: String,
+    pub edits: Vec<TextEdit>,
+}
+
+impl Fix {
+    pub fn new(title: impl Into<String>, edits: Vec<TextEdit>) -> Self {
+        Self {
+            title: title.into(),
+            edits,
         }
     }
+}
 
-    pub fn new_deprecation(message: impl Into<String>, range: TextRange) -> Self {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: TextRange,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    pub fn replace(range: TextRange, new_text: impl Into<String>) -> Self {
         Self {
-            message: message.into(),
             range,
-            is_deprecation: true,
+            new_text: new_text.into(),
         }
     }
 }
@@ -0,0 +1,291 @@
+//! Pluggable lint rules.
+//!
+//! Each rule implements [Lint] and is described by a [LintMeta] giving its id,
+//! default severity, and documentation. A [LintRegistry] holds the set of
+//! rules that may run, and a [LintConfig] enables, disables, or adjusts the
+//! severity of individual rules by id.
+
+use foldhash::{HashMap, HashMapExt};
+use rowan::TextRange;
+use yag_template_envdefs::{EnvDefs, TriggerKind};
+use yag_template_syntax::ast;
+
+use crate::command_args::CommandArgs;
+use crate::control_flow::ControlFlowGraph;
+use crate::scope::ScopeInfo;
+use crate::templates::TemplateTable;
+use crate::types::TypeInfo;
+use crate::{Analysis, Diagnostic, DiagnosticTag, Fix, RelatedInfo, Severity, checks, custom_commands, db_keys, size};
+
+/// Rules spanning several templates, which are checked by callers that know
+/// about the whole workspace rather than by [crate::analyze].
+pub const WORKSPACE_LINTS: &[LintMeta] = &[
+    db_keys::UNWRITTEN_DB_KEY,
+    db_keys::UNREAD_DB_KEY,
+    custom_commands::UNKNOWN_CC_ID,
+    custom_commands::UNKNOWN_EXEC_DATA_FIELD,
+];
+
+/// Static information about a lint rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LintMeta {
+    /// A unique, kebab-case identifier for the rule, e.g., `unused-variable`.
+    pub id: &'static str,
+    pub default_severity: Severity,
+    /// A one-sentence description of what the rule checks.
+    pub description: &'static str,
+    /// Tags applied to all diagnostics produced by the rule.
+    pub tags: &'static [DiagnosticTag],
+}
+
+pub trait Lint: Send + Sync {
+    fn meta(&self) -> &'static LintMeta;
+
+    /// Check the template in `cx`, reporting problems via [LintContext::report].
+    fn check(&self, cx: &mut LintContext<'_>);
+}
+
+/// The information available to a lint while checking a template.
+pub struct LintContext<'a> {
+    pub env: &'a EnvDefs,
+    pub root: &'a ast::Root,
+    pub scope_info: &'a ScopeInfo,
+    pub types: &'a TypeInfo,
+    pub control_flow: &'a ControlFlowGraph,
+    pub templates: &'a TemplateTable,
+    pub command_args: &'a CommandArgs,
+    pub config: &'a LintConfig,
+    meta: &'static LintMeta,
+    severity: Severity,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> LintContext<'a> {
+    /// Create a context for a lint, using the results of the analyses already
+    /// performed in `analysis`.
+    pub(crate) fn new(
+        env: &'a EnvDefs,
+        root: &'a ast::Root,
+        analysis: &'a Analysis,
+        config: &'a LintConfig,
+        meta: &'static LintMeta,
+        severity: Severity,
+    ) -> Self {
+        Self {
+            env,
+            root,
+            scope_info: &analysis.scope_info,
+            types: &analysis.types,
+            control_flow: &analysis.control_flow,
+            templates: &analysis.templates,
+            command_args: &analysis.command_args,
+            config,
+            meta,
+            severity,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn report(&mut self, message: impl Into<String>, range: TextRange) {
+        self.push(message.into(), range, None, Vec::new());
+    }
+
+    pub fn report_with_fix(&mut self, message: impl Into<String>, range: TextRange, fix: Fix) {
+        self.push(message.into(), range, Some(fix), Vec::new());
+    }
+
+    /// Report a problem along with other locations relevant to it.
+    pub fn report_with_related(&mut self, message: impl Into<String>, range: TextRange, related: Vec<RelatedInfo>) {
+        self.push(message.into(), range, None, related);
+    }
+
+    fn push(&mut self, message: String, range: TextRange, fix: Option<Fix>, related: Vec<RelatedInfo>) {
+        self.diagnostics.push(Diagnostic {
+            rule: self.meta.id,
+            severity: self.severity,
+            message,
+            range,
+            tags: self.meta.tags,
+            fix,
+            related,
+        });
+    }
+
+    pub(crate) fn finish(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+}
+
+/// The set of lint rules that may run during analysis.
+pub struct LintRegistry {
+    lints: Vec<Box<dyn Lint>>,
+}
+
+impl LintRegistry {
+    /// Create a registry with no rules.
+    pub fn empty() -> Self {
+        Self { lints: Vec::new() }
+    }
+
+    /// Register `lint`, which must not share an id with a previously
+    /// registered rule.
+    pub fn register(&mut self, lint: impl Lint + 'static) {
+        let id = lint.meta().id;
+        assert!(self.get(id).is_none(), "lint {id} is already registered");
+        self.lints.push(Box::new(lint));
+    }
+
+    pub fn lints(&self) -> impl Iterator<Item = &dyn Lint> {
+        self.lints.iter().map(|lint| lint.as_ref())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&'static LintMeta> {
+        self.lints().map(|lint| lint.meta()).find(|meta| meta.id == id)
+    }
+}
+
+impl Default for LintRegistry {
+    /// Create a registry with all built-in rules.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        checks::register_builtin_lints(&mut registry);
+        registry
+    }
+}
+
+/// Per-rule overrides of the default severity, along with options for
+/// individual rules. Rules not mentioned run at their default severity.
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    levels: HashMap<String, Option<Severity>>,
+    char_limit: usize,
+    premium: bool,
+    fallback_triggers: Vec<TriggerKind>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self {
+            levels: HashMap::new(),
+            char_limit: size::DEFAULT_CHAR_LIMIT,
+            premium: false,
+            fallback_triggers: Vec::new(),
+        }
+    }
+
+    /// The maximum length of the template in characters, checked by the
+    /// `size-limit` rule.
+    pub fn char_limit(&self) -> usize {
+        self.char_limit
+    }
+
+    pub fn set_char_limit(&mut self, limit: usize) {
+        self.char_limit = limit;
+    }
+
+    /// Whether the template runs on a server with YAGPDB premium, which raises
+    /// some of the limits checked by the `call-limit` rule.
+    pub fn premium(&self) -> bool {
+        self.premium
+    }
+
+    pub fn set_premium(&mut self, premium: bool) {
+        self.premium = premium;
+    }
+
+    /// The kinds of trigger the template runs for if it does not declare them
+    /// with a `yag-trigger` directive, checked by the `invalid-for-trigger`
+    /// rule. If there are none, uses are not checked.
+    pub fn fallback_triggers(&self) -> &[TriggerKind] {
+        &self.fallback_triggers
+    }
+
+    pub fn set_fallback_triggers(&mut self, kinds: Vec<TriggerKind>) {
+        self.fallback_triggers = kinds;
+    }
+
+    pub fn disable(&mut self, id: impl Into<String>) {
+        self.levels.insert(id.into(), None);
+    }
+
+    pub fn set_severity(&mut self, id: impl Into<String>, severity: Severity) {
+        self.levels.insert(id.into(), Some(severity));
+    }
+
+    /// The severity at which the rule should run, or `None` if it is disabled.
+    pub fn severity(&self, meta: &LintMeta) -> Option<Severity> {
+        match self.levels.get(meta.id) {
+            Some(level) => *level,
+            None => Some(meta.default_severity),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yag_template_envdefs::{EnvDefSource, EnvDefs};
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::{SyntaxNode, ast, parser};
+
+    use super::{LintConfig, LintRegistry};
+    use crate::{Analysis, Severity, analyze_with};
+
+    fn envdefs() -> EnvDefs {
+        yag_template_envdefs::parse(&[EnvDefSource::new_static(
+            "test.ydef",
+            "func print(args...)\nfunc old()\n\tDeprecated: use print instead.\n",
+        )])
+        .unwrap()
+    }
+
+    fn analyze_source(source: &str, config: &LintConfig) -> Analysis {
+        let parse = parser::parse(source);
+        let root = SyntaxNode::new_root(parse.root).to::<ast::Root>();
+        analyze_with(&envdefs(), root, &LintRegistry::default(), config)
+    }
+
+    fn rules(analysis: &Analysis) -> Vec<&'static str> {
+        analysis.diagnostics.iter().map(|diag| diag.rule).collect()
+    }
+
+    #[test]
+    fn builtin_lints_have_unique_ids() {
+        let registry = LintRegistry::default();
+        let mut ids: Vec<_> = registry.lints().map(|lint| lint.meta().id).collect();
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count);
+    }
+
+    #[test]
+    fn diagnostics_carry_rule_ids() {
+        let analysis = analyze_source("{{$x := old}}{{unknown $y}}", &LintConfig::default());
+        assert_eq!(
+            rules(&analysis),
+            [
+                "unused-variable",
+                "deprecated-function",
+                "undefined-function",
+                "undefined-variable"
+            ]
+        );
+        assert_eq!(analysis.errors().count(), 2);
+    }
+
+    #[test]
+    fn config_disables_and_overrides_rules() {
+        let mut config = LintConfig::new();
+        config.disable("unused-variable");
+        config.set_severity("deprecated-function", Severity::Error);
+        let analysis = analyze_source("{{$x := old}}", &config);
+        assert_eq!(rules(&analysis), ["deprecated-function"]);
+        assert_eq!(analysis.diagnostics[0].severity, Severity::Error);
+    }
+}
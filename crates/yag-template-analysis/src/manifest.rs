@@ -0,0 +1,132 @@
+//! The project manifest, which maps template files to the custom commands they
+//! implement.
+//!
+//! Reading the manifest from disk is left to callers, which construct a
+//! [Manifest] from the commands it lists.
+
+use std::path::{Path, PathBuf};
+
+use yag_template_envdefs::TriggerKind;
+
+/// The filename of the manifest, searched for in workspace roots.
+pub const MANIFEST_FILE_NAME: &str = "yagcc.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerType {
+    None,
+    Command,
+    StartsWith,
+    Contains,
+    Regex,
+    Exact,
+    Reaction,
+    Interval,
+    Component,
+    Modal,
+}
+
+impl TriggerType {
+    pub const ALL: &[TriggerType] = &[
+        TriggerType::None,
+        TriggerType::Command,
+        TriggerType::StartsWith,
+        TriggerType::Contains,
+        TriggerType::Regex,
+        TriggerType::Exact,
+        TriggerType::Reaction,
+        TriggerType::Interval,
+        TriggerType::Component,
+        TriggerType::Modal,
+    ];
+
+    pub fn from_name(name: &str) -> Option<TriggerType> {
+        Self::ALL.iter().copied().find(|ty| ty.name() == name)
+    }
+
+    /// The name of the trigger type as written in the manifest.
+    pub fn name(self) -> &'static str {
+        match self {
+            TriggerType::None => "none",
+            TriggerType::Command => "command",
+            TriggerType::StartsWith => "starts_with",
+            TriggerType::Contains => "contains",
+            TriggerType::Regex => "regex",
+            TriggerType::Exact => "exact",
+            TriggerType::Reaction => "reaction",
+            TriggerType::Interval => "interval",
+            TriggerType::Component => "component",
+            TriggerType::Modal => "modal",
+        }
+    }
+
+    /// A human-readable name of the trigger type.
+    pub fn description(self) -> &'static str {
+        match self {
+            TriggerType::None => "None",
+            TriggerType::Command => "Command",
+            TriggerType::StartsWith => "Starts with",
+            TriggerType::Contains => "Contains",
+            TriggerType::Regex => "Regex",
+            TriggerType::Exact => "Exact match",
+            TriggerType::Reaction => "Reaction",
+            TriggerType::Interval => "Interval",
+            TriggerType::Component => "Message component",
+            TriggerType::Modal => "Modal submission",
+        }
+    }
+
+    /// The kind of trigger, or `None` if the command is never triggered by
+    /// itself, in which case it only runs when executed by other commands.
+    pub fn kind(self) -> Option<TriggerKind> {
+        match self {
+            TriggerType::None => None,
+            TriggerType::Command
+            | TriggerType::StartsWith
+            | TriggerType::Contains
+            | TriggerType::Regex
+            | TriggerType::Exact => Some(TriggerKind::Message),
+            TriggerType::Reaction => Some(TriggerKind::Reaction),
+            TriggerType::Interval => Some(TriggerKind::Interval),
+            TriggerType::Component => Some(TriggerKind::Component),
+            TriggerType::Modal => Some(TriggerKind::Modal),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomCommand {
+    pub id: i64,
+    /// The absolute path of the template implementing the command.
+    pub file: PathBuf,
+    pub trigger_type: TriggerType,
+    pub trigger: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    /// The path of the manifest file.
+    pub path: PathBuf,
+    commands: Vec<CustomCommand>,
+}
+
+impl Manifest {
+    /// Create the manifest at `path` listing `commands`, whose IDs should be
+    /// distinct.
+    pub fn new(path: PathBuf, commands: Vec<CustomCommand>) -> Self {
+        Self { path, commands }
+    }
+
+    pub fn commands(&self) -> &[CustomCommand] {
+        &self.commands
+    }
+
+    pub fn command(&self, id: i64) -> Option<&CustomCommand> {
+        self.commands.iter().find(|command| command.id == id)
+    }
+
+    /// The custom command implemented by the template at `path`, if any. The
+    /// paths are compared exactly, so callers should normalize them alike.
+    pub fn command_for_file(&self, path: &Path) -> Option<&CustomCommand> {
+        self.commands.iter().find(|command| command.file == path)
+    }
+}
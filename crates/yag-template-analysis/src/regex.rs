@@ -0,0 +1,1497 @@
+//! Regular expressions passed to the `re*` functions, which use the RE2 syntax
+//! of Go's `regexp` package.
+//!
+//! The parser follows Go's `regexp/syntax` closely enough to reject the same
+//! patterns, but keeps only as much structure as is needed to explain a
+//! pattern and to resolve references to its capture groups.
+
+use std::fmt::Write;
+use std::mem;
+
+use rowan::{TextRange, TextSize};
+use yag_template_syntax::ast;
+
+/// Functions taking a regular expression as their first argument, along with
+/// the index of the parameter giving a replacement template, if any.
+pub const REGEX_FUNCS: &[(&str, Option<usize>)] = &[
+    ("reFind", None),
+    ("reFindAll", None),
+    ("reFindAllSubmatches", None),
+    ("reReplace", Some(2)),
+    ("reSplit", None),
+];
+
+/// Go rejects repetition counts above this.
+const MAX_REPEAT: u32 = 1000;
+
+/// Go rejects patterns nested more deeply than this.
+const MAX_NESTING: usize = 1000;
+
+const POSIX_CLASSES: &[&str] = &[
+    "alnum", "alpha", "ascii", "blank", "cntrl", "digit", "graph", "lower", "print", "punct", "space", "upper", "word",
+    "xdigit",
+];
+
+/// The Unicode general categories and scripts known to Go, along with the
+/// aliases accepted by recent versions.
+const UNICODE_CLASSES: &[&str] = &[
+    // General categories
+    "C",
+    "Cc",
+    "Cf",
+    "Cn",
+    "Co",
+    "Cs",
+    "L",
+    "LC",
+    "Ll",
+    "Lm",
+    "Lo",
+    "Lt",
+    "Lu",
+    "M",
+    "Mc",
+    "Me",
+    "Mn",
+    "N",
+    "Nd",
+    "Nl",
+    "No",
+    "P",
+    "Pc",
+    "Pd",
+    "Pe",
+    "Pf",
+    "Pi",
+    "Po",
+    "Ps",
+    "S",
+    "Sc",
+    "Sk",
+    "Sm",
+    "So",
+    "Z",
+    "Zl",
+    "Zp",
+    "Zs",
+    // Aliases
+    "Any",
+    "ASCII",
+    "Assigned",
+    "Other",
+    "Control",
+    "Format",
+    "Unassigned",
+    "Private_Use",
+    "Surrogate",
+    "Letter",
+    "Cased_Letter",
+    "Lowercase_Letter",
+    "Modifier_Letter",
+    "Other_Letter",
+    "Titlecase_Letter",
+    "Uppercase_Letter",
+    "Mark",
+    "Combining_Mark",
+    "Spacing_Mark",
+    "Enclosing_Mark",
+    "Nonspacing_Mark",
+    "Number",
+    "Decimal_Number",
+    "Digit",
+    "Letter_Number",
+    "Other_Number",
+    "Punctuation",
+    "Punct",
+    "Connector_Punctuation",
+    "Dash_Punctuation",
+    "Close_Punctuation",
+    "Final_Punctuation",
+    "Initial_Punctuation",
+    "Other_Punctuation",
+    "Open_Punctuation",
+    "Symbol",
+    "Currency_Symbol",
+    "Modifier_Symbol",
+    "Math_Symbol",
+    "Other_Symbol",
+    "Separator",
+    "Line_Separator",
+    "Paragraph_Separator",
+    "Space_Separator",
+    // Scripts
+    "Adlam",
+    "Ahom",
+    "Anatolian_Hieroglyphs",
+    "Arabic",
+    "Armenian",
+    "Avestan",
+    "Balinese",
+    "Bamum",
+    "Bassa_Vah",
+    "Batak",
+    "Bengali",
+    "Bhaiksuki",
+    "Bopomofo",
+    "Brahmi",
+    "Braille",
+    "Buginese",
+    "Buhid",
+    "Canadian_Aboriginal",
+    "Carian",
+    "Caucasian_Albanian",
+    "Chakma",
+    "Cham",
+    "Cherokee",
+    "Chorasmian",
+    "Common",
+    "Coptic",
+    "Cuneiform",
+    "Cypriot",
+    "Cypro_Minoan",
+    "Cyrillic",
+    "Deseret",
+    "Devanagari",
+    "Dives_Akuru",
+    "Dogra",
+    "Duployan",
+    "Egyptian_Hieroglyphs",
+    "Elbasan",
+    "Elymaic",
+    "Ethiopic",
+    "Georgian",
+    "Glagolitic",
+    "Gothic",
+    "Grantha",
+    "Greek",
+    "Gujarati",
+    "Gunjala_Gondi",
+    "Gurmukhi",
+    "Han",
+    "Hangul",
+    "Hanifi_Rohingya",
+    "Hanunoo",
+    "Hatran",
+    "Hebrew",
+    "Hiragana",
+    "Imperial_Aramaic",
+    "Inherited",
+    "Inscriptional_Pahlavi",
+    "Inscriptional_Parthian",
+    "Javanese",
+    "Kaithi",
+    "Kannada",
+    "Katakana",
+    "Kawi",
+    "Kayah_Li",
+    "Kharoshthi",
+    "Khitan_Small_Script",
+    "Khmer",
+    "Khojki",
+    "Khudawadi",
+    "Lao",
+    "Latin",
+    "Lepcha",
+    "Limbu",
+    "Linear_A",
+    "Linear_B",
+    "Lisu",
+    "Lycian",
+    "Lydian",
+    "Mahajani",
+    "Makasar",
+    "Malayalam",
+    "Mandaic",
+    "Manichaean",
+    "Marchen",
+    "Masaram_Gondi",
+    "Medefaidrin",
+    "Meetei_Mayek",
+    "Mende_Kikakui",
+    "Meroitic_Cursive",
+    "Meroitic_Hieroglyphs",
+    "Miao",
+    "Modi",
+    "Mongolian",
+    "Mro",
+    "Multani",
+    "Myanmar",
+    "Nabataean",
+    "Nag_Mundari",
+    "Nandinagari",
+    "New_Tai_Lue",
+    "Newa",
+    "Nko",
+    "Nushu",
+    "Nyiakeng_Puachue_Hmong",
+    "Ogham",
+    "Ol_Chiki",
+    "Old_Hungarian",
+    "Old_Italic",
+    "Old_North_Arabian",
+    "Old_Permic",
+    "Old_Persian",
+    "Old_Sogdian",
+    "Old_South_Arabian",
+    "Old_Turkic",
+    "Old_Uyghur",
+    "Oriya",
+    "Osage",
+    "Osmanya",
+    "Pahawh_Hmong",
+    "Palmyrene",
+    "Pau_Cin_Hau",
+    "Phags_Pa",
+    "Phoenician",
+    "Psalter_Pahlavi",
+    "Rejang",
+    "Runic",
+    "Samaritan",
+    "Saurashtra",
+    "Sharada",
+    "Shavian",
+    "Siddham",
+    "SignWriting",
+    "Sinhala",
+    "Sogdian",
+    "Sora_Sompeng",
+    "Soyombo",
+    "Sundanese",
+    "Syloti_Nagri",
+    "Syriac",
+    "Tagalog",
+    "Tagbanwa",
+    "Tai_Le",
+    "Tai_Tham",
+    "Tai_Viet",
+    "Takri",
+    "Tamil",
+    "Tangsa",
+    "Tangut",
+    "Telugu",
+    "Thaana",
+    "Thai",
+    "Tibetan",
+    "Tifinagh",
+    "Tirhuta",
+    "Toto",
+    "Ugaritic",
+    "Vai",
+    "Vithkuqi",
+    "Wancho",
+    "Warang_Citi",
+    "Yezidi",
+    "Yi",
+    "Zanabazar_Square",
+];
+
+/// The string literal giving the pattern passed to `call`, if `call` is to
+/// one of [REGEX_FUNCS].
+pub fn regex_literal(call: &ast::FuncCall) -> Option<ast::StringLiteral> {
+    regex_func(call)?;
+    string_arg(call, 0)
+}
+
+/// The string literal giving the replacement template passed to `call`, if
+/// `call` is to one of [REGEX_FUNCS] accepting one.
+pub fn replacement_literal(call: &ast::FuncCall) -> Option<ast::StringLiteral> {
+    string_arg(call, regex_func(call)??)
+}
+
+fn regex_func(call: &ast::FuncCall) -> Option<Option<usize>> {
+    let name = call.func_name()?;
+    REGEX_FUNCS
+        .iter()
+        .find(|(func, _)| *func == name.get())
+        .map(|&(_, replacement_index)| replacement_index)
+}
+
+fn string_arg(call: &ast::FuncCall, index: usize) -> Option<ast::StringLiteral> {
+    match call.args().nth(index)? {
+        ast::Expr::Literal(lit) => match lit.kind() {
+            ast::LiteralKind::String(s) => Some(s),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A syntax error in a pattern, with a range relative to the pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexError {
+    pub message: String,
+    pub range: TextRange,
+}
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    pub root: Node,
+    /// The names of the capture groups, in order; unnamed groups are `None`.
+    captures: Vec<Option<String>>,
+}
+
+impl Regex {
+    pub fn capture_count(&self) -> usize {
+        self.captures.len()
+    }
+
+    pub fn has_capture_named(&self, name: &str) -> bool {
+        self.captures.iter().any(|capture| capture.as_deref() == Some(name))
+    }
+
+    /// A Markdown list explaining the pattern piece by piece.
+    pub fn explain(&self) -> String {
+        let mut out = String::new();
+        explain_node(&mut out, &self.root, 0);
+        out
+    }
+}
+
+/// A part of a pattern, with its range relative to the pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub range: TextRange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    /// Matches the empty string.
+    Empty,
+    Literal {
+        c: char,
+        case_insensitive: bool,
+    },
+    /// `.`, which only matches newlines with the `s` flag.
+    AnyChar {
+        matches_newline: bool,
+    },
+    /// A bracketed character class, or a single Perl or Unicode class such as
+    /// `\d`.
+    Class {
+        negated: bool,
+        items: Vec<ClassItem>,
+    },
+    Assertion(Assertion),
+    Group {
+        kind: GroupKind,
+        child: Box<Node>,
+    },
+    /// A `(?flags)` directive, which applies to the rest of the enclosing
+    /// group.
+    SetFlags(String),
+    Repeat {
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+        child: Box<Node>,
+    },
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupKind {
+    /// A capture group, numbered from 1.
+    Capture { index: usize, name: Option<String> },
+    /// A non-capturing group, optionally setting flags as with `(?i:...)`.
+    NonCapture { flags: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassItem {
+    Char(char),
+    Range(char, char),
+    /// A Perl class such as `\d` or `\W`, given by its lowercase letter.
+    Perl {
+        class: char,
+        negated: bool,
+    },
+    Posix {
+        name: String,
+        negated: bool,
+    },
+    Unicode {
+        name: String,
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assertion {
+    TextStart,
+    TextEnd,
+    LineStart,
+    LineEnd,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+pub fn parse(pattern: &str) -> Result<Regex, RegexError> {
+    let mut p = Parser {
+        pattern,
+        pos: 0,
+        flags: Flags::default(),
+        captures: Vec::new(),
+    };
+    let root = p.parse_pattern()?;
+    Ok(Regex {
+        root,
+        captures: p.captures,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Flags {
+    case_insensitive: bool,
+    multi_line: bool,
+    dot_matches_newline: bool,
+    ungreedy: bool,
+}
+
+struct Parser<'a> {
+    pattern: &'a str,
+    pos: usize,
+    flags: Flags,
+    captures: Vec<Option<String>>,
+}
+
+/// An alternation being parsed, which ends at the end of the pattern or of the
+/// enclosing group.
+struct Alternation {
+    start: usize,
+    branches: Vec<Node>,
+    /// The items of the current branch, and where it starts.
+    items: Vec<Node>,
+    branch_start: usize,
+    /// The start of the previous token if it was a repetition operator, which
+    /// may not be followed by another.
+    last_repeat: Option<usize>,
+}
+
+impl Alternation {
+    fn new(start: usize) -> Self {
+        Self {
+            start,
+            branches: Vec::new(),
+            items: Vec::new(),
+            branch_start: start,
+            last_repeat: None,
+        }
+    }
+
+    fn end_branch(&mut self, p: &Parser<'_>) {
+        let mut items = mem::take(&mut self.items);
+        let branch = match items.len() {
+            0 => p.node(NodeKind::Empty, self.branch_start),
+            1 => items.pop().unwrap(),
+            _ => p.node(NodeKind::Concat(items), self.branch_start),
+        };
+        self.branches.push(branch);
+    }
+
+    fn finish(mut self, p: &Parser<'_>) -> Node {
+        self.end_branch(p);
+        if self.branches.len() == 1 {
+            return self.branches.pop().unwrap();
+        }
+        p.node(NodeKind::Alternate(self.branches), self.start)
+    }
+}
+
+/// A group whose contents are being parsed.
+struct OpenGroup {
+    start: usize,
+    kind: GroupKind,
+    /// The flags to restore once the group is closed.
+    outer_flags: Flags,
+    /// The alternation the group is an item of.
+    outer: Alternation,
+}
+
+/// What the start of a group turned out to be.
+enum GroupStart {
+    Group {
+        kind: GroupKind,
+        outer_flags: Flags,
+    },
+    /// A `(?flags)` directive, which is complete.
+    SetFlags(Node),
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.pattern[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let at = self.peek() == Some(c);
+        if at {
+            self.pos += c.len_utf8();
+        }
+        at
+    }
+
+    fn range_from(&self, start: usize) -> TextRange {
+        range(start, self.pos)
+    }
+
+    fn node(&self, kind: NodeKind, start: usize) -> Node {
+        Node {
+            kind,
+            range: self.range_from(start),
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>, start: usize) -> Result<T, RegexError> {
+        Err(RegexError {
+            message: message.into(),
+            range: self.range_from(start),
+        })
+    }
+
+    /// Parse the whole pattern. Groups are kept on an explicit stack rather
+    /// than parsed recursively, so that deeply nested patterns cannot overflow
+    /// the stack.
+    fn parse_pattern(&mut self) -> Result<Node, RegexError> {
+        let mut groups: Vec<OpenGroup> = Vec::new();
+        let mut alt = Alternation::new(self.pos);
+        while let Some(c) = self.peek() {
+            let item_start = self.pos;
+            let mut is_repeat = false;
+            match c {
+                '|' => {
+                    alt.end_branch(self);
+                    self.bump();
+                    alt.branch_start = self.pos;
+                }
+                ')' => {
+                    let Some(group) = groups.pop() else {
+                        self.bump();
+                        return self.error("unexpected `)`", item_start);
+                    };
+                    let child = mem::replace(&mut alt, group.outer).finish(self);
+                    self.bump();
+                    self.flags = group.outer_flags;
+                    let kind = NodeKind::Group {
+                        kind: group.kind,
+                        child: Box::new(child),
+                    };
+                    alt.items.push(self.node(kind, group.start));
+                }
+                '*' | '+' | '?' => {
+                    self.bump();
+                    let (min, max) = match c {
+                        '*' => (0, None),
+                        '+' => (1, None),
+                        _ => (0, Some(1)),
+                    };
+                    self.repeat(&mut alt.items, min, max, item_start, alt.last_repeat)?;
+                    is_repeat = true;
+                }
+                '{' => match self.parse_repeat_bounds() {
+                    Some((min, max)) => {
+                        if min > MAX_REPEAT || max.is_some_and(|max| max > MAX_REPEAT || max < min) {
+                            return self.error("invalid repeat count", item_start);
+                        }
+                        self.repeat(&mut alt.items, min, max, item_start, alt.last_repeat)?;
+                        is_repeat = true;
+                    }
+                    None => {
+                        self.bump();
+                        alt.items.push(self.literal('{', item_start));
+                    }
+                },
+                '(' => match self.parse_group_start()? {
+                    GroupStart::Group { kind, outer_flags } => {
+                        if groups.len() >= MAX_NESTING {
+                            return self.error("pattern nests too deeply", item_start);
+                        }
+                        let outer = mem::replace(&mut alt, Alternation::new(self.pos));
+                        groups.push(OpenGroup {
+                            start: item_start,
+                            kind,
+                            outer_flags,
+                            outer,
+                        });
+                    }
+                    GroupStart::SetFlags(node) => alt.items.push(node),
+                },
+                '[' => alt.items.push(self.parse_class()?),
+                '.' => {
+                    self.bump();
+                    let kind = NodeKind::AnyChar {
+                        matches_newline: self.flags.dot_matches_newline,
+                    };
+                    alt.items.push(self.node(kind, item_start));
+                }
+                '^' | '$' => {
+                    self.bump();
+                    let assertion = match (c, self.flags.multi_line) {
+                        ('^', false) => Assertion::TextStart,
+                        ('^', true) => Assertion::LineStart,
+                        (_, false) => Assertion::TextEnd,
+                        (_, true) => Assertion::LineEnd,
+                    };
+                    alt.items.push(self.node(NodeKind::Assertion(assertion), item_start));
+                }
+                '\\' => self.parse_escape(&mut alt.items)?,
+                _ => {
+                    self.bump();
+                    alt.items.push(self.literal(c, item_start));
+                }
+            }
+            alt.last_repeat = is_repeat.then_some(item_start);
+        }
+        if let Some(group) = groups.last() {
+            return Err(RegexError {
+                message: "missing closing `)`".to_owned(),
+                range: range(group.start, group.start + 1),
+            });
+        }
+        Ok(alt.finish(self))
+    }
+
+    fn literal(&self, c: char, start: usize) -> Node {
+        let kind = NodeKind::Literal {
+            c,
+            case_insensitive: self.flags.case_insensitive,
+        };
+        self.node(kind, start)
+    }
+
+    /// Apply a repetition operator, which has just been parsed, to the last
+    /// item of `items`.
+    fn repeat(
+        &mut self,
+        items: &mut Vec<Node>,
+        min: u32,
+        max: Option<u32>,
+        op_start: usize,
+        last_repeat: Option<usize>,
+    ) -> Result<(), RegexError> {
+        let lazy = self.eat('?');
+        if let Some(last_repeat) = last_repeat {
+            return self.error(
+                format!(
+                    "invalid nested repetition operator `{}`",
+                    &self.pattern[last_repeat..self.pos]
+                ),
+                last_repeat,
+            );
+        }
+        // Flags set immediately before the operator do not count as its argument.
+        let Some(index) = items
+            .iter()
+            .rposition(|item| !matches!(item.kind, NodeKind::SetFlags(_)))
+        else {
+            return self.error(
+                format!(
+                    "missing argument to repetition operator `{}`",
+                    &self.pattern[op_start..self.pos]
+                ),
+                op_start,
+            );
+        };
+        let child = items.remove(index);
+        let start = u32::from(child.range.start()) as usize;
+        let kind = NodeKind::Repeat {
+            min,
+            max,
+            greedy: lazy == self.flags.ungreedy,
+            child: Box::new(child),
+        };
+        items.insert(index, self.node(kind, start));
+        Ok(())
+    }
+
+    /// Parse bounds of the form `{n}`, `{n,}`, or `{n,m}`, returning `None`
+    /// without consuming anything if the `{` does not start such bounds, in
+    /// which case it is a literal.
+    fn parse_repeat_bounds(&mut self) -> Option<(u32, Option<u32>)> {
+        let rest = self.rest().strip_prefix('{')?;
+        let (min, rest) = split_repeat_count(rest)?;
+        let (max, rest) = match rest.strip_prefix(',') {
+            Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => {
+                let (max, rest) = split_repeat_count(rest)?;
+                (Some(max), rest)
+            }
+            Some(rest) => (None, rest),
+            None => (Some(min), rest),
+        };
+        let rest = rest.strip_prefix('}')?;
+        self.pos = self.pattern.len() - rest.len();
+        Some((min, max))
+    }
+
+    /// Parse the start of a group, up to and including the `(` and any
+    /// prefix such as `?:` or `?P<name>`, which may make it a complete
+    /// `(?flags)` directive.
+    fn parse_group_start(&mut self) -> Result<GroupStart, RegexError> {
+        let start = self.pos;
+        self.bump();
+        let outer_flags = self.flags;
+        let kind = if self.eat('?') {
+            if let Some(prefix) = ["=", "!", "<=", "<!"]
+                .into_iter()
+                .find(|prefix| self.rest().starts_with(prefix))
+            {
+                self.pos += prefix.len();
+                return self.error("lookaround assertions are not supported", start);
+            }
+            if let Some(prefix) = ["P<", "<"].into_iter().find(|prefix| self.rest().starts_with(prefix)) {
+                self.pos += prefix.len();
+                let Some(len) = self.rest().find('>') else {
+                    self.pos = self.pattern.len();
+                    return self.error("invalid named capture", start);
+                };
+                let name = self.rest()[..len].to_owned();
+                self.pos += len + 1;
+                if name.is_empty() || !name.chars().all(|c| c == '_' || c.is_ascii_alphanumeric()) {
+                    return self.error(format!("invalid capture group name {name:?}"), start);
+                }
+                self.captures.push(Some(name.clone()));
+                GroupKind::Capture {
+                    index: self.captures.len(),
+                    name: Some(name),
+                }
+            } else {
+                let flags_start = self.pos;
+                let flags = self.parse_flags(start)?;
+                let flag_text = self.pattern[flags_start..self.pos - 1].to_owned();
+                self.flags = flags;
+                if self.pattern[..self.pos].ends_with(')') {
+                    // Flags set for the rest of the enclosing group.
+                    return Ok(GroupStart::SetFlags(self.node(NodeKind::SetFlags(flag_text), start)));
+                }
+                GroupKind::NonCapture {
+                    flags: (!flag_text.is_empty()).then_some(flag_text),
+                }
+            }
+        } else {
+            self.captures.push(None);
+            GroupKind::Capture {
+                index: self.captures.len(),
+                name: None,
+            }
+        };
+
+        Ok(GroupStart::Group { kind, outer_flags })
+    }
+
+    /// Parse the flags of a `(?flags)` or `(?flags:` group, up to and
+    /// including the closing `)` or `:`.
+    fn parse_flags(&mut self, group_start: usize) -> Result<Flags, RegexError> {
+        let mut flags = self.flags;
+        let mut negated = false;
+        let mut saw_flag = false;
+        loop {
+            let c = self.bump();
+            let value = !negated;
+            match c {
+                Some('i') => flags.case_insensitive = value,
+                Some('m') => flags.multi_line = value,
+                Some('s') => flags.dot_matches_newline = value,
+                Some('U') => flags.ungreedy = value,
+                Some('-') if !negated => {
+                    negated = true;
+                    saw_flag = false;
+                    continue;
+                }
+                Some(':' | ')') if !negated || saw_flag => return Ok(flags),
+                _ => {
+                    return self.error(
+                        format!(
+                            "invalid or unsupported group syntax `{}`",
+                            &self.pattern[group_start..self.pos]
+                        ),
+                        group_start,
+                    );
+                }
+            }
+            saw_flag = true;
+        }
+    }
+
+    /// Parse an escape outside a character class, pushing the nodes it
+    /// produces to `items`.
+    fn parse_escape(&mut self, items: &mut Vec<Node>) -> Result<(), RegexError> {
+        let start = self.pos;
+        let mut chars = self.rest().chars();
+        chars.next();
+        let assertion = match chars.next() {
+            Some('A') => Assertion::TextStart,
+            Some('z') => Assertion::TextEnd,
+            Some('b') => Assertion::WordBoundary,
+            Some('B') => Assertion::NotWordBoundary,
+            Some('Q') => {
+                self.pos += 2;
+                let end = self.rest().find(r"\E").map_or(self.pattern.len(), |len| self.pos + len);
+                while self.pos < end {
+                    let char_start = self.pos;
+                    let c = self.bump().unwrap();
+                    items.push(self.literal(c, char_start));
+                }
+                if self.rest().starts_with(r"\E") {
+                    self.pos += 2;
+                }
+                return Ok(());
+            }
+            Some(_) => {
+                if let Some(item) = self.parse_class_escape()? {
+                    let kind = NodeKind::Class {
+                        negated: false,
+                        items: vec![item],
+                    };
+                    items.push(self.node(kind, start));
+                } else {
+                    let c = self.parse_char_escape()?;
+                    items.push(self.literal(c, start));
+                }
+                return Ok(());
+            }
+            None => {
+                self.pos += 1;
+                return self.error("trailing backslash at end of pattern", start);
+            }
+        };
+        self.pos += 2;
+        items.push(self.node(NodeKind::Assertion(assertion), start));
+        Ok(())
+    }
+
+    /// Parse a Perl or Unicode class such as `\d` or `\p{Greek}`, if there is
+    /// one.
+    fn parse_class_escape(&mut self) -> Result<Option<ClassItem>, RegexError> {
+        let start = self.pos;
+        let mut chars = self.rest().chars();
+        if chars.next() != Some('\\') {
+            return Ok(None);
+        }
+        match chars.next() {
+            Some(c @ ('d' | 's' | 'w' | 'D' | 'S' | 'W')) => {
+                self.pos += 2;
+                Ok(Some(ClassItem::Perl {
+                    class: c.to_ascii_lowercase(),
+                    negated: c.is_ascii_uppercase(),
+                }))
+            }
+            Some(c @ ('p' | 'P')) => {
+                self.pos += 2;
+                let name = if self.eat('{') {
+                    let Some(len) = self.rest().find('}') else {
+                        self.pos = self.pattern.len();
+                        return self.error("missing closing `}` in Unicode class", start);
+                    };
+                    let name = &self.pattern[self.pos..self.pos + len];
+                    self.pos += len + 1;
+                    name
+                } else {
+                    let name_start = self.pos;
+                    if self.bump().is_none() {
+                        return self.error("missing Unicode class name", start);
+                    }
+                    &self.pattern[name_start..self.pos]
+                };
+                let (negated, name) = match name.strip_prefix('^') {
+                    Some(name) => (c == 'p', name),
+                    None => (c == 'P', name),
+                };
+                if !is_unicode_class(name) {
+                    return self.error(format!("unknown Unicode class `{name}`"), start);
+                }
+                Ok(Some(ClassItem::Unicode {
+                    name: name.to_owned(),
+                    negated,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Parse an escape denoting a single character, starting at the
+    /// backslash.
+    fn parse_char_escape(&mut self) -> Result<char, RegexError> {
+        let start = self.pos;
+        self.bump();
+        let Some(c) = self.bump() else {
+            return self.error("trailing backslash at end of pattern", start);
+        };
+        let is_octal = |c: Option<char>| c.is_some_and(|c| matches!(c, '0'..='7'));
+        let escaped = match c {
+            // A single nonzero digit would be a backreference, which RE2 does
+            // not support.
+            '1'..='7' if !is_octal(self.peek()) => None,
+            '0'..='7' => {
+                let mut value = c.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    if !is_octal(self.peek()) {
+                        break;
+                    }
+                    value = value * 8 + self.bump().unwrap().to_digit(8).unwrap();
+                }
+                char::from_u32(value)
+            }
+            'x' if self.eat('{') => {
+                let mut value: u32 = 0;
+                let mut digits = 0;
+                loop {
+                    match self.bump() {
+                        Some('}') if digits > 0 => break char::from_u32(value).or(Some(char::REPLACEMENT_CHARACTER)),
+                        Some(c) if c.is_ascii_hexdigit() => {
+                            value = value * 16 + c.to_digit(16).unwrap();
+                            digits += 1;
+                            if value > char::MAX as u32 {
+                                break None;
+                            }
+                        }
+                        _ => break None,
+                    }
+                }
+            }
+            'x' => {
+                let hi = self.bump().and_then(|c| c.to_digit(16));
+                let lo = self.bump().and_then(|c| c.to_digit(16));
+                hi.zip(lo).and_then(|(hi, lo)| char::from_u32(hi * 16 + lo))
+            }
+            'a' => Some('\x07'),
+            'f' => Some('\x0c'),
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            'v' => Some('\x0b'),
+            c if c.is_ascii() && !c.is_ascii_alphanumeric() => Some(c),
+            _ => None,
+        };
+        match escaped {
+            Some(c) => Ok(c),
+            None => self.error(
+                format!("invalid escape sequence `{}`", &self.pattern[start..self.pos]),
+                start,
+            ),
+        }
+    }
+
+    fn parse_class(&mut self) -> Result<Node, RegexError> {
+        let start = self.pos;
+        self.bump();
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        // A `]` at the start of the class is a literal.
+        let mut first = true;
+        loop {
+            match self.peek() {
+                None => return self.missing_bracket(start),
+                Some(']') if !first => {
+                    self.bump();
+                    break;
+                }
+                _ => first = false,
+            }
+
+            let item_start = self.pos;
+            if let Some(rest) = self.rest().strip_prefix("[:")
+                && let Some(len) = rest.find(":]")
+            {
+                let name = rest[..len].to_owned();
+                self.pos += len + 4;
+                let (negated, name) = match name.as_str().strip_prefix('^') {
+                    Some(name) => (true, name),
+                    None => (false, name.as_str()),
+                };
+                if !POSIX_CLASSES.contains(&name) {
+                    return self.error(format!("unknown POSIX class `{name}`"), item_start);
+                }
+                items.push(ClassItem::Posix {
+                    name: name.to_owned(),
+                    negated,
+                });
+                continue;
+            }
+            if let Some(item) = self.parse_class_escape()? {
+                items.push(item);
+                continue;
+            }
+
+            let lo = self.parse_class_char(start)?;
+            let rest = self.rest();
+            if rest.starts_with('-') && rest.len() > 1 && !rest.starts_with("-]") {
+                self.bump();
+                let hi = self.parse_class_char(start)?;
+                if hi < lo {
+                    return self.error(
+                        format!(
+                            "invalid character class range `{}`",
+                            &self.pattern[item_start..self.pos]
+                        ),
+                        item_start,
+                    );
+                }
+                items.push(ClassItem::Range(lo, hi));
+            } else {
+                items.push(ClassItem::Char(lo));
+            }
+        }
+        Ok(self.node(NodeKind::Class { negated, items }, start))
+    }
+
+    fn parse_class_char(&mut self, class_start: usize) -> Result<char, RegexError> {
+        match self.peek() {
+            None => self.missing_bracket(class_start),
+            Some('\\') => self.parse_char_escape(),
+            Some(c) => {
+                self.bump();
+                Ok(c)
+            }
+        }
+    }
+
+    fn missing_bracket<T>(&self, class_start: usize) -> Result<T, RegexError> {
+        Err(RegexError {
+            message: "missing closing `]`".to_owned(),
+            range: range(class_start, class_start + 1),
+        })
+    }
+}
+
+fn range(start: usize, end: usize) -> TextRange {
+    TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32))
+}
+
+/// Split a repetition count off the start of `s`. Like Go, reject leading
+/// zeros, and saturate counts that are too large to be valid anyway.
+fn split_repeat_count(s: &str) -> Option<(u32, &str)> {
+    let len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let digits = &s[..len];
+    if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) {
+        return None;
+    }
+    Some((digits.parse().unwrap_or(u32::MAX), &s[len..]))
+}
+
+/// Report whether `name` names a Unicode class, ignoring case, spaces,
+/// underscores, and hyphens as recent versions of Go do.
+fn is_unicode_class(name: &str) -> bool {
+    fn normalize(name: &str) -> String {
+        name.chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    }
+    let name = normalize(name);
+    UNICODE_CLASSES.iter().any(|class| normalize(class) == name)
+}
+
+/// A reference to a capture group in a replacement template, such as `$1` or
+/// `${name}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureRef {
+    pub name: String,
+    /// Whether the name is enclosed in braces.
+    pub braced: bool,
+    /// The range of the reference relative to the template.
+    pub range: TextRange,
+}
+
+impl CaptureRef {
+    /// The index of the capture group referenced, if the name is numeric.
+    pub fn index(&self) -> Option<usize> {
+        if !self.name.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        // Go treats overly large indices as names.
+        self.name.parse().ok().filter(|&index| index < 100_000_000)
+    }
+}
+
+/// The references to capture groups in `template`, interpreted as by Go's
+/// `Regexp.Expand`: `$$` is a literal `$`, names are as long as possible, and
+/// a malformed reference is left as is.
+pub fn capture_refs(template: &str) -> Vec<CaptureRef> {
+    let mut refs = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = template[pos..].find('$') {
+        let start = pos + offset;
+        pos = start + 1;
+        let rest = &template[pos..];
+        if rest.starts_with('$') {
+            pos += 1;
+            continue;
+        }
+        let braced = rest.starts_with('{');
+        let name_start = pos + usize::from(braced);
+        let name_len = template[name_start..]
+            .find(|c: char| c != '_' && !c.is_alphanumeric())
+            .unwrap_or(template.len() - name_start);
+        let name_end = name_start + name_len;
+        if name_len == 0 || (braced && !template[name_end..].starts_with('}')) {
+            continue;
+        }
+        let end = name_end + usize::from(braced);
+        refs.push(CaptureRef {
+            name: template[name_start..name_end].to_owned(),
+            braced,
+            range: range(start, end),
+        });
+        pos = end;
+    }
+    refs
+}
+
+/// A piece of an explanation yet to be written.
+enum Explain<'a> {
+    Line(String, usize),
+    Node(&'a Node, usize),
+    /// The items of a sequence, combining consecutive literal characters into
+    /// runs.
+    Seq(&'a [Node], usize),
+}
+
+/// Explain `node` at the given depth of the list. The pieces are kept on an
+/// explicit stack, so that deeply nested patterns cannot overflow the stack.
+fn explain_node(out: &mut String, node: &Node, depth: usize) {
+    let mut stack = vec![Explain::Node(node, depth)];
+    while let Some(piece) = stack.pop() {
+        match piece {
+            Explain::Line(line, depth) => push_line(out, depth, &line),
+            Explain::Seq([], _) => {}
+            Explain::Seq(items, depth) => {
+                let run_len = items.iter().take_while(|item| same_case(item, &items[0])).count();
+                if run_len > 0 {
+                    push_line(out, depth, &describe_literals(&items[..run_len]));
+                    stack.push(Explain::Seq(&items[run_len..], depth));
+                } else {
+                    stack.push(Explain::Seq(&items[1..], depth));
+                    stack.push(Explain::Node(&items[0], depth));
+                }
+            }
+            Explain::Node(node, depth) => explain_step(&mut stack, out, node, depth),
+        }
+    }
+}
+
+/// Explain the top level of `node`, pushing the pieces explaining its children
+/// to `stack`.
+fn explain_step<'a>(stack: &mut Vec<Explain<'a>>, out: &mut String, node: &'a Node, depth: usize) {
+    match &node.kind {
+        NodeKind::Concat(items) => stack.push(Explain::Seq(items, depth)),
+        NodeKind::Alternate(branches) => {
+            push_line(out, depth, "one of:");
+            for branch in branches.iter().rev() {
+                match &branch.kind {
+                    NodeKind::Concat(items) if !is_literal_run(items) => {
+                        stack.push(Explain::Seq(items, depth + 2));
+                        stack.push(Explain::Line("in sequence:".to_owned(), depth + 1));
+                    }
+                    _ => stack.push(Explain::Node(branch, depth + 1)),
+                }
+            }
+        }
+        NodeKind::Group { kind, child } => {
+            let label = match kind {
+                GroupKind::Capture {
+                    index,
+                    name: Some(name),
+                } => format!("capture group {index} (`{name}`):"),
+                GroupKind::Capture { index, name: None } => format!("capture group {index}:"),
+                GroupKind::NonCapture { flags: Some(flags) } => format!("a group with flags `{flags}`:"),
+                GroupKind::NonCapture { flags: None } => "a group:".to_owned(),
+            };
+            push_line(out, depth, &label);
+            stack.push(Explain::Node(child, depth + 1));
+        }
+        NodeKind::Repeat {
+            min,
+            max,
+            greedy,
+            child,
+        } => {
+            let mut quantity = match (min, max) {
+                (0, None) => "zero or more times".to_owned(),
+                (1, None) => "one or more times".to_owned(),
+                (0, Some(1)) => "optionally".to_owned(),
+                (min, None) => format!("at least {min} times"),
+                (min, Some(max)) if min == max => format!("exactly {min} times"),
+                (min, Some(max)) => format!("between {min} and {max} times"),
+            };
+            if !greedy {
+                quantity.push_str(", as few as possible");
+            }
+            quantity.push(':');
+            push_line(out, depth, &quantity);
+            stack.push(Explain::Node(child, depth + 1));
+        }
+        _ => push_line(out, depth, &describe_atom(node)),
+    }
+}
+
+fn is_literal_run(items: &[Node]) -> bool {
+    items.iter().all(|item| matches!(item.kind, NodeKind::Literal { .. }))
+        && items.windows(2).all(|pair| same_case(&pair[0], &pair[1]))
+}
+
+fn same_case(a: &Node, b: &Node) -> bool {
+    matches!(
+        (&a.kind, &b.kind),
+        (NodeKind::Literal { case_insensitive: x, .. }, NodeKind::Literal { case_insensitive: y, .. }) if x == y
+    )
+}
+
+fn describe_literals(items: &[Node]) -> String {
+    let mut text = String::new();
+    let mut case_insensitive = false;
+    for item in items {
+        if let NodeKind::Literal {
+            c,
+            case_insensitive: ci,
+        } = item.kind
+        {
+            text.push(c);
+            case_insensitive = ci;
+        }
+    }
+    let mut description = if text.chars().count() == 1 {
+        format!("the character {}", code(&text))
+    } else {
+        format!("the text {}", code(&text))
+    };
+    if case_insensitive {
+        description.push_str(" (ignoring case)");
+    }
+    description
+}
+
+fn describe_atom(node: &Node) -> String {
+    match &node.kind {
+        NodeKind::Empty => "nothing".to_owned(),
+        NodeKind::Literal { .. } => describe_literals(std::slice::from_ref(node)),
+        NodeKind::AnyChar { matches_newline: true } => "any character".to_owned(),
+        NodeKind::AnyChar { matches_newline: false } => "any character except a newline".to_owned(),
+        NodeKind::Class { negated, items } => match (negated, &items[..]) {
+            (false, [item]) => describe_class_item(item),
+            _ => {
+                let items: Vec<_> = items.iter().map(describe_class_item).collect();
+                if *negated {
+                    format!("any character except {}", items.join(", "))
+                } else {
+                    format!("any of {}", items.join(", "))
+                }
+            }
+        },
+        NodeKind::Assertion(assertion) => match assertion {
+            Assertion::TextStart => "the start of the text",
+            Assertion::TextEnd => "the end of the text",
+            Assertion::LineStart => "the start of a line",
+            Assertion::LineEnd => "the end of a line",
+            Assertion::WordBoundary => "a word boundary",
+            Assertion::NotWordBoundary => "a position that is not a word boundary",
+        }
+        .to_owned(),
+        NodeKind::SetFlags(flags) => format!("set the flags `{flags}` for the rest of the group"),
+        NodeKind::Group { .. } | NodeKind::Repeat { .. } | NodeKind::Concat(_) | NodeKind::Alternate(_) => {
+            unreachable!("compound nodes are explained separately")
+        }
+    }
+}
+
+fn describe_class_item(item: &ClassItem) -> String {
+    match item {
+        ClassItem::Char(c) => code(&c.to_string()),
+        ClassItem::Range(lo, hi) => format!("{} to {}", code(&lo.to_string()), code(&hi.to_string())),
+        ClassItem::Perl { class, negated } => {
+            let description = match (class, negated) {
+                ('d', false) => "a digit",
+                ('d', true) => "a non-digit",
+                ('s', false) => "whitespace",
+                ('s', true) => "non-whitespace",
+                ('w', false) => "a word character",
+                _ => "a non-word character",
+            };
+            let escape = if *negated { class.to_ascii_uppercase() } else { *class };
+            format!("{description} (`\\{escape}`)")
+        }
+        ClassItem::Posix { name, negated } => {
+            format!(
+                "a character {} the POSIX class `{name}`",
+                if *negated { "not in" } else { "in" }
+            )
+        }
+        ClassItem::Unicode { name, negated } => {
+            format!(
+                "a character {} the Unicode class `{name}`",
+                if *negated { "not in" } else { "in" }
+            )
+        }
+    }
+}
+
+/// Format `text` as inline code, showing control characters as escapes.
+fn code(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if c.is_control() {
+            escaped.extend(c.escape_default());
+        } else {
+            escaped.push(c);
+        }
+    }
+    if escaped.contains('`') {
+        format!("`` {escaped} ``")
+    } else {
+        format!("`{escaped}`")
+    }
+}
+
+fn push_line(out: &mut String, depth: usize, line: &str) {
+    let _ = writeln!(out, "{:indent$}- {line}", "", indent = depth * 2);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_NESTING, capture_refs, parse};
+
+    fn error(pattern: &str) -> (String, &str) {
+        let err = parse(pattern).unwrap_err();
+        (err.message, &pattern[err.range])
+    }
+
+    #[test]
+    fn valid_patterns_are_accepted() {
+        for pattern in [
+            r"^(\d+)\s*(?P<unit>[smhd])$",
+            r"(?i)hello|(?:wor)+ld{2,3}?",
+            r"[]a-z\-\p{Greek}[:alpha:]\\]",
+            r"a{,5}\{\Q.*+\E[^\n]",
+            r"\x41\x{1F600}\101\0\pL\P{^Han}",
+            r"(?<name>a)(?s-m:.)",
+        ] {
+            assert!(parse(pattern).is_ok(), "{pattern} should be valid");
+        }
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected_at_the_problem() {
+        assert_eq!(error("ab(c"), ("missing closing `)`".to_owned(), "("));
+        assert_eq!(error("ab)c"), ("unexpected `)`".to_owned(), ")"));
+        assert_eq!(error("a[bc"), ("missing closing `]`".to_owned(), "["));
+        assert_eq!(
+            error("x[z-a]"),
+            ("invalid character class range `z-a`".to_owned(), "z-a")
+        );
+        assert_eq!(
+            error("a**"),
+            ("invalid nested repetition operator `**`".to_owned(), "**")
+        );
+        assert_eq!(
+            error("|+"),
+            ("missing argument to repetition operator `+`".to_owned(), "+")
+        );
+        assert_eq!(error("a{1001}"), ("invalid repeat count".to_owned(), "{1001}"));
+        assert_eq!(error(r"(\d)\1"), ("invalid escape sequence `\\1`".to_owned(), r"\1"));
+        assert_eq!(error(r"\C"), ("invalid escape sequence `\\C`".to_owned(), r"\C"));
+        assert_eq!(
+            error("(?=a)"),
+            ("lookaround assertions are not supported".to_owned(), "(?=")
+        );
+        assert_eq!(
+            error("(?x)a"),
+            ("invalid or unsupported group syntax `(?x`".to_owned(), "(?x")
+        );
+        assert_eq!(
+            error(r"\p{Klingon}"),
+            ("unknown Unicode class `Klingon`".to_owned(), r"\p{Klingon}")
+        );
+        assert_eq!(error(r"a\"), ("trailing backslash at end of pattern".to_owned(), r"\"));
+    }
+
+    #[test]
+    fn deeply_nested_patterns_do_not_overflow() {
+        // Each level nests two groups and an alternation.
+        let nested = |depth: usize| format!("{}a{}", "(?:b|(".repeat(depth), "))".repeat(depth));
+        let explanation = std::thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(move || {
+                let regex = parse(&nested(MAX_NESTING / 2)).unwrap();
+                assert_eq!(regex.capture_count(), MAX_NESTING / 2);
+                assert_eq!(
+                    error(&nested(MAX_NESTING / 2 + 1)).0,
+                    "pattern nests too deeply".to_owned()
+                );
+                regex.explain()
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(explanation.ends_with(&format!(
+            "{:indent$}- the character `a`\n",
+            "",
+            indent = MAX_NESTING * 3
+        )));
+    }
+
+    #[test]
+    fn literal_braces_and_capture_groups() {
+        assert!(parse("a{x}b{1,").is_ok());
+        let regex = parse(r"(a)(?:b)(?P<c>c)((d))").unwrap();
+        assert_eq!(regex.capture_count(), 4);
+        assert!(regex.has_capture_named("c"));
+        assert!(!regex.has_capture_named("a"));
+    }
+
+    #[test]
+    fn patterns_are_explained() {
+        let regex = parse(r"^(?P<amount>\d+)(k|mil)?\.x*?$").unwrap();
+        assert_eq!(
+            regex.explain(),
+            "\
+- the start of the text
+- capture group 1 (`amount`):
+  - one or more times:
+    - a digit (`\\d`)
+- optionally:
+  - capture group 2:
+    - one of:
+      - the character `k`
+      - the text `mil`
+- the character `.`
+- zero or more times, as few as possible:
+  - the character `x`
+- the end of the text
+"
+        );
+        assert_eq!(
+            parse("(?i)ab[^a-c\t]").unwrap().explain(),
+            "\
+- set the flags `i` for the rest of the group
+- the text `ab` (ignoring case)
+- any character except `a` to `c`, `\\t`
+"
+        );
+    }
+
+    #[test]
+    fn capture_refs_follow_go_expansion() {
+        let refs: Vec<_> = capture_refs("$1 $$2 ${name}x $1x $ ${bad")
+            .into_iter()
+            .map(|r| (r.name, r.braced))
+            .collect();
+        assert_eq!(
+            refs,
+            [
+                ("1".to_owned(), false),
+                ("name".to_owned(), true),
+                ("1x".to_owned(), false)
+            ]
+        );
+    }
+}
@@ -5,16 +5,16 @@ use rowan::{TextRange, TextSize};
 use slotmap::SlotMap;
 use smol_str::SmolStr;
 use yag_template_syntax::ast;
-use yag_template_syntax::ast::{Action, AstNode, AstToken};
+use yag_template_syntax::ast::{AstNode, AstToken};
+use yag_template_syntax::visit::{self, ScopeKind, Visitor};
 
-use super::{Scope, ScopeId, ScopeInfo, VarSymbol, VarSymbolId};
-use crate::{AnalysisError, AnalysisWarning};
+use super::{DotContext, Scope, ScopeId, ScopeInfo, VarSymbol, VarSymbolId};
 
-pub fn analyze(root: ast::Root) -> (ScopeInfo, Vec<AnalysisError>, Vec<AnalysisWarning>) {
+pub fn analyze(root: ast::Root) -> ScopeInfo {
     let mut s = ScopeAnalyzer::new(root.text_range());
     // The variable $ is predefined as the initial context data.
-    s.declare_var("$", root.text_range().start(), None);
-    s.analyze_all(root.actions());
+    s.declare_var("$", root.text_range().start(), None, None);
+    visit::walk_root(&mut s, &root);
     s.finish()
 }
 
@@ -25,13 +25,16 @@ struct ScopeAnalyzer {
 
     var_syms: SlotMap<VarSymbolId, VarSymbol>,
     resolved_var_uses: HashMap<TextRange, VarSymbolId>, // indexed by text range of ast::Var
-    errors: Vec<AnalysisError>,
+    undefined_var_uses: Vec<(SmolStr, TextRange)>,
+    /// The context data in the bodies of compound actions rebinding `.`,
+    /// indexed by the text ranges of the bodies.
+    rebound_dots: HashMap<TextRange, DotContext>,
 }
 
 impl ScopeAnalyzer {
     fn new(root_range: TextRange) -> Self {
         let mut scopes = SlotMap::with_key();
-        let top_scope = scopes.insert(Scope::new(root_range, None));
+        let top_scope = scopes.insert(Scope::new(root_range, None, DotContext::Root));
         Self {
             scopes,
             top_scope,
@@ -39,51 +42,39 @@ impl ScopeAnalyzer {
 
             var_syms: SlotMap::with_key(),
             resolved_var_uses: HashMap::new(),
-            errors: Vec::new(),
+            undefined_var_uses: Vec::new(),
+            rebound_dots: HashMap::new(),
         }
     }
 
-    fn finish(self) -> (ScopeInfo, Vec<AnalysisError>, Vec<AnalysisWarning>) {
+    fn finish(self) -> ScopeInfo {
         assert!(self.parent_scopes.is_empty());
-        let mut warnings = Vec::new();
-        for v in self.var_syms.values() {
-            if !v.used
-                && !v.name.ends_with("_")
-                && let Some(decl_range) = v.decl_range
-            {
-                warnings.push(AnalysisWarning::new(format!("unused variable {}", v.name), decl_range));
-            }
-        }
-        let info = ScopeInfo::new(self.var_syms, self.resolved_var_uses, self.scopes);
-        (info, self.errors, warnings)
+        ScopeInfo::new(
+            self.var_syms,
+            self.resolved_var_uses,
+            self.undefined_var_uses,
+            self.scopes,
+        )
     }
 
-    fn error(&mut self, message: impl Into<String>, range: TextRange) {
-        self.errors.push(AnalysisError::new(message, range));
+    fn enter_inner_scope(&mut self, range: TextRange, dot: DotContext) {
+        self.enter_scope(range, Some(self.top_scope), dot)
     }
 
-    #[must_use]
-    fn enter_inner_scope(&mut self, range: TextRange) -> ScopeId {
-        self.enter_scope(range, Some(self.top_scope))
+    fn enter_detached_scope(&mut self, range: TextRange, dot: DotContext) {
+        self.enter_scope(range, None, dot)
     }
 
-    #[must_use]
-    fn enter_detached_scope(&mut self, range: TextRange) -> ScopeId {
-        self.enter_scope(range, None)
-    }
-
-    fn enter_scope(&mut self, range: TextRange, parent: Option<ScopeId>) -> ScopeId {
-        let new_scope = self.scopes.insert(Scope::new(range, parent));
+    fn enter_scope(&mut self, range: TextRange, parent: Option<ScopeId>, dot: DotContext) {
+        let new_scope = self.scopes.insert(Scope::new(range, parent, dot));
         self.parent_scopes.push(mem::replace(&mut self.top_scope, new_scope));
-        new_scope
     }
 
-    fn exit(&mut self, scope: ScopeId) {
-        assert!(self.top_scope == scope);
+    fn exit(&mut self) {
         self.top_scope = self
             .parent_scopes
             .pop()
-            .expect("call to exit_scope() should correspond to an earlier enter_scope()");
+            .expect("call to exit() should correspond to an earlier enter_scope()");
     }
 
     fn declare_var(
@@ -91,13 +82,18 @@ impl ScopeAnalyzer {
         name: impl Into<SmolStr>,
         visible_from: TextSize,
         decl_range: Option<TextRange>,
+        name_range: Option<TextRange>,
     ) -> VarSymbolId {
         let name: SmolStr = name.into();
+        let shadows = self.lookup_var(&name);
         let id = self.var_syms.insert_with_key(|id| VarSymbol {
             id,
             name: name.clone(),
             visible_from,
             decl_range,
+            name_range,
+            scope: self.top_scope,
+            shadows,
             used: false,
         });
 
@@ -112,137 +108,66 @@ impl ScopeAnalyzer {
     }
 }
 
-// Action analysis.
-impl ScopeAnalyzer {
-    fn analyze_all(&mut self, actions: impl Iterator<Item = Action>) {
-        for action in actions {
-            self.analyze_action(action);
+impl Visitor for ScopeAnalyzer {
+    fn enter_scope(&mut self, kind: ScopeKind, range: TextRange) {
+        match kind {
+            // The root scope is created up front.
+            ScopeKind::Root => {}
+            ScopeKind::Template => {
+                self.enter_detached_scope(range, DotContext::TemplateData);
+                // All associated template executions have the variable $
+                // predefined as the initial context data.
+                self.declare_var("$", range.start(), None, None);
+            }
+            ScopeKind::Action | ScopeKind::ElseBranch => {
+                self.enter_inner_scope(range, self.scopes[self.top_scope].dot);
+            }
+            ScopeKind::Body => {
+                let dot = self
+                    .rebound_dots
+                    .remove(&range)
+                    .unwrap_or(self.scopes[self.top_scope].dot);
+                self.enter_inner_scope(range, dot);
+            }
         }
     }
 
-    fn analyze_action(&mut self, action: ast::Action) {
-        match action {
-            Action::TemplateDefinition(template_def) => self.analyze_template_def(template_def),
-            Action::TemplateBlock(block) => self.analyze_template_block(block),
-            Action::TemplateInvocation(invocation) => self.analyze_template_invocation(invocation),
-            Action::Return(return_action) => self.analyze_return_action(return_action),
-            Action::If(if_action) => self.analyze_if_action(if_action),
-            Action::With(with_action) => self.analyze_with_action(with_action),
-            Action::Range(range_loop) => self.analyze_range_loop(range_loop),
-            Action::While(while_loop) => self.analyze_while_loop(while_loop),
-            Action::Comment(_) | Action::Break(_) | Action::Continue(_) => {}
-            Action::TryCatch(try_catch) => self.analyze_try_catch_action(try_catch),
-            Action::ExprAction(expr_action) => self.analyze_expr_action(expr_action),
+    fn leave_scope(&mut self, kind: ScopeKind, _range: TextRange) {
+        if kind != ScopeKind::Root {
+            self.exit();
         }
     }
 
-    fn analyze_template_def(&mut self, def: ast::TemplateDefinition) {
-        if let Some(body) = def.template_body() {
-            let body_scope = self.enter_detached_scope(body.text_range());
-            // All associated template executions have the variable $ predefined
-            // as the initial context data.
-            self.declare_var("$", body.text_range().start(), None);
-            self.analyze_all(body.actions());
-            self.exit(body_scope);
+    fn enter_with_action(&mut self, with_action: &ast::WithAction) {
+        if let (Some(condition), Some(body)) = (with_action.condition(), with_action.body()) {
+            self.rebound_dots
+                .insert(body.text_range(), DotContext::WithValue(condition.text_range()));
         }
     }
 
-    fn analyze_template_block(&mut self, block: ast::TemplateBlock) {
-        self.try_analyze_expr(block.context_data());
-
-        if let Some(body) = block.template_body() {
-            let body_scope = self.enter_detached_scope(body.text_range());
-            self.declare_var("$", body.text_range().start(), None);
-            self.analyze_all(body.actions());
-            self.exit(body_scope);
+    fn enter_try_catch_action(&mut self, try_catch: &ast::TryCatchAction) {
+        if let Some(catch_body) = try_catch.catch_body() {
+            self.rebound_dots
+                .insert(catch_body.text_range(), DotContext::CaughtError);
         }
     }
 
-    fn analyze_template_invocation(&mut self, invocation: ast::TemplateInvocation) {
-        self.try_analyze_expr(invocation.context_data());
-    }
-
-    fn analyze_return_action(&mut self, ret: ast::ReturnAction) {
-        self.try_analyze_expr(ret.expr());
-    }
-
-    fn analyze_if_action(&mut self, if_action: ast::IfAction) {
-        let if_scope = self.enter_inner_scope(if_action.text_range());
-        {
-            self.try_analyze_expr(if_action.condition());
-            if let Some(if_body) = if_action.body() {
-                let if_body_scope = self.enter_inner_scope(if_body.text_range());
-                self.analyze_all(if_body.actions());
-                self.exit(if_body_scope);
-            }
-            self.analyze_else_branches(if_action.else_branches());
+    fn enter_range_loop(&mut self, range_loop: &ast::RangeLoop) {
+        if let (Some(range_expr), Some(body)) = (range_loop.range_expr(), range_loop.body()) {
+            self.rebound_dots
+                .insert(body.text_range(), DotContext::RangeElement(range_expr.text_range()));
         }
-        self.exit(if_scope);
-    }
 
-    fn analyze_with_action(&mut self, with_action: ast::WithAction) {
-        let with_scope = self.enter_inner_scope(with_action.text_range());
-        {
-            self.try_analyze_expr(with_action.condition());
-            if let Some(with_body) = with_action.body() {
-                let with_body_scope = self.enter_inner_scope(with_body.text_range());
-                self.analyze_all(with_body.actions());
-                self.exit(with_body_scope);
-            }
-            self.analyze_else_branches(with_action.else_branches());
-        }
-        self.exit(with_scope);
-    }
-
-    fn analyze_else_branches(&mut self, mut else_branches: impl Iterator<Item = ast::ElseBranch>) {
-        let Some(else_branch) = else_branches.next() else {
+        let Some(range_clause) = range_loop.clause() else {
             return;
         };
-
-        let else_branch_scope = self.enter_inner_scope(else_branch.text_range());
-        {
-            self.try_analyze_expr(else_branch.condition());
-            if let Some(else_body) = else_branch.body() {
-                let else_body_scope = self.enter_inner_scope(else_body.text_range());
-                self.analyze_all(else_body.actions());
-                self.exit(else_body_scope);
-            }
-
-            // Recurse on the remaining branches.
-            self.analyze_else_branches(else_branches);
-        }
-        self.exit(else_branch_scope);
-    }
-
-    fn analyze_range_loop(&mut self, range_loop: ast::RangeLoop) {
-        let range_scope = self.enter_inner_scope(range_loop.text_range());
-        {
-            if let Some(range_clause) = range_loop.clause() {
-                self.analyze_range_clause(range_clause);
-            }
-            if let Some(range_body) = range_loop.body() {
-                let range_body_scope = self.enter_inner_scope(range_body.text_range());
-                self.analyze_all(range_body.actions());
-                self.exit(range_body_scope);
-            }
-            if let Some(else_branch) = range_loop.else_branch()
-                && let Some(else_body) = else_branch.body()
-            {
-                let else_body_scope = self.enter_inner_scope(else_body.text_range());
-                self.analyze_all(else_body.actions());
-                self.exit(else_body_scope);
-            }
-        }
-        self.exit(range_scope);
-    }
-
-    fn analyze_range_clause(&mut self, range_clause: ast::RangeClause) {
         if range_clause.declares_vars() {
             for var in range_clause.iteration_vars() {
                 let id = self.declare_var(
                     var.name(),
                     range_clause.text_range().end(),
                     Some(range_clause.text_range()),
+                    Some(var.text_range()),
                 );
                 self.set_referent(var, id);
             }
@@ -251,101 +176,31 @@ impl ScopeAnalyzer {
                 self.resolve_var_use(var);
             }
         }
-        self.try_analyze_expr(range_clause.expr());
     }
 
-    fn analyze_while_loop(&mut self, while_loop: ast::WhileLoop) {
-        let while_scope = self.enter_inner_scope(while_loop.text_range());
-        {
-            self.try_analyze_expr(while_loop.condition());
-            if let Some(while_body) = while_loop.body() {
-                let while_body_scope = self.enter_inner_scope(while_body.text_range());
-                self.analyze_all(while_body.actions());
-                self.exit(while_body_scope);
-            }
-            if let Some(else_branch) = while_loop.else_branch()
-                && let Some(else_body) = else_branch.body()
-            {
-                let else_body_scope = self.enter_inner_scope(else_body.text_range());
-                self.analyze_all(else_body.actions());
-                self.exit(else_body_scope);
-            }
-        }
-        self.exit(while_scope);
-    }
-
-    fn analyze_try_catch_action(&mut self, try_catch: ast::TryCatchAction) {
-        if let Some(try_body) = try_catch.try_body() {
-            let try_body_scope = self.enter_inner_scope(try_body.text_range());
-            self.analyze_all(try_body.actions());
-            self.exit(try_body_scope);
-        }
-
-        if let Some(catch_body) = try_catch.catch_body() {
-            let catch_body_scope = self.enter_inner_scope(catch_body.text_range());
-            self.analyze_all(catch_body.actions());
-            self.exit(catch_body_scope);
-        }
-    }
-
-    fn analyze_expr_action(&mut self, expr_action: ast::ExprAction) {
-        self.try_analyze_expr(expr_action.expr());
-    }
-}
-
-// Expression analysis.
-impl ScopeAnalyzer {
-    fn try_analyze_expr(&mut self, expr: Option<ast::Expr>) {
-        if let Some(expr) = expr {
-            self.analyze_expr(expr);
-        }
-    }
-
-    fn analyze_expr(&mut self, expr: ast::Expr) {
-        use ast::Expr::*;
-        match expr {
-            FuncCall(call) => call.args().for_each(|arg| self.analyze_expr(arg)),
-            ExprCall(call) => {
-                self.try_analyze_expr(call.callee());
-                call.args().for_each(|arg| self.analyze_expr(arg));
-            }
-            Parenthesized(p) => self.try_analyze_expr(p.inner_expr()),
-            Pipeline(p) => {
-                self.try_analyze_expr(p.init_expr());
-                p.stages().for_each(|stage| self.try_analyze_expr(stage.call_expr()));
-            }
-            ContextAccess(_) => {}
-            ContextFieldChain(_) => {}
-            ExprFieldChain(chain) => self.try_analyze_expr(chain.base_expr()),
-            VarAccess(access) => self.analyze_var_access(access),
-            VarDecl(decl) => self.analyze_var_decl(decl),
-            VarAssign(assign) => self.analyze_var_assign(assign),
-            Literal(_) => {}
-        }
-    }
-
-    fn analyze_var_access(&mut self, access: ast::VarAccess) {
+    fn enter_var_access(&mut self, access: &ast::VarAccess) {
         if let Some(var) = access.var() {
             self.resolve_var_use(var);
         }
     }
 
-    fn analyze_var_decl(&mut self, decl: ast::VarDecl) {
+    fn enter_var_decl(&mut self, decl: &ast::VarDecl) {
         if let Some(var) = decl.var() {
             let decl_range = decl.text_range();
-            let id = self.declare_var(var.name(), decl_range.end(), Some(decl_range));
+            let id = self.declare_var(var.name(), decl_range.end(), Some(decl_range), Some(var.text_range()));
             self.set_referent(var, id);
         }
-        self.try_analyze_expr(decl.initializer());
     }
 
-    fn analyze_var_assign(&mut self, assign: ast::VarAssign) {
+    fn enter_var_assign(&mut self, assign: &ast::VarAssign) {
         if let Some(var) = assign.var() {
             self.resolve_var_use(var)
         }
-        self.try_analyze_expr(assign.assign_expr());
     }
+}
 
+// Variable resolution.
+impl ScopeAnalyzer {
     fn resolve_var_use(&mut self, var_use: ast::Var) {
         let name = var_use.name();
         match self.lookup_var(name) {
@@ -353,7 +208,7 @@ impl ScopeAnalyzer {
                 self.var_syms[id].used = true;
                 self.set_referent(var_use, id);
             }
-            None => self.error(format!("undefined variable {name}"), var_use.text_range()),
+            None => self.undefined_var_uses.push((SmolStr::new(name), var_use.text_range())),
         }
     }
 
@@ -369,3 +224,66 @@ impl ScopeAnalyzer {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::{SyntaxNode, ast, parser};
+
+    use super::analyze;
+    use crate::scope::DotContext;
+
+    fn analyze_source(source: &str) -> usize {
+        let parse = parser::parse(source);
+        let root = SyntaxNode::new_root(parse.root).to::<ast::Root>();
+        analyze(root).undefined_var_uses().len()
+    }
+
+    #[test]
+    fn long_else_if_chains_do_not_overflow() {
+        let source = format!("{{{{if 1}}}}{}{{{{end}}}}", "{{else if $x := 1}}{{$x}}".repeat(20_000));
+        assert_eq!(analyze_source(&source), 0);
+    }
+
+    #[test]
+    fn pathologically_nested_templates_do_not_overflow() {
+        let depth = 20_000;
+        // The innermost use is past the parser's nesting limit and so is not
+        // analyzed, but the use following the nested actions still should be.
+        let source = format!(
+            "{}{{{{{}$undefined{}}}}}{}{{{{$undefined}}}}",
+            "{{with 1}}".repeat(depth),
+            "(".repeat(depth),
+            ")".repeat(depth),
+            "{{end}}".repeat(depth)
+        );
+        assert_eq!(analyze_source(&source), 1);
+    }
+
+    #[test]
+    fn dot_is_tracked_through_rebinding_actions() {
+        let source = r#"{{.}}{{with .A}}{{.}}{{else}}{{.}}{{end}}{{range $x := .B}}{{.}}{{end}}{{try}}{{.}}{{catch}}{{.}}{{end}}{{define "t"}}{{.}}{{end}}"#;
+        let parse = parser::parse(source);
+        let info = analyze(SyntaxNode::new_root(parse.root).to::<ast::Root>());
+        let dots: Vec<_> = source
+            .match_indices("{{.}}")
+            .map(|(i, _)| info.dot_at((i as u32 + 2).into()))
+            .collect();
+        let range_of = |s: &str| {
+            let start = source.find(s).unwrap() as u32;
+            rowan::TextRange::new(start.into(), (start + s.len() as u32).into())
+        };
+        assert_eq!(
+            dots,
+            [
+                DotContext::Root,
+                DotContext::WithValue(range_of(".A")),
+                DotContext::Root,
+                DotContext::RangeElement(range_of(".B")),
+                DotContext::Root,
+                DotContext::CaughtError,
+                DotContext::TemplateData,
+            ]
+        );
+    }
+}
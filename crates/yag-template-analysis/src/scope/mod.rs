@@ -17,6 +17,7 @@ new_key_type! { pub struct ScopeId; }
 pub struct ScopeInfo {
     var_syms: SlotMap<VarSymbolId, VarSymbol>,
     resolved_var_uses: HashMap<TextRange, VarSymbolId>, // indexed by text range of ast::Var
+    undefined_var_uses: Vec<(SmolStr, TextRange)>,
     scopes: SlotMap<ScopeId, Scope>,
 }
 
@@ -24,11 +25,13 @@ impl ScopeInfo {
     pub(crate) fn new(
         var_syms: SlotMap<VarSymbolId, VarSymbol>,
         resolved_var_uses: HashMap<TextRange, VarSymbolId>,
+        undefined_var_uses: Vec<(SmolStr, TextRange)>,
         scopes: SlotMap<ScopeId, Scope>,
     ) -> Self {
         Self {
             var_syms,
             resolved_var_uses,
+            undefined_var_uses,
             scopes,
         }
     }
@@ -39,6 +42,36 @@ impl ScopeInfo {
             .and_then(|id| self.var_syms.get(*id))
     }
 
+    /// Report whether `var` is a use of the variable `$` holding the context
+    /// data of the custom command, rather than the data passed to an
+    /// associated template.
+    pub fn is_root_data_var(&self, var: ast::Var) -> bool {
+        var.name() == "$"
+            && self
+                .resolve_var(var)
+                .and_then(|sym| self.scope(sym.scope))
+                .is_some_and(|scope| scope.dot == DotContext::Root)
+    }
+
+    /// Iterate over all variables declared in the template, including the
+    /// predefined `$`.
+    pub fn var_symbols(&self) -> impl Iterator<Item = &VarSymbol> {
+        self.var_syms.values()
+    }
+
+    pub fn var_symbol(&self, id: VarSymbolId) -> Option<&VarSymbol> {
+        self.var_syms.get(id)
+    }
+
+    pub fn scope(&self, id: ScopeId) -> Option<&Scope> {
+        self.scopes.get(id)
+    }
+
+    /// The names and ranges of variable uses that could not be resolved.
+    pub fn undefined_var_uses(&self) -> &[(SmolStr, TextRange)] {
+        &self.undefined_var_uses
+    }
+
     pub fn find_uses(&self, sym: &VarSymbol, include_decl: bool) -> VarUsesIter<'_> {
         VarUsesIter::new(self, sym, include_decl)
     }
@@ -48,6 +81,12 @@ impl ScopeInfo {
         ParentScopesIter::new(self, self.innermost_scope_containing(offset))
     }
 
+    /// What the context data `.` refers to at the offset.
+    pub fn dot_at(&self, offset: TextSize) -> DotContext {
+        self.innermost_scope_containing(offset)
+            .map_or(DotContext::Root, |id| self.scopes[id].dot)
+    }
+
     pub fn innermost_scope_containing(&self, offset: TextSize) -> Option<ScopeId> {
         self.scopes
             .iter()
@@ -64,24 +103,50 @@ pub struct VarSymbol {
     /// Within its scope, the variable is accessible after this offset.
     pub visible_from: TextSize,
     pub decl_range: Option<TextRange>,
+    /// The range of the variable name within the declaration.
+    pub name_range: Option<TextRange>,
+    /// The scope in which the variable is declared.
+    pub scope: ScopeId,
+    /// The variable of the same name that was accessible at the point of
+    /// declaration, if any, which is hidden by this one.
+    pub shadows: Option<VarSymbolId>,
     pub used: bool,
 }
 
+/// What the context data `.` refers to within a scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DotContext {
+    /// The context data of the custom command, with fields such as `.User`.
+    Root,
+    /// The data passed to an associated template.
+    TemplateData,
+    /// The value of the condition of a `{{with}}` action, given by the range
+    /// of the condition.
+    WithValue(TextRange),
+    /// The current element of a `{{range}}` loop, given by the range of the
+    /// expression ranged over.
+    RangeElement(TextRange),
+    /// The error caught by a `{{catch}}` body.
+    CaughtError,
+}
+
 #[derive(Debug)]
 pub struct Scope {
     pub range: TextRange,
     pub vars_by_name: HashMap<SmolStr, VarSymbolId>,
     pub declared_vars: Vec<VarSymbol>,
     pub parent: Option<ScopeId>,
+    pub dot: DotContext,
 }
 
 impl Scope {
-    pub(crate) fn new(range: TextRange, parent: Option<ScopeId>) -> Self {
+    pub(crate) fn new(range: TextRange, parent: Option<ScopeId>, dot: DotContext) -> Self {
         Self {
             range,
             vars_by_name: HashMap::new(),
             declared_vars: Vec::new(),
             parent,
+            dot,
         }
     }
 
@@ -0,0 +1,128 @@
+//! Measuring templates against the custom command length limit.
+//!
+//! YAGPDB rejects custom commands whose response is longer than a fixed
+//! number of characters, counted as Unicode code points. Since the limit is
+//! often reached in practice, [measure] also breaks down how much of the
+//! template is taken up by comments, whitespace, and each associated template
+//! definition, so that users know what to trim.
+
+use rowan::{TextRange, TextSize};
+use smol_str::SmolStr;
+use yag_template_syntax::ast::{self, AstNode};
+use yag_template_syntax::{SyntaxElement, SyntaxKind};
+
+/// The maximum length of a custom command response, in characters.
+pub const DEFAULT_CHAR_LIMIT: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct SizeReport {
+    /// The length of the entire template, in characters.
+    pub total: usize,
+    /// The characters in comments, including the delimiters of actions
+    /// containing only a comment.
+    pub comments: usize,
+    /// The characters in whitespace within actions and in text consisting only
+    /// of whitespace.
+    pub whitespace: usize,
+    /// The associated template definitions, in source order.
+    pub definitions: Vec<DefinitionSize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DefinitionSize {
+    /// The name of the template, or `None` if it is missing.
+    pub name: Option<SmolStr>,
+    /// The range of the entire `{{define}}` action.
+    pub range: TextRange,
+    /// The length of the definition, in characters.
+    pub len: usize,
+}
+
+impl SizeReport {
+    /// The number of characters remaining before `limit` is reached, or a
+    /// negative number if it is exceeded.
+    pub fn remaining(&self, limit: usize) -> isize {
+        limit as isize - self.total as isize
+    }
+}
+
+pub fn measure(root: &ast::Root) -> SizeReport {
+    let syntax = root.syntax();
+    let mut comments = 0;
+    let mut whitespace = 0;
+    for element in syntax.descendants_with_tokens() {
+        match element {
+            SyntaxElement::Node(node) if node.kind() == SyntaxKind::CommentAction => {
+                comments += char_len(&node.to_string());
+            }
+            SyntaxElement::Token(token) => match token.kind() {
+                // Comments in comment actions were counted above.
+                SyntaxKind::Comment
+                    if token
+                        .parent()
+                        .is_none_or(|parent| parent.kind() != SyntaxKind::CommentAction) =>
+                {
+                    comments += char_len(token.text())
+                }
+                SyntaxKind::Whitespace => whitespace += char_len(token.text()),
+                SyntaxKind::Text if token.text().trim().is_empty() => whitespace += char_len(token.text()),
+                _ => {}
+            },
+            SyntaxElement::Node(_) => {}
+        }
+    }
+
+    let definitions = syntax
+        .descendants()
+        .filter_map(ast::TemplateDefinition::cast)
+        .map(|define| DefinitionSize {
+            name: define
+                .clause()
+                .and_then(|clause| clause.template_name())
+                .map(|name| SmolStr::new(name.get())),
+            range: define.text_range(),
+            len: char_len(&define.syntax().to_string()),
+        })
+        .collect();
+
+    SizeReport {
+        total: char_len(&syntax.to_string()),
+        comments,
+        whitespace,
+        definitions,
+    }
+}
+
+/// The offset of the character at index `n` in `src`, or `None` if `src` has
+/// no more than `n` characters.
+pub fn nth_char_offset(src: &str, n: usize) -> Option<TextSize> {
+    src.char_indices().nth(n).map(|(i, _)| TextSize::from(i as u32))
+}
+
+fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::{SyntaxNode, ast, parser};
+
+    use super::measure;
+
+    #[test]
+    fn template_is_broken_down() {
+        let source = "{{/* ü */}}\n{{ $x := 1 /* c */ }}\n{{define \"t\"}}é{{end}}";
+        let parse = parser::parse(source);
+        assert!(parse.errors.is_empty(), "unexpected errors: {:?}", parse.errors);
+        let report = measure(&SyntaxNode::new_root(parse.root).to::<ast::Root>());
+        assert_eq!(report.total, source.chars().count());
+        assert_eq!(report.comments, "{{/* ü */}}".chars().count() + "/* c */".len());
+        // Two newlines, and spaces within the second action and the define clause.
+        assert_eq!(report.whitespace, 2 + 5 + 1);
+        assert_eq!(report.definitions.len(), 1);
+        assert_eq!(report.definitions[0].name.as_deref(), Some("t"));
+        assert_eq!(report.definitions[0].len, "{{define \"t\"}}é{{end}}".chars().count());
+        assert_eq!(report.remaining(40), 40 - report.total as isize);
+    }
+}
@@ -0,0 +1,382 @@
+//! Suppression of diagnostics via directives in comment actions.
+//!
+//! A comment action of the form `{{/* yag-ignore-next-line rule-a rule-b */}}`
+//! suppresses diagnostics from the listed rules that start on the following
+//! line, and `{{/* yag-ignore-file rule-a */}}` suppresses them throughout the
+//! template. If no rules are listed, diagnostics from all rules are suppressed.
+//! Anything after `--` is taken to be an explanation and ignored.
+//!
+//! Suppressions that do not suppress anything, or that name unknown rules, are
+//! themselves reported by the `unused-suppression` and `unknown-suppression`
+//! rules. These diagnostics are only suppressed by directives naming their
+//! rules explicitly, and such directives are never reported as unused.
+
+use rowan::{TextRange, TextSize};
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+
+use crate::lint::{Lint, LintConfig, LintContext, LintMeta, LintRegistry, WORKSPACE_LINTS};
+use crate::{Diagnostic, DiagnosticTag, Severity};
+
+pub const UNUSED_SUPPRESSION: LintMeta = LintMeta {
+    id: "unused-suppression",
+    default_severity: Severity::Warning,
+    description: "Reports suppression comments that do not suppress any diagnostic.",
+    tags: &[DiagnosticTag::Unnecessary],
+};
+
+pub const UNKNOWN_SUPPRESSION: LintMeta = LintMeta {
+    id: "unknown-suppression",
+    default_severity: Severity::Warning,
+    description: "Reports suppression comments naming rules that do not exist.",
+    tags: &[],
+};
+
+/// The rule reporting unused suppressions. Its diagnostics are produced by
+/// [apply] once all other rules have run, so its [Lint::check] does nothing.
+pub struct UnusedSuppression;
+
+impl Lint for UnusedSuppression {
+    fn meta(&self) -> &'static LintMeta {
+        &UNUSED_SUPPRESSION
+    }
+
+    fn check(&self, _cx: &mut LintContext<'_>) {}
+}
+
+/// The rule reporting suppressions of unknown rules. Like
+/// [UnusedSuppression], its diagnostics are produced by [apply].
+pub struct UnknownSuppression;
+
+impl Lint for UnknownSuppression {
+    fn meta(&self) -> &'static LintMeta {
+        &UNKNOWN_SUPPRESSION
+    }
+
+    fn check(&self, _cx: &mut LintContext<'_>) {}
+}
+
+const IGNORE_NEXT_LINE: &str = "yag-ignore-next-line";
+const IGNORE_FILE: &str = "yag-ignore-file";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuppressionScope {
+    /// The line following the comment.
+    NextLine,
+    /// The whole template.
+    File,
+}
+
+/// A single rule suppressed by a directive.
+#[derive(Debug, Clone)]
+pub struct Suppression {
+    pub scope: SuppressionScope,
+    /// The rule to suppress, or `None` to suppress all rules.
+    pub rule: Option<String>,
+    /// The range of the rule name in the directive, or of the whole directive
+    /// if no rules are listed.
+    pub range: TextRange,
+    /// The line the directive applies to, if its scope is
+    /// [SuppressionScope::NextLine].
+    line: usize,
+}
+
+/// Collect the suppression directives in `root`.
+pub fn collect(root: &ast::Root) -> Vec<Suppression> {
+    let lines = LineIndex::new(&root.syntax().to_string());
+    root.syntax()
+        .descendants()
+        .filter_map(ast::CommentAction::cast)
+        .flat_map(|action| {
+            let line = lines.line_of(action.text_range().end()) + 1;
+            action
+                .comment()
+                .map(|comment| parse_directive(&comment, line))
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn parse_directive(comment: &ast::Comment, line: usize) -> Vec<Suppression> {
+    let text = comment.text();
+    // Offset of the comment text within the source, skipping the `/*`.
+    let text_start = comment.text_range().start() + TextSize::of("/*");
+
+    let trimmed = text.trim_start();
+    let (scope, keyword) = if trimmed.starts_with(IGNORE_NEXT_LINE) {
+        (SuppressionScope::NextLine, IGNORE_NEXT_LINE)
+    } else if trimmed.starts_with(IGNORE_FILE) {
+        (SuppressionScope::File, IGNORE_FILE)
+    } else {
+        return Vec::new();
+    };
+    let keyword_start = text.len() - trimmed.len();
+    let rest = &trimmed[keyword.len()..];
+    if rest.chars().next().is_some_and(|c| !c.is_whitespace()) {
+        // Something like `yag-ignore-filex`.
+        return Vec::new();
+    }
+    let rest = rest.split("--").next().unwrap_or_default();
+    let rest_start = keyword_start + keyword.len();
+
+    let rules: Vec<Suppression> = words(rest)
+        .into_iter()
+        .map(|(offset, rule)| {
+            let start = text_start + TextSize::from((rest_start + offset) as u32);
+            Suppression {
+                scope,
+                rule: Some(rule.to_owned()),
+                range: TextRange::at(start, TextSize::of(rule)),
+                line,
+            }
+        })
+        .collect();
+    if rules.is_empty() {
+        let start = text_start + TextSize::from(keyword_start as u32);
+        vec![Suppression {
+            scope,
+            rule: None,
+            range: TextRange::at(start, TextSize::of(keyword)),
+            line,
+        }]
+    } else {
+        rules
+    }
+}
+
+/// Split `s` into words separated by whitespace or commas, returning each
+/// word along with its byte offset.
+pub(crate) fn words(s: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut word_start = None;
+    for (i, c) in s.char_indices() {
+        let is_separator = c.is_whitespace() || c == ',';
+        match word_start {
+            None if !is_separator => word_start = Some(i),
+            Some(start) if is_separator => {
+                words.push((start, &s[start..i]));
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = word_start {
+        words.push((start, &s[start..]));
+    }
+    words
+}
+
+/// Remove the diagnostics suppressed by directives in `root`, and add
+/// diagnostics for unused and unknown suppressions.
+pub(crate) fn apply(
+    root: &ast::Root,
+    registry: &LintRegistry,
+    config: &LintConfig,
+    diagnostics: Vec<Diagnostic>,
+) -> Vec<Diagnostic> {
+    let suppressions = collect(root);
+    if suppressions.is_empty() {
+        return diagnostics;
+    }
+
+    let lines = LineIndex::new(&root.syntax().to_string());
+    let mut used = vec![false; suppressions.len()];
+    let mut kept: Vec<Diagnostic> = diagnostics
+        .into_iter()
+        .filter(|diag| {
+            let line = lines.line_of(diag.range.start());
+            let mut suppressed = false;
+            for (i, suppression) in suppressions.iter().enumerate() {
+                if suppression.matches(diag, line) {
+                    used[i] = true;
+                    suppressed = true;
+                }
+            }
+            !suppressed
+        })
+        .collect();
+
+    let mut reported = Vec::new();
+    for (suppression, used) in suppressions.iter().zip(used) {
+        if used {
+            continue;
+        }
+        let (meta, message) = match &suppression.rule {
+            // Rules checked across the workspace do not run here, so there is no
+            // telling whether their suppressions are used.
+            Some(rule) if WORKSPACE_LINTS.iter().any(|meta| meta.id == rule) => continue,
+            // Suppressions of the diagnostics reported here are handled below.
+            Some(rule) if is_suppression_rule(rule) => continue,
+            Some(rule) => match registry.get(rule) {
+                None => (&UNKNOWN_SUPPRESSION, format!("unknown rule {rule} in suppression")),
+                // Suppressions of disabled rules are trivially unused.
+                Some(rule_meta) if config.severity(rule_meta).is_none() => continue,
+                Some(_) => (&UNUSED_SUPPRESSION, format!("unused suppression of {rule}")),
+            },
+            None => (&UNUSED_SUPPRESSION, "unused suppression".to_owned()),
+        };
+        if registry.get(meta.id).is_none() {
+            continue;
+        }
+        if let Some(severity) = config.severity(meta) {
+            reported.push(Diagnostic {
+                rule: meta.id,
+                severity,
+                message,
+                range: suppression.range,
+                tags: meta.tags,
+                fix: None,
+                related: Vec::new(),
+            });
+        }
+    }
+
+    kept.extend(reported.into_iter().filter(|diag| {
+        let line = lines.line_of(diag.range.start());
+        !suppressions
+            .iter()
+            .any(|suppression| suppression.rule.as_deref() == Some(diag.rule) && suppression.matches(diag, line))
+    }));
+    kept
+}
+
+fn is_suppression_rule(rule: &str) -> bool {
+    rule == UNUSED_SUPPRESSION.id || rule == UNKNOWN_SUPPRESSION.id
+}
+
+/// Remove the diagnostics suppressed by directives in `root`, without
+/// reporting unused suppressions. This is meant for diagnostics produced
+/// outside [crate::analyze], such as those of [crate::db_keys::check_workspace].
+pub fn remove_suppressed(root: &ast::Root, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let suppressions = collect(root);
+    if suppressions.is_empty() {
+        return diagnostics;
+    }
+    let lines = LineIndex::new(&root.syntax().to_string());
+    diagnostics
+        .into_iter()
+        .filter(|diag| {
+            let line = lines.line_of(diag.range.start());
+            !suppressions.iter().any(|suppression| suppression.matches(diag, line))
+        })
+        .collect()
+}
+
+impl Suppression {
+    fn matches(&self, diag: &Diagnostic, diag_line: usize) -> bool {
+        let rule_matches = self.rule.as_deref().is_none_or(|rule| rule == diag.rule);
+        let scope_matches = match self.scope {
+            SuppressionScope::NextLine => diag_line == self.line,
+            SuppressionScope::File => true,
+        };
+        rule_matches && scope_matches
+    }
+}
+
+struct LineIndex {
+    line_starts: Vec<TextSize>,
+}
+
+impl LineIndex {
+    fn new(text: &str) -> Self {
+        let line_starts = std::iter::once(TextSize::new(0))
+            .chain(text.match_indices('\n').map(|(i, _)| TextSize::from(i as u32 + 1)))
+            .collect();
+        Self { line_starts }
+    }
+
+    fn line_of(&self, offset: TextSize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yag_template_envdefs::{EnvDefSource, EnvDefs};
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::{SyntaxNode, ast, parser};
+
+    use crate::lint::{LintConfig, LintRegistry};
+    use crate::{Analysis, analyze_with};
+
+    fn envdefs() -> EnvDefs {
+        yag_template_envdefs::parse(&[EnvDefSource::new_static(
+            "test.ydef",
+            "func old()\n\tDeprecated: do not use.\n",
+        )])
+        .unwrap()
+    }
+
+    fn analyze_source(source: &str, config: &LintConfig) -> Analysis {
+        let parse = parser::parse(source);
+        assert!(parse.errors.is_empty(), "unexpected errors: {:?}", parse.errors);
+        let root = SyntaxNode::new_root(parse.root).to::<ast::Root>();
+        analyze_with(&envdefs(), root, &LintRegistry::default(), config)
+    }
+
+    fn messages(analysis: &Analysis) -> Vec<String> {
+        analysis.diagnostics.iter().map(|diag| diag.message.clone()).collect()
+    }
+
+    #[test]
+    fn next_line_suppressions_apply_only_to_the_next_line() {
+        let analysis = analyze_source(
+            "{{/* yag-ignore-next-line unused-variable */}}\n{{$x := 1}}\n{{$y := 2}}",
+            &LintConfig::default(),
+        );
+        assert_eq!(messages(&analysis), ["unused variable $y"]);
+    }
+
+    #[test]
+    fn file_suppressions_apply_everywhere() {
+        let analysis = analyze_source(
+            "{{$x := old}}\n{{/* yag-ignore-file deprecated-function, unused-variable -- legacy */}}\n{{old}}",
+            &LintConfig::default(),
+        );
+        assert!(analysis.diagnostics.is_empty(), "{:?}", messages(&analysis));
+    }
+
+    #[test]
+    fn suppressions_without_rules_suppress_all_rules() {
+        let analysis = analyze_source("{{/* yag-ignore-next-line */}}\n{{$x := old}}", &LintConfig::default());
+        assert!(analysis.diagnostics.is_empty(), "{:?}", messages(&analysis));
+    }
+
+    #[test]
+    fn unused_and_unknown_suppressions_are_reported() {
+        let source = "{{/* yag-ignore-next-line unused-variable no-such-rule */}}\n{{old}}";
+        let analysis = analyze_source(source, &LintConfig::default());
+        let reported: Vec<_> = analysis
+            .diagnostics
+            .iter()
+            .map(|diag| (diag.rule, &source[diag.range]))
+            .collect();
+        assert_eq!(
+            reported,
+            [
+                ("unused-suppression", "unused-variable"),
+                ("unknown-suppression", "no-such-rule"),
+                ("deprecated-function", "old"),
+            ]
+        );
+    }
+
+    #[test]
+    fn suppression_diagnostics_can_be_suppressed_by_name() {
+        let source =
+            "{{/* yag-ignore-file unknown-suppression */}}\n{{/* yag-ignore-next-line no-such-rule */}}\n{{1}}";
+        let analysis = analyze_source(source, &LintConfig::default());
+        assert!(analysis.diagnostics.is_empty(), "{:?}", messages(&analysis));
+
+        let registry = LintRegistry::default();
+        assert!(registry.get("unused-suppression").is_some());
+        assert!(registry.get("unknown-suppression").is_some());
+    }
+
+    #[test]
+    fn suppressions_of_disabled_rules_are_not_reported() {
+        let mut config = LintConfig::new();
+        config.disable("unused-variable");
+        let analysis = analyze_source("{{/* yag-ignore-next-line unused-variable */}}\n{{1}}", &config);
+        assert!(analysis.diagnostics.is_empty(), "{:?}", messages(&analysis));
+    }
+}
@@ -0,0 +1,296 @@
+//! The associated templates defined and used in a template.
+
+use rowan::TextRange;
+use smol_str::SmolStr;
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+use yag_template_syntax::visit::{self, Visitor};
+use yag_template_syntax::{SyntaxKind, SyntaxToken};
+
+/// Functions executing an associated template, along with the index of the
+/// parameter naming the template.
+const EXEC_TEMPLATE_FUNCS: &[(&str, usize)] = &[("execTemplate", 0), ("sendTemplate", 1), ("sendTemplateDM", 0)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateDefKind {
+    /// `{{define "name"}}`.
+    Define,
+    /// `{{block "name" data}}`, which also invokes the template.
+    Block,
+}
+
+#[derive(Debug, Clone)]
+pub struct TemplateDef {
+    pub name: SmolStr,
+    pub kind: TemplateDefKind,
+    /// The range of the name literal.
+    pub name_range: TextRange,
+    /// The range of the entire `define` or `block` action.
+    pub range: TextRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateUseKind {
+    /// `{{template "name"}}`.
+    Invocation,
+    /// `{{block "name" data}}`.
+    Block,
+    /// A call to a function such as `execTemplate` with a literal name.
+    Exec,
+}
+
+#[derive(Debug, Clone)]
+pub struct TemplateUse {
+    pub name: SmolStr,
+    pub kind: TemplateUseKind,
+    /// The range of the name literal.
+    pub name_range: TextRange,
+    /// The name of the associated template in which the use occurs, or `None`
+    /// if it is in the root template.
+    pub caller: Option<SmolStr>,
+    /// Whether the use is in a branch or loop body or follows a `{{return}}`,
+    /// and so may not be executed every time the enclosing template is.
+    pub is_conditional: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct TemplateTable {
+    defs: Vec<TemplateDef>,
+    uses: Vec<TemplateUse>,
+    has_dynamic_uses: bool,
+}
+
+impl TemplateTable {
+    /// All template definitions, in source order.
+    pub fn defs(&self) -> &[TemplateDef] {
+        &self.defs
+    }
+
+    /// All uses of templates by name, in source order.
+    pub fn uses(&self) -> &[TemplateUse] {
+        &self.uses
+    }
+
+    pub fn defs_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a TemplateDef> {
+        self.defs.iter().filter(move |def| def.name == name)
+    }
+
+    pub fn uses_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a TemplateUse> {
+        self.uses.iter().filter(move |template_use| template_use.name == name)
+    }
+
+    /// The definition in effect for the template `name`. As in Go, later
+    /// definitions replace earlier ones.
+    pub fn resolve(&self, name: &str) -> Option<&TemplateDef> {
+        self.defs.iter().rev().find(|def| def.name == name)
+    }
+
+    /// Report whether some template is executed by a name that is not a
+    /// literal, such as `{{execTemplate $name}}`, so that it is not known
+    /// which templates are used.
+    pub fn has_dynamic_uses(&self) -> bool {
+        self.has_dynamic_uses
+    }
+}
+
+pub fn build(root: &ast::Root) -> TemplateTable {
+    let mut collector = TemplateCollector {
+        table: TemplateTable::default(),
+        after_return: vec![false],
+    };
+    visit::walk_root(&mut collector, root);
+    collector.table
+}
+
+struct TemplateCollector {
+    table: TemplateTable,
+    /// For the root template and each enclosing associated template, whether
+    /// a `{{return}}` action has been seen in it so far.
+    after_return: Vec<bool>,
+}
+
+impl TemplateCollector {
+    fn add_def(&mut self, name: Option<ast::StringLiteral>, kind: TemplateDefKind, range: TextRange) {
+        if let Some(name) = name {
+            self.table.defs.push(TemplateDef {
+                name: SmolStr::new(name.get()),
+                kind,
+                name_range: name.text_range(),
+                range,
+            });
+        }
+    }
+
+    fn add_use(&mut self, name: Option<ast::StringLiteral>, kind: TemplateUseKind) {
+        if let Some(name) = name {
+            let (caller, is_conditional) = use_context(name.syntax());
+            let is_conditional = is_conditional || self.after_return.last().copied().unwrap_or_default();
+            self.table.uses.push(TemplateUse {
+                name: SmolStr::new(name.get()),
+                kind,
+                name_range: name.text_range(),
+                caller,
+                is_conditional,
+            });
+        }
+    }
+}
+
+/// Find the associated template enclosing `token`, and whether `token` is in a
+/// conditionally executed part of it.
+fn use_context(token: &SyntaxToken) -> (Option<SmolStr>, bool) {
+    let mut is_conditional = false;
+    let Some(mut prev) = token.parent() else {
+        return (None, false);
+    };
+    while let Some(node) = prev.parent() {
+        let from_body = prev.kind() == SyntaxKind::ActionList;
+        match node.kind() {
+            SyntaxKind::TemplateDefinition => {
+                let name = ast::TemplateDefinition::cast(node)
+                    .and_then(|define| define.clause())
+                    .and_then(|clause| clause.template_name());
+                return (name.map(|name| SmolStr::new(name.get())), is_conditional);
+            }
+            SyntaxKind::TemplateBlock if from_body => {
+                let name = ast::TemplateBlock::cast(node)
+                    .and_then(|block| block.clause())
+                    .and_then(|clause| clause.template_name());
+                return (name.map(|name| SmolStr::new(name.get())), is_conditional);
+            }
+            SyntaxKind::IfAction
+            | SyntaxKind::WithAction
+            | SyntaxKind::RangeLoop
+            | SyntaxKind::WhileLoop
+            | SyntaxKind::ElseBranch
+                if from_body =>
+            {
+                is_conditional = true;
+            }
+            SyntaxKind::TryCatchAction if from_body => {
+                let catch_body = ast::TryCatchAction::cast(node.clone()).and_then(|try_catch| try_catch.catch_body());
+                if catch_body.is_some_and(|body| body.syntax() == &prev) {
+                    is_conditional = true;
+                }
+            }
+            _ => {}
+        }
+        prev = node;
+    }
+    (None, is_conditional)
+}
+
+impl Visitor for TemplateCollector {
+    fn enter_template_definition(&mut self, define: &ast::TemplateDefinition) {
+        let name = define.clause().and_then(|clause| clause.template_name());
+        self.add_def(name, TemplateDefKind::Define, define.text_range());
+        self.after_return.push(false);
+    }
+
+    fn leave_template_definition(&mut self, _define: &ast::TemplateDefinition) {
+        self.after_return.pop();
+    }
+
+    fn enter_template_block(&mut self, block: &ast::TemplateBlock) {
+        let name = block.clause().and_then(|clause| clause.template_name());
+        self.add_def(name.clone(), TemplateDefKind::Block, block.text_range());
+        self.add_use(name, TemplateUseKind::Block);
+        self.after_return.push(false);
+    }
+
+    fn leave_template_block(&mut self, _block: &ast::TemplateBlock) {
+        self.after_return.pop();
+    }
+
+    fn leave_return_action(&mut self, _return_action: &ast::ReturnAction) {
+        if let Some(after_return) = self.after_return.last_mut() {
+            *after_return = true;
+        }
+    }
+
+    fn enter_template_invocation(&mut self, invocation: &ast::TemplateInvocation) {
+        self.add_use(invocation.template_name(), TemplateUseKind::Invocation);
+    }
+
+    fn enter_func_call(&mut self, call: &ast::FuncCall) {
+        let Some(func_name) = call.func_name() else {
+            return;
+        };
+        let Some(&(_, name_index)) = EXEC_TEMPLATE_FUNCS.iter().find(|(name, _)| *name == func_name.get()) else {
+            return;
+        };
+        match call.args().nth(name_index) {
+            Some(ast::Expr::Literal(lit)) => match lit.kind() {
+                ast::LiteralKind::String(name) => self.add_use(Some(name), TemplateUseKind::Exec),
+                _ => self.table.has_dynamic_uses = true,
+            },
+            // The name may also be missing, in which case it is piped in.
+            _ => self.table.has_dynamic_uses = true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::{SyntaxNode, ast, parser};
+
+    use super::{TemplateDefKind, TemplateTable, TemplateUseKind, build};
+
+    fn table(source: &str) -> TemplateTable {
+        let parse = parser::parse(source);
+        assert!(parse.errors.is_empty(), "unexpected errors: {:?}", parse.errors);
+        build(&SyntaxNode::new_root(parse.root).to::<ast::Root>())
+    }
+
+    #[test]
+    fn definitions_and_uses_are_collected() {
+        let table = table(
+            r#"{{define "a"}}{{end}}{{block "b" .}}{{end}}{{template "a"}}{{execTemplate "a" 1}}{{sendTemplate nil "c"}}"#,
+        );
+        let defs: Vec<_> = table.defs().iter().map(|def| (def.name.as_str(), def.kind)).collect();
+        assert_eq!(defs, [("a", TemplateDefKind::Define), ("b", TemplateDefKind::Block)]);
+        let uses: Vec<_> = table.uses().iter().map(|u| (u.name.as_str(), u.kind)).collect();
+        assert_eq!(
+            uses,
+            [
+                ("b", TemplateUseKind::Block),
+                ("a", TemplateUseKind::Invocation),
+                ("a", TemplateUseKind::Exec),
+                ("c", TemplateUseKind::Exec),
+            ]
+        );
+        assert!(!table.has_dynamic_uses());
+        assert!(table.resolve("a").is_some());
+    }
+
+    #[test]
+    fn uses_record_their_context() {
+        let table = table(
+            r#"{{template "a"}}{{define "t"}}{{if .}}{{template "b"}}{{end}}{{block "c" .}}{{template "d"}}{{end}}{{end}}{{try}}{{template "e"}}{{catch}}{{template "f"}}{{end}}{{return}}{{template "g"}}"#,
+        );
+        let uses: Vec<_> = table
+            .uses()
+            .iter()
+            .map(|u| (u.name.as_str(), u.caller.as_deref(), u.is_conditional))
+            .collect();
+        assert_eq!(
+            uses,
+            [
+                ("a", None, false),
+                ("b", Some("t"), true),
+                ("c", Some("t"), false),
+                ("d", Some("c"), false),
+                ("e", None, false),
+                ("f", None, true),
+                ("g", None, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn later_definitions_take_effect() {
+        let source = r#"{{define "a"}}1{{end}}{{define "a"}}2{{end}}"#;
+        let table = table(source);
+        assert_eq!(&source[table.resolve("a").unwrap().range], r#"{{define "a"}}2{{end}}"#);
+    }
+}
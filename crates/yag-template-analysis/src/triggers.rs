@@ -0,0 +1,63 @@
+//! The kinds of trigger a custom command runs for.
+//!
+//! A template declares the kinds of trigger it runs for with a comment action
+//! of the form `{{/* yag-trigger message exec */}}`, conventionally at the
+//! start of the template. Otherwise, the kinds given by
+//! [LintConfig::fallback_triggers] are used, which callers may derive from the
+//! project manifest.
+
+use rowan::{TextRange, TextSize};
+use yag_template_envdefs::TriggerKind;
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+
+use crate::suppress::words;
+
+const TRIGGER_DIRECTIVE: &str = "yag-trigger";
+
+/// The kinds of trigger declared by a `yag-trigger` directive.
+#[derive(Debug, Clone)]
+pub struct TriggerDirective {
+    pub kinds: Vec<TriggerKind>,
+    /// Names in the directive that are not kinds of trigger, with their ranges.
+    pub unknown: Vec<(String, TextRange)>,
+}
+
+/// The first `yag-trigger` directive in `root`, if any.
+pub fn directive(root: &ast::Root) -> Option<TriggerDirective> {
+    root.syntax()
+        .descendants()
+        .filter_map(ast::CommentAction::cast)
+        .filter_map(|action| action.comment())
+        .find_map(|comment| parse_directive(&comment))
+}
+
+fn parse_directive(comment: &ast::Comment) -> Option<TriggerDirective> {
+    let text = comment.text();
+    // Offset of the comment text within the source, skipping the `/*`.
+    let text_start = comment.text_range().start() + TextSize::of("/*");
+    let trimmed = text.trim_start();
+    let rest = trimmed.strip_prefix(TRIGGER_DIRECTIVE)?;
+    if rest.chars().next().is_some_and(|c| !c.is_whitespace()) {
+        // Something like `yag-triggers`.
+        return None;
+    }
+    let rest = rest.split("--").next().unwrap_or_default();
+    let rest_start = text.len() - trimmed.len() + TRIGGER_DIRECTIVE.len();
+
+    let mut directive = TriggerDirective {
+        kinds: Vec::new(),
+        unknown: Vec::new(),
+    };
+    for (offset, name) in words(rest) {
+        match TriggerKind::from_name(name) {
+            Some(kind) => directive.kinds.push(kind),
+            None => {
+                let start = text_start + TextSize::from((rest_start + offset) as u32);
+                directive
+                    .unknown
+                    .push((name.to_owned(), TextRange::at(start, TextSize::of(name))));
+            }
+        }
+    }
+    Some(directive)
+}
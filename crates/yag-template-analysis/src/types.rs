@@ -0,0 +1,350 @@
+//! Type inference for template expressions.
+//!
+//! Types are inferred bottom-up from literals, the return types of functions
+//! and the types of context fields in the environment definitions, and the
+//! initializers of variables. The
+//! inference is deliberately conservative: anything whose type cannot be
+//! determined with certainty, such as field accesses or variables that are
+//! reassigned later, is given the type [Type::Any].
+
+use foldhash::{HashMap, HashSet, HashSetExt};
+use rowan::TextRange;
+pub use yag_template_envdefs::Type;
+use yag_template_envdefs::{ContextField, EnvDefs};
+use yag_template_syntax::SyntaxKind;
+use yag_template_syntax::ast::{self, AstNode};
+use yag_template_syntax::visit::{self, Visitor};
+
+use crate::command_args::{ArgMethod, CommandArgs};
+use crate::scope::{DotContext, ScopeInfo, VarSymbolId};
+
+#[derive(Debug, Default)]
+pub struct TypeInfo {
+    expr_types: HashMap<(TextRange, SyntaxKind), Type>,
+    var_types: HashMap<VarSymbolId, Type>,
+    elem_types: HashMap<(TextRange, SyntaxKind), Type>,
+    var_elem_types: HashMap<VarSymbolId, Type>,
+}
+
+impl TypeInfo {
+    /// The inferred type of `expr`, or [Type::Any] if it is unknown.
+    pub fn type_of(&self, expr: &ast::Expr) -> Type {
+        self.expr_types.get(&expr_key(expr)).copied().unwrap_or(Type::Any)
+    }
+
+    /// The inferred type of the variable, or [Type::Any] if it is unknown.
+    pub fn var_type(&self, id: VarSymbolId) -> Type {
+        self.var_types.get(&id).copied().unwrap_or(Type::Any)
+    }
+
+    /// The inferred type of the elements of `expr`, such as those ranged over
+    /// by `{{range}}`, or [Type::Any] if it is unknown.
+    pub fn elem_type_of(&self, expr: &ast::Expr) -> Type {
+        self.elem_types.get(&expr_key(expr)).copied().unwrap_or(Type::Any)
+    }
+}
+
+fn expr_key(expr: &ast::Expr) -> (TextRange, SyntaxKind) {
+    (expr.text_range(), expr.syntax().kind())
+}
+
+pub fn infer(env: &EnvDefs, root: &ast::Root, scope_info: &ScopeInfo, command_args: &CommandArgs) -> TypeInfo {
+    let mut reassigned = ReassignmentCollector {
+        scope_info,
+        vars: HashSet::new(),
+    };
+    visit::walk_root(&mut reassigned, root);
+
+    let mut inferrer = TypeInferrer {
+        env,
+        scope_info,
+        command_args,
+        reassigned: reassigned.vars,
+        info: TypeInfo::default(),
+    };
+    visit::walk_root(&mut inferrer, root);
+    inferrer.info
+}
+
+/// Collects variables that are assigned after their declaration, whose types
+/// therefore cannot be determined from their initializers alone.
+struct ReassignmentCollector<'a> {
+    scope_info: &'a ScopeInfo,
+    vars: HashSet<VarSymbolId>,
+}
+
+impl ReassignmentCollector<'_> {
+    fn mark(&mut self, var: ast::Var) {
+        if let Some(sym) = self.scope_info.resolve_var(var) {
+            self.vars.insert(sym.id);
+        }
+    }
+}
+
+impl Visitor for ReassignmentCollector<'_> {
+    fn enter_var_assign(&mut self, assign: &ast::VarAssign) {
+        if let Some(var) = assign.var() {
+            self.mark(var);
+        }
+    }
+
+    fn enter_range_loop(&mut self, range_loop: &ast::RangeLoop) {
+        if let Some(clause) = range_loop.clause().filter(|clause| clause.assigns_vars()) {
+            clause.iteration_vars().for_each(|var| self.mark(var));
+        }
+    }
+}
+
+struct TypeInferrer<'a> {
+    env: &'a EnvDefs,
+    scope_info: &'a ScopeInfo,
+    command_args: &'a CommandArgs,
+    reassigned: HashSet<VarSymbolId>,
+    info: TypeInfo,
+}
+
+impl TypeInferrer<'_> {
+    fn opt_type_of(&self, expr: Option<ast::Expr>) -> Type {
+        expr.map_or(Type::Any, |expr| self.info.type_of(&expr))
+    }
+
+    fn var_type(&self, var: Option<ast::Var>) -> Type {
+        var.and_then(|var| self.scope_info.resolve_var(var))
+            .map_or(Type::Any, |sym| self.info.var_type(sym.id))
+    }
+
+    fn opt_elem_type_of(&self, expr: Option<ast::Expr>) -> Type {
+        expr.map_or(Type::Any, |expr| self.info.elem_type_of(&expr))
+    }
+
+    /// The field of the context data accessed by `chain`, if it consists of a
+    /// single field of the context data of the custom command.
+    fn root_field(&self, chain: &ast::ContextFieldChain) -> Option<&ContextField> {
+        let mut fields = chain.fields();
+        let field = fields.next()?;
+        if fields.next().is_some() || self.scope_info.dot_at(chain.text_range().start()) != DotContext::Root {
+            return None;
+        }
+        self.env.fields.get(field.name()?)
+    }
+
+    fn elem_type(&mut self, expr: &ast::Expr, ty: Type) -> Type {
+        use ast::Expr::*;
+
+        match expr {
+            ContextFieldChain(chain) => self.root_field(chain).map_or(Type::Any, |field| field.elem_ty),
+            Parenthesized(p) => self.opt_elem_type_of(p.inner_expr()),
+            Pipeline(p) if p.stages().next().is_none() => self.opt_elem_type_of(p.init_expr()),
+            VarAccess(access) => access
+                .var()
+                .and_then(|var| self.scope_info.resolve_var(var))
+                .and_then(|sym| self.info.var_elem_types.get(&sym.id).copied())
+                .unwrap_or(Type::Any),
+            VarDecl(decl) => {
+                let elem_ty = self.opt_elem_type_of(decl.initializer());
+                if let Some(sym) = decl.var().and_then(|var| self.scope_info.resolve_var(var))
+                    && !self.reassigned.contains(&sym.id)
+                    && elem_ty != Type::Any
+                {
+                    self.info.var_elem_types.insert(sym.id, elem_ty);
+                }
+                elem_ty
+            }
+            // Ranging over an integer n yields the integers from 0 to n-1.
+            _ if ty == Type::Int => Type::Int,
+            _ => Type::Any,
+        }
+    }
+}
+
+impl Visitor for TypeInferrer<'_> {
+    // Children are visited before `leave_expr` is called, so their types are
+    // already known.
+    fn leave_expr(&mut self, expr: &ast::Expr) {
+        use ast::Expr::*;
+
+        let ty = match expr {
+            FuncCall(call) => call
+                .func_name()
+                .and_then(|name| self.env.funcs.get(name.get()))
+                .map_or(Type::Any, |func| func.return_type),
+            Parenthesized(p) => self.opt_type_of(p.inner_expr()),
+            Pipeline(p) => match p.stages().last() {
+                Some(stage) => self.opt_type_of(stage.call_expr()),
+                None => self.opt_type_of(p.init_expr()),
+            },
+            VarAccess(access) => self.var_type(access.var()),
+            VarDecl(decl) => {
+                let ty = self.opt_type_of(decl.initializer());
+                if let Some(sym) = decl.var().and_then(|var| self.scope_info.resolve_var(var))
+                    && !self.reassigned.contains(&sym.id)
+                {
+                    self.info.var_types.insert(sym.id, ty);
+                }
+                ty
+            }
+            VarAssign(assign) => self.opt_type_of(assign.assign_expr()),
+            Literal(lit) => match lit.kind() {
+                ast::LiteralKind::String(_) => Type::String,
+                ast::LiteralKind::Bool(_) => Type::Bool,
+                ast::LiteralKind::Int(_) | ast::LiteralKind::Char(_) => Type::Int,
+                ast::LiteralKind::Float(_) => Type::Float,
+                ast::LiteralKind::Nil(_) => Type::Nil,
+            },
+            ExprCall(call) => match self.command_args.access_at(call.text_range()) {
+                Some(access) if access.method == ArgMethod::IsSet => Type::Bool,
+                Some(access) => self
+                    .command_args
+                    .resolve(access)
+                    .and_then(|def| def.ty)
+                    .map_or(Type::Any, |ty| ty.value_type()),
+                None => Type::Any,
+            },
+            ContextFieldChain(chain) => self.root_field(chain).map_or(Type::Any, |field| field.ty),
+            ContextAccess(_) | ExprFieldChain(_) => Type::Any,
+        };
+        if ty != Type::Any {
+            self.info.expr_types.insert(expr_key(expr), ty);
+        }
+        let elem_ty = self.elem_type(expr, ty);
+        if elem_ty != Type::Any {
+            self.info.elem_types.insert(expr_key(expr), elem_ty);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yag_template_envdefs::{EnvDefSource, EnvDefs};
+    use yag_template_syntax::ast::ext::SyntaxNodeExt;
+    use yag_template_syntax::ast::{self, AstNode};
+    use yag_template_syntax::{SyntaxNode, parser};
+
+    use super::Type;
+    use crate::{Analysis, analyze};
+
+    fn envdefs() -> EnvDefs {
+        yag_template_envdefs::parse(&[EnvDefSource::new_static(
+            "test.ydef",
+            "func sdict(values...) sdict\nfunc seq(start int, stop int) slice\nfunc lower(s string) string\nfunc print(args...)\n\
+             field CCID int\nfield Args []string\n",
+        )])
+        .unwrap()
+    }
+
+    fn analyze_source(source: &str) -> Analysis {
+        let parse = parser::parse(source);
+        assert!(parse.errors.is_empty(), "unexpected errors: {:?}", parse.errors);
+        let root = SyntaxNode::new_root(parse.root).to::<ast::Root>();
+        analyze(&envdefs(), root)
+    }
+
+    /// The type of the last top-level expression action in `source`.
+    fn type_of_last_expr(source: &str) -> Type {
+        let analysis = analyze_source(source);
+        let root = SyntaxNode::new_root(parser::parse(source).root).to::<ast::Root>();
+        let expr = root
+            .syntax()
+            .descendants()
+            .filter_map(ast::ExprAction::cast)
+            .last()
+            .and_then(|action| action.expr())
+            .unwrap();
+        analysis.type_of(&expr)
+    }
+
+    fn mismatches(source: &str) -> Vec<String> {
+        analyze_source(source)
+            .diagnostics
+            .into_iter()
+            .filter(|diag| diag.rule == "type-mismatch")
+            .map(|diag| diag.message)
+            .collect()
+    }
+
+    #[test]
+    fn literals_have_their_own_types() {
+        assert_eq!(type_of_last_expr(r#"{{"s"}}"#), Type::String);
+        assert_eq!(type_of_last_expr("{{1}}"), Type::Int);
+        assert_eq!(type_of_last_expr("{{'c'}}"), Type::Int);
+        assert_eq!(type_of_last_expr("{{1.5}}"), Type::Float);
+        assert_eq!(type_of_last_expr("{{true}}"), Type::Bool);
+        assert_eq!(type_of_last_expr("{{nil}}"), Type::Nil);
+    }
+
+    #[test]
+    fn calls_have_their_return_types() {
+        assert_eq!(type_of_last_expr("{{sdict}}"), Type::Sdict);
+        assert_eq!(type_of_last_expr("{{(seq 0 1)}}"), Type::Slice);
+        assert_eq!(type_of_last_expr(r#"{{"A" | lower}}"#), Type::String);
+        assert_eq!(type_of_last_expr("{{print 1}}"), Type::Any);
+        assert_eq!(type_of_last_expr("{{.Field}}"), Type::Any);
+    }
+
+    #[test]
+    fn variables_have_the_types_of_their_initializers() {
+        assert_eq!(type_of_last_expr("{{$x := sdict}}{{$x}}"), Type::Sdict);
+        assert_eq!(type_of_last_expr("{{$x := 1}}{{$x = .}}{{$x}}"), Type::Any);
+        assert_eq!(
+            type_of_last_expr("{{$x := 1}}{{range $x = seq 0 1}}{{end}}{{$x}}"),
+            Type::Any
+        );
+    }
+
+    #[test]
+    fn context_fields_have_their_declared_types() {
+        assert_eq!(type_of_last_expr("{{.CCID}}"), Type::Int);
+        assert_eq!(type_of_last_expr("{{$x := .Args}}{{$x}}"), Type::Slice);
+        assert_eq!(type_of_last_expr("{{with 1}}{{.CCID}}{{end}}"), Type::Any);
+        assert_eq!(
+            type_of_last_expr(r#"{{define "t"}}{{.CCID}}{{end}}{{.CCID.X}}"#),
+            Type::Any
+        );
+    }
+
+    #[test]
+    fn element_types_are_inferred() {
+        let elem_type_of_last_expr = |source: &str| {
+            let analysis = analyze_source(source);
+            let root = SyntaxNode::new_root(parser::parse(source).root).to::<ast::Root>();
+            let expr = root
+                .syntax()
+                .descendants()
+                .filter_map(ast::ExprAction::cast)
+                .last()
+                .and_then(|action| action.expr())
+                .unwrap();
+            analysis.types.elem_type_of(&expr)
+        };
+        assert_eq!(elem_type_of_last_expr("{{.Args}}"), Type::String);
+        assert_eq!(elem_type_of_last_expr("{{$x := (.Args)}}{{$x}}"), Type::String);
+        assert_eq!(elem_type_of_last_expr("{{5}}"), Type::Int);
+        assert_eq!(elem_type_of_last_expr("{{seq 0 5}}"), Type::Any);
+    }
+
+    #[test]
+    fn command_args_have_their_declared_types() {
+        let decl = r#"{{$args := parseArgs 1 "" (carg "int" "n") (carg "member" "m")}}"#;
+        assert_eq!(type_of_last_expr(&format!("{decl}{{{{$args.Get 0}}}}")), Type::Int);
+        assert_eq!(type_of_last_expr(&format!("{decl}{{{{$args.Get 1}}}}")), Type::Any);
+        assert_eq!(type_of_last_expr(&format!("{decl}{{{{$args.IsSet 1}}}}")), Type::Bool);
+    }
+
+    #[test]
+    fn mismatched_arguments_are_reported() {
+        assert_eq!(
+            mismatches("{{$d := sdict}}{{seq 0 $d}}"),
+            ["expected int for parameter stop of seq, found sdict"]
+        );
+        assert_eq!(
+            mismatches("{{1 | lower}}"),
+            ["expected string for parameter s of lower, found int from pipeline"]
+        );
+    }
+
+    #[test]
+    fn compatible_arguments_are_not_reported() {
+        assert!(mismatches(r#"{{seq 0 "5"}}{{seq .A 2.0}}{{lower "A"}}{{print (sdict) 1 nil}}"#).is_empty());
+        assert!(mismatches("{{$x := sdict}}{{$x = 1}}{{seq 0 $x}}").is_empty());
+    }
+}
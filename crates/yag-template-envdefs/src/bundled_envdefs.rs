@@ -11,6 +11,7 @@ macro_rules! sources {
 pub fn load() -> Result<EnvDefs, ParseError> {
     static BUNDLED_SOURCES: &[EnvDefSource] = sources![
         "builtin_funcs.ydef",
+        "context_data.ydef",
         "context_funcs.ydef",
         "ext_plugin_funcs.ydef",
         "general_funcs.ydef",
@@ -12,14 +12,23 @@ pub mod bundled_envdefs;
 #[derive(Debug)]
 pub struct EnvDefs {
     pub funcs: HashMap<String, Func>,
+    pub fields: HashMap<String, ContextField>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Func {
     pub name: String,
     pub params: Vec<Param>,
+    pub return_type: Type,
     pub doc: String,
     pub is_deprecated: bool,
+    /// The cap on calls to the function in a single execution, if any.
+    pub limit: Option<CallLimit>,
+    /// The kinds of trigger for which the function may be used, or `None` if
+    /// it may be used regardless of the trigger.
+    pub triggers: Option<Vec<TriggerKind>>,
+    /// The role of the function in the flow of user-controlled data, if any.
+    pub taint: Option<Taint>,
 }
 
 impl Func {
@@ -38,6 +47,9 @@ impl Func {
             }
         }
         buf.push(')');
+        if self.return_type != Type::Any {
+            write!(buf, " {}", self.return_type).unwrap();
+        }
         buf
     }
 }
@@ -47,6 +59,7 @@ pub struct Param {
     pub name: String,
     pub is_optional: bool,
     pub is_variadic: bool,
+    pub ty: Type,
 }
 
 impl fmt::Display for Param {
@@ -58,10 +71,184 @@ impl fmt::Display for Param {
         if self.is_variadic {
             f.write_str("...")?;
         }
+        if self.ty != Type::Any {
+            write!(f, " {}", self.ty)?;
+        }
         Ok(())
     }
 }
 
+/// A field of the context data `.` of a custom command, such as `.User`.
+#[derive(Debug, Clone)]
+pub struct ContextField {
+    pub name: String,
+    pub ty: Type,
+    /// The type of the elements of the field, if it is a slice.
+    pub elem_ty: Type,
+    pub doc: String,
+    /// The kinds of trigger for which the field exists, or `None` if it
+    /// always exists.
+    pub triggers: Option<Vec<TriggerKind>>,
+    /// Whether the field holds user-controlled data; only [Taint::Source] is
+    /// meaningful for fields.
+    pub taint: Option<Taint>,
+}
+
+/// The role of a function or context field in the flow of user-controlled
+/// data, such as the content of the triggering message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Taint {
+    /// The value is controlled by the user triggering the custom command. If
+    /// `fields` is nonempty, only those fields of the value are, as with the
+    /// content of a message object.
+    Source { fields: Vec<String> },
+    /// The function must not be passed user-controlled data in the given
+    /// parameters, or in any parameter if `params` is empty.
+    Sink { params: Vec<String> },
+    /// The result of the function is safe regardless of its arguments.
+    Sanitizer,
+}
+
+/// A kind of event that runs a custom command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriggerKind {
+    /// A message, as with command, regex, and other text-based triggers.
+    Message,
+    Reaction,
+    Interval,
+    /// A message component, such as a button or select menu.
+    Component,
+    /// A modal submission.
+    Modal,
+    /// An execution by another custom command, as with `execCC`.
+    Exec,
+}
+
+impl TriggerKind {
+    pub const ALL: &[TriggerKind] = &[
+        TriggerKind::Message,
+        TriggerKind::Reaction,
+        TriggerKind::Interval,
+        TriggerKind::Component,
+        TriggerKind::Modal,
+        TriggerKind::Exec,
+    ];
+
+    pub fn from_name(name: &str) -> Option<TriggerKind> {
+        Self::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TriggerKind::Message => "message",
+            TriggerKind::Reaction => "reaction",
+            TriggerKind::Interval => "interval",
+            TriggerKind::Component => "component",
+            TriggerKind::Modal => "modal",
+            TriggerKind::Exec => "exec",
+        }
+    }
+}
+
+impl fmt::Display for TriggerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A cap on the number of calls to a function per template execution. Several
+/// functions may share a counter, in which case calls to any of them count
+/// toward the same maximum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallLimit {
+    pub counter: String,
+    /// The maximum number of calls on a non-premium server.
+    pub max: u32,
+    /// The maximum number of calls on a premium server.
+    pub premium_max: u32,
+}
+
+impl CallLimit {
+    /// The maximum number of calls on a server with or without premium.
+    pub fn max_for(&self, premium: bool) -> u32 {
+        if premium { self.premium_max } else { self.max }
+    }
+}
+
+/// The type of a parameter, return value, or expression.
+///
+/// Types are deliberately coarse: they distinguish the kinds of value that
+/// template functions commonly accept and return, not the exact Go types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    /// Any value; the type is unknown or unconstrained.
+    Any,
+    Nil,
+    Bool,
+    Int,
+    Float,
+    String,
+    Duration,
+    Time,
+    Slice,
+    Dict,
+    Sdict,
+}
+
+impl Type {
+    pub fn from_name(name: &str) -> Option<Type> {
+        Some(match name {
+            "any" => Type::Any,
+            "nil" => Type::Nil,
+            "bool" => Type::Bool,
+            "int" => Type::Int,
+            "float" => Type::Float,
+            "string" => Type::String,
+            "duration" => Type::Duration,
+            "time" => Type::Time,
+            "slice" => Type::Slice,
+            "dict" => Type::Dict,
+            "sdict" => Type::Sdict,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Type::Any => "any",
+            Type::Nil => "nil",
+            Type::Bool => "bool",
+            Type::Int => "int",
+            Type::Float => "float",
+            Type::String => "string",
+            Type::Duration => "duration",
+            Type::Time => "time",
+            Type::Slice => "slice",
+            Type::Dict => "dict",
+            Type::Sdict => "sdict",
+        }
+    }
+
+    /// Report whether a value of this type can be passed to a parameter of type
+    /// `param`. Numeric parameters also accept strings and durations, since
+    /// template functions generally convert their numeric arguments.
+    pub fn is_assignable_to(self, param: Type) -> bool {
+        use Type::*;
+        match (self, param) {
+            (Any, _) | (_, Any) => true,
+            (Int | Float | String | Duration, Int | Float | Duration) => true,
+            (Nil, Slice | Dict | Sdict) => true,
+            _ => self == param,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 #[derive(Debug)]
 pub struct ParseError {
     pub src_name: String,
@@ -110,13 +297,56 @@ impl EnvDefSource {
 }
 
 pub fn parse(sources: &[EnvDefSource]) -> Result<EnvDefs, ParseError> {
-    let mut defs = EnvDefs { funcs: HashMap::new() };
+    let mut defs = EnvDefs {
+        funcs: HashMap::new(),
+        fields: HashMap::new(),
+    };
     for src in sources {
         process_source(&mut defs, src)?;
     }
     Ok(defs)
 }
 
+/// A definition in the process of being parsed.
+enum Def {
+    Func(Func),
+    Field(ContextField),
+}
+
+impl Def {
+    /// Report whether the definition has neither documentation nor trigger
+    /// kinds yet, so that a `triggers` line may follow.
+    fn accepts_triggers(&self) -> bool {
+        match self {
+            Def::Func(f) => f.doc.is_empty() && f.triggers.is_none(),
+            Def::Field(f) => f.doc.is_empty() && f.triggers.is_none(),
+        }
+    }
+
+    /// Report whether the definition has neither documentation nor a taint
+    /// role yet, so that a `taint` line may follow.
+    fn accepts_taint(&self) -> bool {
+        match self {
+            Def::Func(f) => f.doc.is_empty() && f.taint.is_none(),
+            Def::Field(f) => f.doc.is_empty() && f.taint.is_none(),
+        }
+    }
+
+    fn doc_mut(&mut self) -> &mut String {
+        match self {
+            Def::Func(f) => &mut f.doc,
+            Def::Field(f) => &mut f.doc,
+        }
+    }
+
+    fn triggers_mut(&mut self) -> &mut Option<Vec<TriggerKind>> {
+        match self {
+            Def::Func(f) => &mut f.triggers,
+            Def::Field(f) => &mut f.triggers,
+        }
+    }
+}
+
 fn process_source(defs: &mut EnvDefs, src: &EnvDefSource) -> Result<(), ParseError> {
     macro_rules! bail {
         ($msg:expr, $lineno:expr) => {
@@ -124,52 +354,98 @@ fn process_source(defs: &mut EnvDefs, src: &EnvDefSource) -> Result<(), ParseErr
         };
     }
 
-    let mut funcs: Vec<(Func, usize)> = vec![];
+    let mut parsed: Vec<(Def, usize)> = vec![];
     for (lineno, line) in src.data.lines().enumerate() {
         if line.starts_with("==") {
             // comment; ignore
         } else if line.chars().all(char::is_whitespace) {
-            // blank line, possibly separating paragraphs in function documentation
-            if let Some((f, _)) = funcs.last_mut()
-                && !f.doc.is_empty()
+            // blank line, possibly separating paragraphs in documentation
+            if let Some((def, _)) = parsed.last_mut()
+                && !def.doc_mut().is_empty()
             {
                 // might result in some trailing blank lines, but it's OK:
                 // we'll trim the documentation at the end
-                f.doc.push('\n');
+                def.doc_mut().push('\n');
             }
         } else if line.starts_with("func") {
             match parse_func_signature(line) {
-                Ok(f) => funcs.push((f, lineno)),
+                Ok(f) => parsed.push((Def::Func(f), lineno)),
+                Err(msg) => bail!(msg, lineno),
+            }
+        } else if let Some(name) = line.strip_prefix("field ") {
+            match parse_field(name) {
+                Ok(f) => parsed.push((Def::Field(f), lineno)),
                 Err(msg) => bail!(msg, lineno),
             }
+        } else if let Some(limit) = line.strip_prefix("limit ") {
+            match parsed.last_mut() {
+                Some((Def::Func(f), _)) if f.doc.is_empty() && f.limit.is_none() => match parse_call_limit(limit) {
+                    Ok(limit) => f.limit = Some(limit),
+                    Err(msg) => bail!(msg, lineno),
+                },
+                _ => bail!("limit must directly follow a function signature", lineno),
+            }
+        } else if let Some(triggers) = line.strip_prefix("triggers ") {
+            match parsed.last_mut() {
+                Some((def, _)) if def.accepts_triggers() => match parse_trigger_kinds(triggers) {
+                    Ok(kinds) => *def.triggers_mut() = Some(kinds),
+                    Err(msg) => bail!(msg, lineno),
+                },
+                _ => bail!("triggers must directly follow a function signature or field", lineno),
+            }
+        } else if let Some(taint) = line.strip_prefix("taint ") {
+            let taint = match parse_taint(taint) {
+                Ok(taint) => taint,
+                Err(msg) => bail!(msg, lineno),
+            };
+            match parsed.last_mut() {
+                Some((def, _)) if def.accepts_taint() => {
+                    if let Err(msg) = set_taint(def, taint) {
+                        bail!(msg, lineno)
+                    }
+                }
+                _ => bail!("taint must directly follow a function signature or field", lineno),
+            }
         } else if let Some(doc_line) = line.strip_prefix('\t') {
-            match funcs.last_mut() {
-                Some((f, _)) => {
-                    f.doc.push_str(doc_line);
-                    f.doc.push('\n');
+            match parsed.last_mut() {
+                Some((def, _)) => {
+                    def.doc_mut().push_str(doc_line);
+                    def.doc_mut().push('\n');
                 }
-                None => bail!("unexpected indented line not part of function documentation", lineno),
+                None => bail!("unexpected indented line not part of documentation", lineno),
             }
         } else {
             bail!(
-                "could not interpret line as comment, function signature, or indented documentation",
+                "could not interpret line as comment, function signature, field, limit, triggers, taint, or \
+                 indented documentation",
                 lineno
             )
         }
     }
 
-    for (func, lineno) in funcs {
-        match defs.funcs.entry(func.name.clone()) {
-            Entry::Occupied(_) => bail!(format!("duplicate definition for function {}", func.name), lineno),
-            Entry::Vacant(entry) => {
-                let trimmed_doc = func.doc.trim();
-                entry.insert(Func {
-                    doc: trimmed_doc.into(),
-                    is_deprecated: has_deprecation_marker(trimmed_doc),
-                    ..func
-                })
-            }
-        };
+    for (def, lineno) in parsed {
+        match def {
+            Def::Func(func) => match defs.funcs.entry(func.name.clone()) {
+                Entry::Occupied(_) => bail!(format!("duplicate definition for function {}", func.name), lineno),
+                Entry::Vacant(entry) => {
+                    let trimmed_doc = func.doc.trim();
+                    entry.insert(Func {
+                        doc: trimmed_doc.into(),
+                        is_deprecated: has_deprecation_marker(trimmed_doc),
+                        ..func
+                    });
+                }
+            },
+            Def::Field(field) => match defs.fields.entry(field.name.clone()) {
+                Entry::Occupied(_) => bail!(format!("duplicate definition for field {}", field.name), lineno),
+                Entry::Vacant(entry) => {
+                    entry.insert(ContextField {
+                        doc: field.doc.trim().into(),
+                        ..field
+                    });
+                }
+            },
+        }
     }
     Ok(())
 }
@@ -220,24 +496,311 @@ fn parse_func_signature(line: &str) -> Result<Func, String> {
                 "parameter {param_name} cannot be marked as both variadic and optional; variadic implies optional"
             ));
         }
+
+        // Parse the optional type annotation.
+        s.eat_whitespace();
+        let ty = parse_type(&mut s)?;
         params.push(Param {
             name: param_name.into(),
             is_optional,
             is_variadic,
+            ty,
         });
     }
     ensure!(s.eat_if(')'), "expected ')' concluding parameter list");
 
+    // Parse the optional return type annotation.
+    s.eat_whitespace();
+    let return_type = parse_type(&mut s)?;
+
     s.eat_whitespace();
-    ensure!(s.done(), "expected line to end after parameter list");
+    ensure!(s.done(), "expected line to end after parameter list and return type");
     Ok(Func {
         name: name.into(),
         params,
+        return_type,
         doc: String::new(),
         is_deprecated: false, // assume not deprecated at this stage
+        limit: None,
+        triggers: None,
+        taint: None,
+    })
+}
+
+/// Parse the remainder of a `field <name> [type]` line, where the type of a
+/// slice may give the type of its elements as in `[]string`.
+fn parse_field(line: &str) -> Result<ContextField, String> {
+    let mut s = Scanner::new(line);
+    s.eat_whitespace();
+    let name = s.eat_while(is_ident_char);
+    ensure!(!name.is_empty(), "expected field name after 'field'");
+    s.eat_whitespace();
+    let (ty, elem_ty) = if s.eat_if("[]") {
+        (Type::Slice, parse_type(&mut s)?)
+    } else {
+        (parse_type(&mut s)?, Type::Any)
+    };
+    s.eat_whitespace();
+    ensure!(s.done(), "expected line to end after field type");
+    Ok(ContextField {
+        name: name.into(),
+        ty,
+        elem_ty,
+        doc: String::new(),
+        triggers: None,
+        taint: None,
     })
 }
 
+/// Parse the remainder of a `triggers <kind>...` line.
+fn parse_trigger_kinds(line: &str) -> Result<Vec<TriggerKind>, String> {
+    let kinds = line
+        .split_whitespace()
+        .map(|name| TriggerKind::from_name(name).ok_or_else(|| format!("unknown trigger kind {name}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    ensure!(!kinds.is_empty(), "expected trigger kinds after 'triggers'");
+    Ok(kinds)
+}
+
+/// Parse the remainder of a `taint source [field...]`, `taint sink [param...]`,
+/// or `taint sanitizer` line.
+fn parse_taint(line: &str) -> Result<Taint, String> {
+    let mut words = line.split_whitespace();
+    let role = words.next().ok_or("expected taint role after 'taint'")?;
+    let names: Vec<String> = words.map(String::from).collect();
+    match role {
+        "source" => Ok(Taint::Source { fields: names }),
+        "sink" => Ok(Taint::Sink { params: names }),
+        "sanitizer" => {
+            ensure!(names.is_empty(), "expected line to end after 'sanitizer'");
+            Ok(Taint::Sanitizer)
+        }
+        _ => Err(format!(
+            "unknown taint role {role}; expected source, sink, or sanitizer"
+        )),
+    }
+}
+
+/// Attach `taint` to `def`, checking that it makes sense for the definition.
+fn set_taint(def: &mut Def, taint: Taint) -> Result<(), String> {
+    match def {
+        Def::Func(f) => {
+            if let Taint::Sink { params } = &taint
+                && let Some(unknown) = params
+                    .iter()
+                    .find(|name| !f.params.iter().any(|param| param.name == **name))
+            {
+                return Err(format!("function {} has no parameter {unknown}", f.name));
+            }
+            f.taint = Some(taint);
+        }
+        Def::Field(f) => {
+            ensure!(
+                matches!(taint, Taint::Source { .. }),
+                "fields may only be marked as taint sources"
+            );
+            f.taint = Some(taint);
+        }
+    }
+    Ok(())
+}
+
+/// Parse the remainder of a `limit <counter> <max> [premium <max>]` line.
+fn parse_call_limit(line: &str) -> Result<CallLimit, String> {
+    let mut s = Scanner::new(line);
+    s.eat_whitespace();
+    ensure!(s.at(is_ident_char), "expected counter name after 'limit'");
+    let counter = s.eat_while(is_ident_char);
+    s.eat_whitespace();
+    let max = parse_max_calls(&mut s, "expected maximum number of calls after counter name")?;
+    s.eat_whitespace();
+    let premium_max = if s.eat_if("premium") {
+        s.eat_whitespace();
+        parse_max_calls(&mut s, "expected maximum number of calls after 'premium'")?
+    } else {
+        max
+    };
+    s.eat_whitespace();
+    ensure!(s.done(), "expected line to end after maximum number of calls");
+    Ok(CallLimit {
+        counter: counter.into(),
+        max,
+        premium_max,
+    })
+}
+
+fn parse_max_calls(s: &mut Scanner, missing: &str) -> Result<u32, String> {
+    let max = s.eat_while(|c: char| c.is_ascii_digit());
+    ensure!(!max.is_empty(), missing);
+    max.parse()
+        .map_err(|_| format!("maximum number of calls {max} is too large"))
+}
+
+/// Parse a type name if one is present, returning [Type::Any] otherwise.
+fn parse_type(s: &mut Scanner) -> Result<Type, String> {
+    if !s.at(is_ident_char) {
+        return Ok(Type::Any);
+    }
+    let type_name = s.eat_while(is_ident_char);
+    Type::from_name(type_name).ok_or_else(|| format!("unknown type {type_name}"))
+}
+
 fn is_ident_char(c: char) -> bool {
     c == '_' || c.is_ascii_alphanumeric()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CallLimit, EnvDefSource, Taint, TriggerKind, Type, parse};
+
+    fn parse_one(def: &'static str) -> Result<super::Func, super::ParseError> {
+        let mut defs = parse(&[EnvDefSource::new_static("test.ydef", def)])?;
+        Ok(defs.funcs.drain().next().unwrap().1)
+    }
+
+    #[test]
+    fn type_annotations_are_optional() {
+        let func = parse_one("func f(x, y?, z...)\n").unwrap();
+        assert!(func.params.iter().all(|param| param.ty == Type::Any));
+        assert_eq!(func.return_type, Type::Any);
+        assert_eq!(func.signature(), "func f(x, y?, z...)");
+    }
+
+    #[test]
+    fn type_annotations_are_parsed() {
+        let func = parse_one("func slice(item, start int, end? int, rest... string) slice\n").unwrap();
+        let types: Vec<_> = func.params.iter().map(|param| param.ty).collect();
+        assert_eq!(types, [Type::Any, Type::Int, Type::Int, Type::String]);
+        assert_eq!(func.return_type, Type::Slice);
+        assert_eq!(
+            func.signature(),
+            "func slice(item, start int, end? int, rest... string) slice"
+        );
+    }
+
+    #[test]
+    fn unknown_types_are_rejected() {
+        let err = parse_one("func f(x integer)\n").unwrap_err();
+        assert_eq!(err.message, "unknown type integer");
+        let err = parse_one("func f() integer\n").unwrap_err();
+        assert_eq!(err.message, "unknown type integer");
+    }
+
+    #[test]
+    fn call_limits_are_parsed() {
+        let func =
+            parse_one("func dbGet(userID, key)\nlimit db_interactions 10 premium 50\n\tGets a value.\n").unwrap();
+        assert_eq!(
+            func.limit,
+            Some(CallLimit {
+                counter: "db_interactions".into(),
+                max: 10,
+                premium_max: 50
+            })
+        );
+        assert_eq!(func.doc, "Gets a value.");
+        let func = parse_one("func sendDM(msg)\nlimit send_dm 1\n").unwrap();
+        assert_eq!(func.limit.unwrap().max_for(true), 1);
+        assert!(parse_one("func f()\n").unwrap().limit.is_none());
+    }
+
+    #[test]
+    fn misplaced_or_malformed_call_limits_are_rejected() {
+        let err = parse_one("func f()\n\tDocs.\nlimit f 1\n").unwrap_err();
+        assert_eq!(err.message, "limit must directly follow a function signature");
+        let err = parse_one("func f()\nlimit f\n").unwrap_err();
+        assert_eq!(err.message, "expected maximum number of calls after counter name");
+        let err = parse_one("func f()\nlimit f 1 premium\n").unwrap_err();
+        assert_eq!(err.message, "expected maximum number of calls after 'premium'");
+    }
+
+    #[test]
+    fn trigger_kinds_and_fields_are_parsed() {
+        let defs = parse(&[EnvDefSource::new_static(
+            "test.ydef",
+            "func updateMessage(msg)\ntriggers component\n\tUpdates.\n\nfield Reaction\ntriggers reaction\n\tThe \
+             reaction.\nfield User\n",
+        )])
+        .unwrap();
+        assert_eq!(defs.funcs["updateMessage"].triggers, Some(vec![TriggerKind::Component]));
+        assert_eq!(defs.funcs["updateMessage"].doc, "Updates.");
+        assert_eq!(defs.fields["Reaction"].triggers, Some(vec![TriggerKind::Reaction]));
+        assert_eq!(defs.fields["Reaction"].doc, "The reaction.");
+        assert!(defs.fields["User"].triggers.is_none());
+    }
+
+    #[test]
+    fn field_types_are_parsed() {
+        let defs = parse(&[EnvDefSource::new_static(
+            "test.ydef",
+            "field CCID int\nfield Args []string\nfield User\n",
+        )])
+        .unwrap();
+        assert_eq!(
+            (defs.fields["CCID"].ty, defs.fields["CCID"].elem_ty),
+            (Type::Int, Type::Any)
+        );
+        assert_eq!(
+            (defs.fields["Args"].ty, defs.fields["Args"].elem_ty),
+            (Type::Slice, Type::String)
+        );
+        assert_eq!(
+            (defs.fields["User"].ty, defs.fields["User"].elem_ty),
+            (Type::Any, Type::Any)
+        );
+        let err = parse(&[EnvDefSource::new_static("test.ydef", "field Args []strings\n")]).unwrap_err();
+        assert_eq!(err.message, "unknown type strings");
+    }
+
+    #[test]
+    fn misplaced_or_malformed_triggers_are_rejected() {
+        let err = parse_one("func f()\n\tDocs.\ntriggers exec\n").unwrap_err();
+        assert_eq!(
+            err.message,
+            "triggers must directly follow a function signature or field"
+        );
+        let err = parse_one("func f()\ntriggers button\n").unwrap_err();
+        assert_eq!(err.message, "unknown trigger kind button");
+    }
+
+    #[test]
+    fn taint_roles_are_parsed() {
+        let defs = parse(&[EnvDefSource::new_static(
+            "test.ydef",
+            "func sendMessageNoEscape(channel, message)\nlimit api_calls 100\ntaint sink message\n\tSends.\n\
+             func sanitizeText(s string) string\ntaint sanitizer\nfield Message\ntaint source Content\n\
+             field StrippedMsg\ntriggers message\ntaint source\n",
+        )])
+        .unwrap();
+        assert_eq!(
+            defs.funcs["sendMessageNoEscape"].taint,
+            Some(Taint::Sink {
+                params: vec!["message".into()]
+            })
+        );
+        assert_eq!(defs.funcs["sendMessageNoEscape"].doc, "Sends.");
+        assert_eq!(defs.funcs["sanitizeText"].taint, Some(Taint::Sanitizer));
+        assert_eq!(
+            defs.fields["Message"].taint,
+            Some(Taint::Source {
+                fields: vec!["Content".into()]
+            })
+        );
+        assert_eq!(defs.fields["StrippedMsg"].taint, Some(Taint::Source { fields: vec![] }));
+    }
+
+    #[test]
+    fn misplaced_or_malformed_taint_roles_are_rejected() {
+        let err = parse_one("func f(x)\ntaint sink y\n").unwrap_err();
+        assert_eq!(err.message, "function f has no parameter y");
+        let err = parse_one("func f()\ntaint escape\n").unwrap_err();
+        assert_eq!(
+            err.message,
+            "unknown taint role escape; expected source, sink, or sanitizer"
+        );
+        let err = parse_one("func f()\n\tDocs.\ntaint source\n").unwrap_err();
+        assert_eq!(err.message, "taint must directly follow a function signature or field");
+        let err = parse(&[EnvDefSource::new_static("test.ydef", "field Args\ntaint sanitizer\n")]).unwrap_err();
+        assert_eq!(err.message, "fields may only be marked as taint sources");
+    }
+}
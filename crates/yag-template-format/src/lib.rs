@@ -96,8 +96,9 @@ pub fn format(source: &str, envdefs: &EnvDefs, options: &FormatOptions) -> Forma
 #[cfg(test)]
 mod tests {
     use yag_template_envdefs::{EnvDefSource, bundled_envdefs};
+    use yag_template_syntax::parser::MAX_NESTING_DEPTH;
 
-    use super::{DelimiterPadding, FormatOptions, format};
+    use super::{DelimiterPadding, FormatDiagnosticKind, FormatOptions, format};
 
     #[test]
     fn formatting_overwidth_protected_text_has_no_diagnostics() {
@@ -143,4 +144,39 @@ mod tests {
             assert!(result.text.contains("\"a\"\n"), "{name}: {}", result.text);
         }
     }
+
+    #[test]
+    fn formatting_maximally_nested_input_does_not_overflow() {
+        let envdefs = bundled_envdefs::load().unwrap();
+        // The argument to `add` is two levels deeper than the condition itself.
+        let depth = MAX_NESTING_DEPTH - 2;
+        let source = format!("{}x{}", "{{if (add 1 2)}}".repeat(depth), "{{end}}".repeat(depth));
+        let result = format(&source, &envdefs, &FormatOptions::default());
+
+        assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+    }
+
+    #[test]
+    fn formatting_long_else_if_chains_does_not_overflow() {
+        let envdefs = bundled_envdefs::load().unwrap();
+        let source = format!("{{{{if 1}}}}{}{{{{end}}}}", "{{else if 1}}x".repeat(20_000));
+        let result = format(&source, &envdefs, &FormatOptions::default());
+
+        assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+    }
+
+    #[test]
+    fn overly_nested_input_is_returned_unchanged() {
+        let envdefs = bundled_envdefs::load().unwrap();
+        let source = format!("{{{{{}1{}}}}}", "(".repeat(20_000), ")".repeat(20_000));
+        let result = format(&source, &envdefs, &FormatOptions::default());
+
+        assert_eq!(result.text, source);
+        assert!(
+            result
+                .diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.kind == FormatDiagnosticKind::ParseError)
+        );
+    }
 }
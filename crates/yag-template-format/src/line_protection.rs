@@ -2,7 +2,8 @@
 
 use std::ops::Range;
 
-use yag_template_syntax::ast::{Action, AstNode, Expr};
+use yag_template_syntax::ast::{self, AstNode, Expr};
+use yag_template_syntax::visit::{self, Visitor};
 use yag_template_syntax::{SyntaxKind, SyntaxNode};
 
 use crate::line_index::LineIndex;
@@ -81,13 +82,8 @@ impl LineProtector {
     }
 
     fn protect_display_lines(&mut self, root: &SyntaxNode) {
-        for action in root.descendants().filter_map(Action::cast) {
-            let Action::ExprAction(action) = action else {
-                continue;
-            };
-            if action.expr().is_some_and(is_output_expression) {
-                self.protect_range(source_range(&action));
-            }
+        if let Some(root) = ast::Root::cast(root.clone()) {
+            visit::walk_root(self, &root);
         }
     }
 
@@ -107,6 +103,14 @@ impl LineProtector {
     }
 }
 
+impl Visitor for LineProtector {
+    fn enter_expr_action(&mut self, action: &ast::ExprAction) {
+        if action.expr().is_some_and(is_output_expression) {
+            self.protect_range(source_range(action));
+        }
+    }
+}
+
 fn source_range(node: &impl AstNode) -> Range<usize> {
     let range = node.text_range();
     byte_offset(range.start())..byte_offset(range.end())
@@ -3,6 +3,7 @@ use std::ops::Range;
 use yag_template_envdefs::EnvDefs;
 use yag_template_syntax::SyntaxNode;
 use yag_template_syntax::ast::{AstNode, Root};
+use yag_template_syntax::trivia::CommentMap;
 
 use crate::FormatOptions;
 use crate::line_protection::{LineProtection, ReflowPolicy};
@@ -19,7 +20,7 @@ pub(super) fn lower(
     let Some(root) = Root::cast(root.clone()) else {
         return text(source);
     };
-    let mut f = Formatter::new(source, envdefs, options, protection);
+    let mut f = Formatter::new(source, envdefs, options, protection, CommentMap::build(root.syntax()));
     f.root(&root)
 }
 
@@ -29,17 +30,25 @@ pub(crate) struct Formatter<'a> {
     pub(crate) envdefs: &'a EnvDefs,
     pub(crate) options: &'a FormatOptions,
     pub(crate) protection: &'a LineProtection,
+    pub(crate) comments: CommentMap,
     next_group_id: usize,
 }
 
 impl<'a> Formatter<'a> {
     /// Build the context used for one lowering pass.
-    fn new(source: &'a str, envdefs: &'a EnvDefs, options: &'a FormatOptions, protection: &'a LineProtection) -> Self {
+    fn new(
+        source: &'a str,
+        envdefs: &'a EnvDefs,
+        options: &'a FormatOptions,
+        protection: &'a LineProtection,
+        comments: CommentMap,
+    ) -> Self {
         Self {
             source,
             envdefs,
             options,
             protection,
+            comments,
             next_group_id: 0,
         }
     }
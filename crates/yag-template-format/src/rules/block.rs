@@ -1,6 +1,7 @@
 //! Declarative document rules for compound bodies.
 
-use yag_template_syntax::ast::{ActionList, ActionOrText, AstNode, AstToken, Root, Text};
+use yag_template_syntax::ast::{Action, ActionList, ActionOrText, AstNode, AstToken, Root, Text};
+use yag_template_syntax::trivia::CommentPlacement;
 
 use crate::cursor::DoubleEndedPeekable;
 use crate::iterutil::iter_with_neighbors;
@@ -187,6 +188,9 @@ impl<'a> Formatter<'a> {
     /// remaining text is passed to [`lower_text`]. This keeps action
     /// separation policy in one place while typed action rules remain
     /// responsible only for their own syntax.
+    ///
+    /// Comments trailing an action stay on that action's line, so that they
+    /// remain attached to the same action after formatting.
     fn sequence(&mut self, elements: &[ActionOrText], allow_compact: bool) -> LoweredSequence {
         let action_separator = if allow_compact { soft_line() } else { hard_line() };
 
@@ -195,7 +199,10 @@ impl<'a> Formatter<'a> {
         for (previous, element, next) in iter_with_neighbors(elements) {
             match element {
                 ActionOrText::Action(action) => {
-                    if matches!(previous, Some(ActionOrText::Action(_))) && self.may_reflow_before(action) {
+                    if matches!(previous, Some(ActionOrText::Action(_)))
+                        && self.may_reflow_before(action)
+                        && !self.is_trailing_comment(action)
+                    {
                         parts.push(action_separator.clone());
                     }
                     parts.push(self.action(action.clone()));
@@ -204,7 +211,12 @@ impl<'a> Formatter<'a> {
                     let separates_actions = matches!(previous, Some(ActionOrText::Action(_)))
                         && matches!(next, Some(ActionOrText::Action(_)));
                     if separates_actions && self.may_reflow_inline_whitespace(literal) {
-                        parts.push(action_separator.clone());
+                        match next {
+                            Some(ActionOrText::Action(next)) if self.is_trailing_comment(next) => {
+                                parts.push(text(" "));
+                            }
+                            _ => parts.push(action_separator.clone()),
+                        }
                     } else {
                         let (doc, final_line) = self.lower_text(literal, next.is_none());
                         trailing_line |= final_line;
@@ -239,6 +251,16 @@ impl<'a> Formatter<'a> {
         (doc, trailing_line)
     }
 
+    /// Return whether `action` is a comment trailing the action before it.
+    fn is_trailing_comment(&self, action: &Action) -> bool {
+        let Action::Comment(comment) = action else {
+            return false;
+        };
+        self.comments
+            .owner(comment)
+            .is_some_and(|(_, placement)| placement == CommentPlacement::Trailing)
+    }
+
     /// Return whether this exact source boundary belongs to formatter-owned
     /// reflow. Protected boundaries always remain source-owned.
     fn may_reflow_boundary(&self, boundary: ReflowBoundary) -> bool {
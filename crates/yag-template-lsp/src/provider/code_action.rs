@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse, TextEdit, WorkspaceEdit,
+};
+
+use super::diagnostics::diag_for_analysis_diagnostic;
+use crate::session::Session;
+
+pub(crate) async fn code_action(
+    sess: &Session,
+    params: CodeActionParams,
+) -> anyhow::Result<Option<CodeActionResponse>> {
+    let doc = sess.document(&params.text_document.uri)?;
+
+    let requested_range = doc.mapper.text_range(params.range);
+    let actions = doc
+        .analysis
+        .diagnostics
+        .iter()
+        .filter(|diag| diag.range.intersect(requested_range).is_some())
+        .filter_map(|diag| {
+            let fix = diag.fix.as_ref()?;
+            let edits = fix
+                .edits
+                .iter()
+                .map(|edit| TextEdit::new(doc.mapper.range(edit.range), edit.new_text.clone()))
+                .collect();
+            Some(CodeActionOrCommand::CodeAction(CodeAction {
+                title: fix.title.clone(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diag_for_analysis_diagnostic(&doc, diag)]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(doc.uri.clone(), edits)])),
+                    ..Default::default()
+                }),
+                is_preferred: Some(true),
+                ..Default::default()
+            }))
+        })
+        .collect();
+    Ok(Some(actions))
+}
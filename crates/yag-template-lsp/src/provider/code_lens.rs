@@ -0,0 +1,53 @@
+use tower_lsp::lsp_types::{CodeLens, CodeLensParams, Command, Position, Range};
+use yag_template_analysis::size;
+use yag_template_syntax::ast;
+use yag_template_syntax::ast::ext::SyntaxNodeExt;
+
+use crate::session::Session;
+
+/// Show the remaining character budget at the top of the document, and the
+/// length of each associated template definition above it.
+pub(crate) async fn code_lens(sess: &Session, params: CodeLensParams) -> anyhow::Result<Option<Vec<CodeLens>>> {
+    let doc = sess.document(&params.text_document.uri)?;
+    let report = size::measure(&doc.syntax().to::<ast::Root>());
+
+    let limit = sess.lint_config().char_limit();
+    let remaining = report.remaining(limit);
+    let budget = if remaining >= 0 {
+        format!("{remaining} left")
+    } else {
+        format!("{} over", -remaining)
+    };
+    let summary = format!(
+        "{}/{limit} characters ({budget}): comments {}, whitespace {}",
+        report.total, report.comments, report.whitespace
+    );
+    let mut lenses = vec![display_lens(
+        Range::new(Position::new(0, 0), Position::new(0, 0)),
+        summary,
+    )];
+    for def in &report.definitions {
+        let name = def.name.as_deref().unwrap_or("<missing name>");
+        let title = format!(
+            "{} characters ({:.1}% of the limit) in {name:?}",
+            def.len,
+            def.len as f64 * 100.0 / limit as f64
+        );
+        let start = doc.mapper.range(def.range).start;
+        lenses.push(display_lens(Range::new(start, start), title));
+    }
+    Ok(Some(lenses))
+}
+
+/// A code lens that only displays `title` and does nothing when clicked.
+fn display_lens(range: Range, title: String) -> CodeLens {
+    CodeLens {
+        range,
+        command: Some(Command {
+            title,
+            command: String::new(),
+            arguments: None,
+        }),
+        data: None,
+    }
+}
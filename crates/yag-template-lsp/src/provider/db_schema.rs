@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use yag_template_analysis::db_keys::DbAccess;
+
+use crate::session::Session;
+use crate::session::workspace::IndexedKeyUse;
+
+/// The command returning the database schema of the workspace.
+pub(crate) const DATABASE_SCHEMA_COMMAND: &str = "yag-template-lsp.databaseSchema";
+
+#[derive(Default)]
+struct KeyEntry<'a> {
+    writers: Vec<&'a IndexedKeyUse>,
+    readers: Vec<&'a IndexedKeyUse>,
+    deleters: Vec<&'a IndexedKeyUse>,
+}
+
+/// A Markdown document listing the database keys used in the workspace,
+/// along with the templates writing, reading, and deleting them.
+pub(crate) fn database_schema(sess: &Session) -> String {
+    let mut keys: BTreeMap<String, KeyEntry> = BTreeMap::new();
+    let mut dynamic = Vec::new();
+    let index = sess.workspace.db_keys();
+    for indexed in &index.uses {
+        let Some(key) = &indexed.key_use.key else {
+            dynamic.push(indexed);
+            continue;
+        };
+        let entry = keys.entry(key.to_string()).or_default();
+        let access = indexed.key_use.access;
+        if access.writes() {
+            entry.writers.push(indexed);
+        }
+        if access.reads() {
+            entry.readers.push(indexed);
+        }
+        if access == DbAccess::Delete {
+            entry.deleters.push(indexed);
+        }
+    }
+
+    let mut out = String::from("# Database schema\n\n");
+    if keys.is_empty() && dynamic.is_empty() {
+        out.push_str("No database keys are used in the workspace.\n");
+        return out;
+    }
+    out.push_str("Parts of keys that are not known statically are shown as `*`.\n");
+    for (key, entry) in &keys {
+        let _ = write!(out, "\n## `{key}`\n\n");
+        write_uses(&mut out, sess, "Written by", &entry.writers);
+        write_uses(&mut out, sess, "Read by", &entry.readers);
+        write_uses(&mut out, sess, "Deleted by", &entry.deleters);
+    }
+    if !dynamic.is_empty() {
+        out.push_str("\n## Keys not known statically\n\n");
+        write_uses(&mut out, sess, "Used by", &dynamic);
+    }
+    out
+}
+
+fn write_uses(out: &mut String, sess: &Session, label: &str, uses: &[&IndexedKeyUse]) {
+    if uses.is_empty() {
+        let _ = writeln!(out, "- {label}: nothing");
+        return;
+    }
+    let _ = writeln!(out, "- {label}:");
+    for indexed in uses {
+        let path = sess
+            .workspace
+            .relative_path(&indexed.uri)
+            .unwrap_or_else(|| indexed.uri.to_string());
+        let line = indexed.range.start.line + 1;
+        let _ = writeln!(
+            out,
+            "  - [{path}:{line}]({}#L{line}) (`{}`)",
+            indexed.uri, indexed.key_use.func
+        );
+    }
+}
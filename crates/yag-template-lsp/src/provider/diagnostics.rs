@@ -1,51 +1,129 @@
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, Url};
-use yag_template_analysis::{AnalysisError, AnalysisWarning};
-use yag_template_syntax::SyntaxError;
+use tower_lsp::lsp_types::{self, DiagnosticSeverity, NumberOrString, Url};
+use yag_template_analysis::{Diagnostic, DiagnosticTag, Severity, custom_commands, suppress};
+use yag_template_syntax::ast::ext::SyntaxNodeExt;
+use yag_template_syntax::{SyntaxError, ast};
 
-use crate::session::{Document, Session};
+use crate::session::{Document, Session, manifest};
 
-pub(crate) async fn publish(sess: &Session, uri: &Url) -> anyhow::Result<()> {
-    let doc = sess.document(uri)?;
-
-    let syntax_error_diags = doc.parse.errors.iter().map(|err| diag_for_syntax_error(&doc, err));
-    let analysis_error_diags = doc.analysis.errors.iter().map(|err| diag_for_analysis_error(&doc, err));
-    let analysis_warning_diags = doc
+/// The diagnostics to publish for `doc`.
+fn diagnostics(sess: &Session, doc: &Document) -> Vec<lsp_types::Diagnostic> {
+    let syntax_error_diags = doc.parse.errors.iter().map(|err| diag_for_syntax_error(doc, err));
+    let analysis_diags = doc
         .analysis
-        .warnings
+        .diagnostics
         .iter()
-        .map(|warning| diag_for_analysis_warning(&doc, warning));
-    let all_diags = syntax_error_diags
-        .chain(analysis_error_diags)
-        .chain(analysis_warning_diags)
-        .collect();
+        .map(|diag| diag_for_analysis_diagnostic(doc, diag));
+    let workspace_diags = workspace_diagnostics(sess, doc);
+    let workspace_diags = workspace_diags
+        .iter()
+        .map(|diag| diag_for_analysis_diagnostic(doc, diag));
+    syntax_error_diags
+        .chain(analysis_diags)
+        .chain(workspace_diags)
+        .collect()
+}
 
-    let version = Default::default();
-    sess.client
-        .publish_diagnostics(uri.clone(), all_diags, Some(version))
-        .await;
+/// Publish diagnostics for all open documents, as is necessary when a change
+/// to one template affects the diagnostics of others.
+pub(crate) async fn publish_all(sess: &Session) -> anyhow::Result<()> {
+    for uri in sess.document_uris() {
+        reanalyze_if_triggers_changed(sess, &uri)?;
+        // The document may have been closed since the URIs were collected.
+        let Ok(all_diags) = sess.document(&uri).map(|doc| diagnostics(sess, &doc)) else {
+            continue;
+        };
+        let version = Default::default();
+        sess.client.publish_diagnostics(uri, all_diags, Some(version)).await;
+    }
     Ok(())
 }
 
-fn diag_for_syntax_error(doc: &Document, err: &SyntaxError) -> Diagnostic {
-    Diagnostic::new_simple(doc.mapper.range(err.range), err.message.clone())
+/// Reanalyze the document at `uri` if the kinds of trigger it runs for
+/// according to the manifest have changed since it was analyzed, as happens
+/// when the manifest changes or another template starts executing it.
+fn reanalyze_if_triggers_changed(sess: &Session, uri: &Url) -> anyhow::Result<()> {
+    let Ok(source) = sess
+        .document(uri)
+        .map(|doc| (doc.fallback_triggers != sess.workspace.trigger_kinds(uri)).then(|| doc.source.clone()))
+    else {
+        return Ok(());
+    };
+    if let Some(source) = source {
+        let doc = Document::new(sess, uri.clone(), &source)?;
+        sess.replace_document(uri, doc);
+    }
+    Ok(())
 }
 
-fn diag_for_analysis_error(doc: &Document, err: &AnalysisError) -> Diagnostic {
-    Diagnostic::new_simple(doc.mapper.range(err.range), err.message.clone())
+/// Diagnostics for `doc` that depend on the other templates in the workspace.
+fn workspace_diagnostics(sess: &Session, doc: &Document) -> Vec<Diagnostic> {
+    let config = sess.lint_config();
+    let mut diagnostics = sess.workspace.db_keys().keys.check(&doc.analysis.db_keys, &config);
+    if let Some(manifest) = sess.workspace.manifest() {
+        diagnostics.extend(custom_commands::check_calls(
+            &doc.analysis.custom_commands.calls,
+            &manifest,
+            &config,
+        ));
+        let command = doc
+            .uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| manifest::command_for_file(&manifest, &path));
+        if let Some(command) = command {
+            diagnostics.extend(custom_commands::check_exec_data(
+                command.id,
+                &doc.analysis.custom_commands.exec_data,
+                sess.workspace.cc_calls().iter(),
+                &config,
+            ));
+        }
+    }
+    let root = doc.syntax().to::<ast::Root>();
+    suppress::remove_suppressed(&root, diagnostics)
 }
 
-fn diag_for_analysis_warning(doc: &Document, warning: &AnalysisWarning) -> Diagnostic {
-    Diagnostic::new(
-        doc.mapper.range(warning.range),
-        Some(DiagnosticSeverity::WARNING),
-        None,
-        None,
-        warning.message.clone(),
+fn diag_for_syntax_error(doc: &Document, err: &SyntaxError) -> lsp_types::Diagnostic {
+    lsp_types::Diagnostic::new_simple(doc.mapper.range(err.range), err.message.clone())
+}
+
+pub(crate) fn diag_for_analysis_diagnostic(doc: &Document, diag: &Diagnostic) -> lsp_types::Diagnostic {
+    let tags: Vec<_> = diag
+        .tags
+        .iter()
+        .map(|tag| match tag {
+            DiagnosticTag::Deprecated => lsp_types::DiagnosticTag::DEPRECATED,
+            DiagnosticTag::Unnecessary => lsp_types::DiagnosticTag::UNNECESSARY,
+        })
+        .collect();
+    let related: Vec<_> = diag
+        .related
+        .iter()
+        .map(|info| lsp_types::DiagnosticRelatedInformation {
+            location: doc.location_for(info.range),
+            message: info.message.clone(),
+        })
+        .collect();
+    lsp_types::Diagnostic::new(
+        doc.mapper.range(diag.range),
+        Some(severity(diag.severity)),
+        Some(NumberOrString::String(diag.rule.into())),
         None,
-        warning.is_deprecation.then(|| vec![DiagnosticTag::DEPRECATED]),
+        diag.message.clone(),
+        (!related.is_empty()).then_some(related),
+        (!tags.is_empty()).then_some(tags),
     )
 }
 
+fn severity(severity: Severity) -> DiagnosticSeverity {
+    match severity {
+        Severity::Hint => DiagnosticSeverity::HINT,
+        Severity::Info => DiagnosticSeverity::INFORMATION,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Error => DiagnosticSeverity::ERROR,
+    }
+}
+
 pub(crate) async fn clear(sess: &Session, uri: &Url) {
     let version = Default::default();
     sess.client
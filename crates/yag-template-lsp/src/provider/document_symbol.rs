@@ -0,0 +1,40 @@
+use tower_lsp::lsp_types::{DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, SymbolKind};
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+use yag_template_syntax::trivia;
+
+use crate::session::{Document, Session};
+
+pub(crate) async fn document_symbol(
+    sess: &Session,
+    params: DocumentSymbolParams,
+) -> anyhow::Result<Option<DocumentSymbolResponse>> {
+    let doc = sess.document(&params.text_document.uri)?;
+    let symbols = doc
+        .syntax()
+        .descendants()
+        .filter_map(ast::Action::cast)
+        .filter_map(|action| template_symbol(&doc, action))
+        .collect();
+    Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+}
+
+fn template_symbol(doc: &Document, action: ast::Action) -> Option<DocumentSymbol> {
+    let name = match &action {
+        ast::Action::TemplateDefinition(define) => define.clause()?.template_name()?,
+        ast::Action::TemplateBlock(block) => block.clause()?.template_name()?,
+        _ => return None,
+    };
+    let docs = trivia::doc_text(&trivia::leading_comments(&action));
+
+    #[allow(deprecated)]
+    Some(DocumentSymbol {
+        name: name.get().into_owned(),
+        detail: docs.lines().next().map(str::to_owned),
+        kind: SymbolKind::FUNCTION,
+        tags: None,
+        deprecated: None,
+        range: doc.mapper.range(action.text_range()),
+        selection_range: doc.mapper.range(name.text_range()),
+        children: None,
+    })
+}
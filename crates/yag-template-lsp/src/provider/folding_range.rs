@@ -1,7 +1,8 @@
 use tower_lsp::lsp_types::{FoldingRange, FoldingRangeKind, FoldingRangeParams, Range};
+use yag_template_syntax::ast;
 use yag_template_syntax::ast::AstNode;
 use yag_template_syntax::ast::ext::SyntaxNodeExt;
-use yag_template_syntax::{SyntaxKind, SyntaxNode, ast};
+use yag_template_syntax::visit::{self, Visitor};
 
 use crate::session::{Document, Session};
 
@@ -10,31 +11,79 @@ pub(crate) async fn folding_range(
     params: FoldingRangeParams,
 ) -> anyhow::Result<Option<Vec<FoldingRange>>> {
     let doc = sess.document(&params.text_document.uri)?;
-    let folding_ranges = doc
-        .syntax()
-        .descendants()
-        .filter_map(|node| folding_range_for_node(&doc, node))
-        .collect();
-    Ok(Some(folding_ranges))
+    let mut collector = FoldingRangeCollector {
+        doc: &doc,
+        folding_ranges: Vec::new(),
+    };
+    visit::walk_root(&mut collector, &doc.syntax().to::<ast::Root>());
+    Ok(Some(collector.folding_ranges))
+}
+
+struct FoldingRangeCollector<'a> {
+    doc: &'a Document,
+    folding_ranges: Vec<FoldingRange>,
+}
+
+impl FoldingRangeCollector<'_> {
+    fn push(&mut self, folding_range: Option<FoldingRange>) {
+        self.folding_ranges.extend(folding_range);
+    }
+
+    fn fold_action<N: AstNode>(&mut self, action: &impl AstNode, collapsed_node: Option<N>) {
+        self.push(fold(self.doc.mapper.range(action.text_range()), collapsed_node));
+    }
+
+    fn fold_else_branches(&mut self, else_branches: impl Iterator<Item = ast::ElseBranch>) {
+        for else_branch in else_branches {
+            self.push(fold_else_branch(self.doc, else_branch));
+        }
+    }
 }
 
-fn folding_range_for_node(doc: &Document, node: SyntaxNode) -> Option<FoldingRange> {
-    use SyntaxKind::*;
-
-    let range = doc.mapper.range(node.text_range());
-    match node.kind() {
-        TemplateDefinition => fold(range, node.to::<ast::TemplateDefinition>().clause()),
-        TemplateBlock => fold(range, node.to::<ast::TemplateBlock>().clause()),
-        IfAction => fold(range, node.to::<ast::IfAction>().clause()),
-        ElseBranch => fold_else_branch(doc, node.to::<ast::ElseBranch>()),
-        WithAction => fold(range, node.to::<ast::WithAction>().clause()),
-        RangeLoop => fold(range, node.to::<ast::RangeLoop>().clause()),
-        WhileLoop => fold(range, node.to::<ast::WhileLoop>().clause()),
-        TryCatchAction => fold(range, node.to::<ast::TryCatchAction>().try_clause()),
-        CatchClause => fold_catch_clause(doc, node.to::<ast::CatchClause>()),
-        VarDecl | VarAssign => fold_var_decl_or_assign(doc, node),
-        CommentAction => fold_comment(doc, node),
-        _ => None,
+impl Visitor for FoldingRangeCollector<'_> {
+    fn enter_comment_action(&mut self, node: &ast::CommentAction) {
+        self.push(fold_comment(self.doc, node));
+    }
+
+    fn enter_template_definition(&mut self, node: &ast::TemplateDefinition) {
+        self.fold_action(node, node.clause());
+    }
+
+    fn enter_template_block(&mut self, node: &ast::TemplateBlock) {
+        self.fold_action(node, node.clause());
+    }
+
+    fn enter_if_action(&mut self, node: &ast::IfAction) {
+        self.fold_action(node, node.clause());
+        self.fold_else_branches(node.else_branches());
+    }
+
+    fn enter_with_action(&mut self, node: &ast::WithAction) {
+        self.fold_action(node, node.clause());
+        self.fold_else_branches(node.else_branches());
+    }
+
+    fn enter_range_loop(&mut self, node: &ast::RangeLoop) {
+        self.fold_action(node, node.clause());
+        self.fold_else_branches(node.else_branch().into_iter());
+    }
+
+    fn enter_while_loop(&mut self, node: &ast::WhileLoop) {
+        self.fold_action(node, node.clause());
+        self.fold_else_branches(node.else_branch().into_iter());
+    }
+
+    fn enter_try_catch_action(&mut self, node: &ast::TryCatchAction) {
+        self.fold_action(node, node.try_clause());
+        if let Some(catch_clause) = node.catch_clause() {
+            self.push(fold_catch_clause(self.doc, node, catch_clause));
+        }
+    }
+
+    fn enter_expr_action(&mut self, node: &ast::ExprAction) {
+        if matches!(node.expr(), Some(ast::Expr::VarDecl(_) | ast::Expr::VarAssign(_))) {
+            self.fold_action::<ast::ExprAction>(node, None);
+        }
     }
 }
 
@@ -61,8 +110,11 @@ fn fold_else_branch(doc: &Document, else_branch: ast::ElseBranch) -> Option<Fold
     fold(trimmed_range, else_branch.clause())
 }
 
-fn fold_catch_clause(doc: &Document, catch_clause: ast::CatchClause) -> Option<FoldingRange> {
-    let trycatch = catch_clause.syntax().parent()?.try_to::<ast::TryCatchAction>()?;
+fn fold_catch_clause(
+    doc: &Document,
+    trycatch: &ast::TryCatchAction,
+    catch_clause: ast::CatchClause,
+) -> Option<FoldingRange> {
     let range = Range {
         start: doc.mapper.position(catch_clause.text_range().start()),
         end: doc.mapper.position(trycatch.catch_body()?.trimmed_end_pos()),
@@ -70,12 +122,7 @@ fn fold_catch_clause(doc: &Document, catch_clause: ast::CatchClause) -> Option<F
     fold(range, Some(catch_clause))
 }
 
-fn fold_var_decl_or_assign(doc: &Document, decl_or_assign: SyntaxNode) -> Option<FoldingRange> {
-    let parent_action = decl_or_assign.parent()?.try_to::<ast::ExprAction>()?;
-    fold::<ast::ExprAction>(doc.mapper.range(parent_action.text_range()), None)
-}
-
-fn fold_comment(doc: &Document, comment: SyntaxNode) -> Option<FoldingRange> {
+fn fold_comment(doc: &Document, comment: &ast::CommentAction) -> Option<FoldingRange> {
     let r = doc.mapper.range(comment.text_range());
     if r.start.line == r.end.line {
         return None;
@@ -1,4 +1,4 @@
-use tower_lsp::lsp_types::{GotoDefinitionParams, GotoDefinitionResponse};
+use tower_lsp::lsp_types::{GotoDefinitionParams, GotoDefinitionResponse, Location, Range, Url};
 
 use crate::session::Session;
 
@@ -17,8 +17,25 @@ pub(crate) async fn goto_definition(
             .resolve_var(var)
             .and_then(|sym| sym.decl_range)
             .map(|range| GotoDefinitionResponse::Scalar(doc.location_for(range)))
+    } else if let Some(name) = query.template_name() {
+        doc.analysis
+            .templates
+            .resolve(&name.get())
+            .map(|def| GotoDefinitionResponse::Scalar(doc.location_for(def.name_range)))
+    } else if let Some(call) = doc.analysis.custom_commands.call_at(doc.mapper.offset(pos)) {
+        call.id
+            .and_then(|id| custom_command_location(sess, id))
+            .map(GotoDefinitionResponse::Scalar)
     } else {
         None
     };
     Ok(def_info)
 }
+
+/// The start of the template implementing the custom command with the given
+/// ID, according to the manifest.
+fn custom_command_location(sess: &Session, id: i64) -> Option<Location> {
+    let manifest = sess.workspace.manifest()?;
+    let uri = Url::from_file_path(&manifest.command(id)?.file).ok()?;
+    Some(Location::new(uri, Range::default()))
+}
@@ -1,7 +1,11 @@
-use tower_lsp::lsp_types::{Hover, HoverContents, HoverParams, MarkupContent, MarkupKind};
+use rowan::{TextRange, TextSize};
+use tower_lsp::lsp_types::{Hover, HoverContents, HoverParams, MarkupContent, MarkupKind, Url};
+use yag_template_analysis::custom_commands::CcCall;
+use yag_template_analysis::regex;
+use yag_template_analysis::scope::DotContext;
 use yag_template_envdefs::EnvDefs;
-use yag_template_syntax::ast;
-use yag_template_syntax::ast::AstToken;
+use yag_template_syntax::ast::{self, AstNode, AstToken};
+use yag_template_syntax::{SyntaxToken, trivia};
 
 use crate::session::{Document, Session};
 
@@ -16,6 +20,19 @@ pub(crate) async fn hover(sess: &Session, params: HoverParams) -> anyhow::Result
     } else if query.is_in_func_call() {
         let func_ident = query.ident().unwrap();
         hover_func(&sess.envdefs, &doc, func_ident)
+    } else if let Some(name) = query.template_name() {
+        hover_template(&doc, name)
+    } else if let Some(dot) = query.context_dot() {
+        hover_dot(&doc, dot)
+    } else if let Some(pattern) = query.map(|tok| {
+        let call = tok.parent_ancestors().find_map(ast::FuncCall::cast)?;
+        regex::regex_literal(&call).filter(|pattern| pattern.syntax() == tok)
+    }) {
+        hover_regex(&doc, pattern)
+    } else if let Some(call) = doc.analysis.custom_commands.call_at(doc.mapper.offset(pos)) {
+        hover_cc_id(sess, &doc, call)
+    } else if let Some(call) = query.map(|tok| tok.parent_ancestors().find_map(ast::ExprCall::cast)) {
+        hover_arg_access(&doc, call)
     } else {
         None
     };
@@ -60,3 +77,119 @@ fn hover_func(env: &EnvDefs, doc: &Document, func_ident: ast::Ident) -> Option<H
         range: Some(doc.mapper.range(func_ident.text_range())),
     })
 }
+
+fn hover_template(doc: &Document, name: ast::StringLiteral) -> Option<Hover> {
+    let mut hover_info = format!("```\n(template) {}\n```", name.syntax().text());
+    let docs = find_template_definition(doc, &name.get())
+        .map(|definition| trivia::doc_text(&trivia::leading_comments(&definition)))
+        .unwrap_or_default();
+    if !docs.is_empty() {
+        hover_info.push_str("\n\n");
+        hover_info.push_str(&docs);
+    }
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: hover_info,
+        }),
+        range: Some(doc.mapper.range(name.text_range())),
+    })
+}
+
+fn hover_dot(doc: &Document, dot: SyntaxToken) -> Option<Hover> {
+    let description = match doc.analysis.scope_info.dot_at(dot.text_range().start()) {
+        DotContext::Root => "The context data of the custom command.".to_owned(),
+        DotContext::TemplateData => "The data passed to the associated template.".to_owned(),
+        DotContext::WithValue(range) => format!("The value of `{}` in the enclosing `with`.", &doc.source[range]),
+        DotContext::RangeElement(range) => {
+            format!(
+                "The current element of `{}` in the enclosing `range`.",
+                &doc.source[range]
+            )
+        }
+        DotContext::CaughtError => "The error caught by the enclosing `catch`.".to_owned(),
+    };
+    // Only the `.` itself is described, even at the start of a field chain.
+    let range = TextRange::at(dot.text_range().start(), TextSize::from(1));
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!("```\n(context) .\n```\n{description}"),
+        }),
+        range: Some(doc.mapper.range(range)),
+    })
+}
+
+/// Explain the regular expression given by `pattern`, if it is valid.
+fn hover_regex(doc: &Document, pattern: ast::StringLiteral) -> Option<Hover> {
+    let regex = regex::parse(&pattern.get()).ok()?;
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!("```\n(regex) {}\n```\n{}", pattern.get(), regex.explain()),
+        }),
+        range: Some(doc.mapper.range(pattern.text_range())),
+    })
+}
+
+/// Describe the custom command executed by `call`, according to the manifest.
+fn hover_cc_id(sess: &Session, doc: &Document, call: &CcCall) -> Option<Hover> {
+    let manifest = sess.workspace.manifest()?;
+    let command = manifest.command(call.id?)?;
+    let path = Url::from_file_path(&command.file)
+        .ok()
+        .and_then(|uri| sess.workspace.relative_path(&uri))
+        .unwrap_or_else(|| command.file.display().to_string());
+    let mut hover_info = format!(
+        "```\n(custom command) #{} {path}\n```\nTrigger: {}",
+        command.id,
+        command.trigger_type.description()
+    );
+    if let Some(trigger) = &command.trigger {
+        hover_info.push_str(&format!(" `{trigger}`"));
+    }
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: hover_info,
+        }),
+        range: Some(doc.mapper.range(call.id_range)),
+    })
+}
+
+fn hover_arg_access(doc: &Document, call: ast::ExprCall) -> Option<Hover> {
+    let args = &doc.analysis.command_args;
+    let access = args.access_at(call.text_range())?;
+    let def = args.resolve(access)?;
+    let mut hover_info = format!("```\n(argument {}) {}", access.index, def.name);
+    if let Some(ty) = def.ty {
+        hover_info.push_str(": ");
+        hover_info.push_str(ty.name());
+    }
+    hover_info.push_str("\n```\n");
+    let num_required = args.schema(access.var).and_then(|schema| schema.num_required);
+    if num_required.is_some_and(|num_required| access.index < num_required) {
+        hover_info.push_str("Required argument.");
+    } else {
+        hover_info.push_str(&format!(
+            "Optional argument; check whether it was provided with `.IsSet {}`.",
+            access.index
+        ));
+    }
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: hover_info,
+        }),
+        range: Some(doc.mapper.range(call.text_range())),
+    })
+}
+
+/// Find the `define` or `block` action in effect for the template `name`.
+fn find_template_definition(doc: &Document, name: &str) -> Option<ast::Action> {
+    let def = doc.analysis.templates.resolve(name)?;
+    doc.syntax()
+        .descendants()
+        .filter_map(ast::Action::cast)
+        .find(|action| action.text_range() == def.range)
+}
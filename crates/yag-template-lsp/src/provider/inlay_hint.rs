@@ -1,4 +1,5 @@
 use tower_lsp::lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, InlayHintParams};
+use yag_template_analysis::command_args::{ArgAccess, ArgMethod};
 use yag_template_envdefs::{EnvDefs, Param};
 use yag_template_syntax::ast;
 use yag_template_syntax::ast::AstNode;
@@ -12,7 +13,7 @@ pub(crate) async fn inlay_hint(sess: &Session, params: InlayHintParams) -> anyho
     let doc = sess.document(&params.text_document.uri)?;
     let requested_range = doc.mapper.text_range(params.range);
 
-    let inlay_hints = doc
+    let mut inlay_hints: Vec<_> = doc
         .syntax()
         .descendants()
         .filter_map(ast::FuncCall::cast)
@@ -20,9 +21,36 @@ pub(crate) async fn inlay_hint(sess: &Session, params: InlayHintParams) -> anyho
         .filter_map(|call| inlay_hints_for_fn_call(&sess.envdefs, &doc, call))
         .flatten()
         .collect();
+    inlay_hints.extend(
+        doc.analysis
+            .command_args
+            .accesses()
+            .iter()
+            .filter(|access| access.method == ArgMethod::Get && requested_range.contains_range(access.range))
+            .filter_map(|access| inlay_hint_for_arg_access(&doc, access)),
+    );
     Ok(Some(inlay_hints))
 }
 
+/// A hint giving the name and type of the argument read by `$args.Get N`.
+fn inlay_hint_for_arg_access(doc: &Document, access: &ArgAccess) -> Option<InlayHint> {
+    let def = doc.analysis.command_args.resolve(access)?;
+    let label = match def.ty {
+        Some(ty) => format!("{}: {}", def.name, ty.name()),
+        None => def.name.to_string(),
+    };
+    Some(InlayHint {
+        position: doc.mapper.position(access.index_range.end()),
+        label: InlayHintLabel::String(label),
+        kind: Some(InlayHintKind::TYPE),
+        text_edits: None,
+        tooltip: None,
+        padding_left: Some(true),
+        padding_right: None,
+        data: None,
+    })
+}
+
 fn inlay_hints_for_fn_call<'e, 'd>(
     env: &'e EnvDefs,
     doc: &'d Document,
@@ -1,5 +1,9 @@
+pub(crate) mod code_action;
+pub(crate) mod code_lens;
 pub(crate) mod completion;
+pub(crate) mod db_schema;
 pub(crate) mod diagnostics;
+pub(crate) mod document_symbol;
 pub(crate) mod folding_range;
 pub(crate) mod formatting;
 pub(crate) mod goto_definition;
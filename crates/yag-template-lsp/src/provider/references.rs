@@ -1,4 +1,5 @@
 use tower_lsp::lsp_types::{Location, ReferenceContext, ReferenceParams};
+use yag_template_analysis::db_keys::DbKeyUse;
 use yag_template_syntax::ast::{self, AstNode};
 
 use crate::session::{Document, Session};
@@ -14,6 +15,8 @@ pub(crate) async fn references(sess: &Session, params: ReferenceParams) -> anyho
     } else if query.is_in_func_call() {
         let func_ident = query.ident().unwrap();
         find_func_references(&doc, func_ident.get())
+    } else if let Some(key_use) = doc.db_key_use_at(pos) {
+        find_db_key_references(sess, key_use)
     } else {
         None
     };
@@ -31,6 +34,27 @@ fn find_var_references(doc: &Document, var: ast::Var, context: &ReferenceContext
     Some(refs)
 }
 
+/// Find the uses of a database key throughout the workspace, including those
+/// of patterns that may match it.
+fn find_db_key_references(sess: &Session, key_use: &DbKeyUse) -> Option<Vec<Location>> {
+    let key = key_use.key.as_ref()?;
+    let refs: Vec<_> = sess
+        .workspace
+        .db_keys()
+        .uses
+        .iter()
+        .filter(|other| {
+            other
+                .key_use
+                .key
+                .as_ref()
+                .is_some_and(|other_key| key.may_overlap(other_key))
+        })
+        .map(|other| Location::new(other.uri.clone(), other.range))
+        .collect();
+    Some(refs)
+}
+
 fn find_func_references(doc: &Document, func_name: &str) -> Option<Vec<Location>> {
     let refs: Vec<_> = doc
         .syntax()
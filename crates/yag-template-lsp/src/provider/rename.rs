@@ -1,21 +1,32 @@
 use std::collections::HashMap;
 
-use tower_lsp::lsp_types::{RenameParams, TextEdit, WorkspaceEdit};
+use tower_lsp::lsp_types::{MessageType, RenameParams, TextEdit, WorkspaceEdit};
+use yag_template_analysis::db_keys::{DbAccess, DbKeyUse};
 use yag_template_syntax::ast;
 
 use crate::session::{Document, Session};
 
 pub(crate) async fn rename(sess: &Session, params: RenameParams) -> anyhow::Result<Option<WorkspaceEdit>> {
     let uri = params.text_document_position.text_document.uri;
-    let doc = sess.document(&uri)?;
-
-    let pos = params.text_document_position.position;
-    let query = doc.query_at(pos);
-    let edits = if let Some(var) = query.var() {
-        rename_var(&doc, var, params.new_name)
-    } else {
-        None
+    let mut warning = None;
+    let edits = {
+        let doc = sess.document(&uri)?;
+        let pos = params.text_document_position.position;
+        let query = doc.query_at(pos);
+        if let Some(var) = query.var() {
+            rename_var(&doc, var, params.new_name)
+        } else if let Some(key_use) = doc.db_key_use_at(pos) {
+            rename_db_key(sess, key_use, &params.new_name).map(|(edits, skipped)| {
+                warning = skipped;
+                edits
+            })
+        } else {
+            None
+        }
     };
+    if let Some(message) = warning {
+        sess.client.show_message(MessageType::WARNING, message).await;
+    }
     Ok(edits)
 }
 
@@ -38,3 +49,68 @@ fn rename_var(doc: &Document, var: ast::Var, mut new_name: String) -> Option<Wor
         ..Default::default()
     })
 }
+
+/// Rename a database key given by a string literal throughout the workspace.
+/// Keys built with `printf` or `joinStr`, and patterns, are left as is; along
+/// with the edits, return a message telling the user about those that may
+/// match the key, if any.
+fn rename_db_key(sess: &Session, key_use: &DbKeyUse, new_name: &str) -> Option<(WorkspaceEdit, Option<String>)> {
+    if !key_use.is_literal || key_use.access == DbAccess::ReadPattern {
+        return None;
+    }
+    let key = key_use.key.as_ref()?;
+    let old_name = key.literal()?;
+    let new_literal = quote(new_name);
+
+    let mut changes: HashMap<_, Vec<_>> = HashMap::new();
+    let mut skipped = Vec::new();
+    for other in &sess.workspace.db_keys().uses {
+        let Some(other_key) = &other.key_use.key else {
+            continue;
+        };
+        let is_same_key = other.key_use.is_literal
+            && other_key.literal() == Some(old_name)
+            && other.key_use.access != DbAccess::ReadPattern;
+        if is_same_key {
+            changes
+                .entry(other.uri.clone())
+                .or_default()
+                .push(TextEdit::new(other.range, new_literal.clone()));
+        } else if key.may_overlap(other_key) {
+            let path = sess
+                .workspace
+                .relative_path(&other.uri)
+                .unwrap_or_else(|| other.uri.to_string());
+            skipped.push(format!("{path}:{}", other.range.start.line + 1));
+        }
+    }
+    let warning = (!skipped.is_empty()).then(|| {
+        format!(
+            "Uses of keys that may match {old_name:?} were not renamed, since they are built with printf or joinStr \
+             or are patterns: {}",
+            skipped.join(", ")
+        )
+    });
+    let edits = WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    };
+    Some((edits, warning))
+}
+
+/// Quote `s` as an interpreted string literal.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
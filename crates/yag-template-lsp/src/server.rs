@@ -2,12 +2,14 @@ use std::sync::Arc;
 
 use tower_lsp::jsonrpc::{self, Result};
 use tower_lsp::lsp_types::{
-    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
-    DidOpenTextDocumentParams, DocumentFormattingParams, FoldingRange, FoldingRangeParams,
-    FoldingRangeProviderCapability, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverParams,
-    HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams, InlayHint, InlayHintParams,
-    Location, OneOf, ReferenceParams, RenameParams, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
-    TextDocumentSyncKind, TextEdit, WorkspaceEdit,
+    CodeActionParams, CodeActionProviderCapability, CodeActionResponse, CodeLens, CodeLensOptions, CodeLensParams,
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeConfigurationParams, DidChangeTextDocumentParams,
+    DidChangeWatchedFilesParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentFormattingParams,
+    DocumentSymbolParams, DocumentSymbolResponse, ExecuteCommandOptions, ExecuteCommandParams, FoldingRange,
+    FoldingRangeParams, FoldingRangeProviderCapability, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverParams, HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams, InlayHint,
+    InlayHintParams, LSPAny, Location, MessageType, OneOf, ReferenceParams, RenameParams, ServerCapabilities,
+    ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, WorkspaceEdit,
 };
 use tower_lsp::{Client, LanguageServer, async_trait};
 
@@ -46,20 +48,40 @@ fn server_capabilities() -> ServerCapabilities {
             trigger_characters: Some(completion_trigger_chars),
             ..Default::default()
         }),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        code_lens_provider: Some(CodeLensOptions {
+            resolve_provider: Some(false),
+        }),
         folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
         definition_provider: Some(OneOf::Left(true)),
         hover_provider: Some(HoverProviderCapability::Simple(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
         inlay_hint_provider: Some(OneOf::Left(true)),
         references_provider: Some(OneOf::Left(true)),
         rename_provider: Some(OneOf::Left(true)),
         document_formatting_provider: Some(OneOf::Left(true)),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: vec![provider::db_schema::DATABASE_SCHEMA_COMMAND.into()],
+            ..Default::default()
+        }),
         ..Default::default()
     }
 }
 
 #[async_trait]
 impl LanguageServer for YagTemplateLanguageServer {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        #[allow(deprecated)]
+        let root_uris = match params.workspace_folders {
+            Some(folders) => folders.into_iter().map(|folder| folder.uri).collect(),
+            None => params.root_uri.into_iter().collect::<Vec<_>>(),
+        };
+        let roots = root_uris.iter().filter_map(|uri| uri.to_file_path().ok()).collect();
+        self.session.workspace.set_roots(roots);
+        if let Some(options) = &params.initialization_options {
+            self.session.set_lint_config(session::settings::lint_config(options));
+        }
+
         Ok(InitializeResult {
             capabilities: server_capabilities(),
             server_info: Some(ServerInfo {
@@ -70,7 +92,21 @@ impl LanguageServer for YagTemplateLanguageServer {
     }
 
     async fn initialized(&self, _: InitializedParams) {
-        tracing::info!("server initialized")
+        tracing::info!("server initialized");
+        if let Err(error) = self.session.workspace.load_manifest() {
+            self.session
+                .client
+                .show_message(MessageType::ERROR, format!("Could not load project manifest: {error}"))
+                .await;
+        }
+        session::sync::watch_files(&self.session).await;
+        // Scanning reads every template in the workspace, so keep it off the
+        // threads serving requests.
+        let sess = Arc::clone(&self.session);
+        if let Err(error) = tokio::task::spawn_blocking(move || sess.workspace.scan()).await {
+            tracing::error!("could not scan workspace: {error}");
+        }
+        provider::diagnostics::publish_all(&self.session).await.unwrap();
     }
 
     async fn shutdown(&self) -> Result<()> {
@@ -85,14 +121,34 @@ impl LanguageServer for YagTemplateLanguageServer {
         session::sync::on_document_change(&self.session, params).await.unwrap();
     }
 
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        session::sync::on_configuration_change(&self.session, params)
+            .await
+            .unwrap();
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        session::sync::on_watched_files_change(&self.session, params)
+            .await
+            .unwrap();
+    }
+
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
-        session::sync::on_document_close(&self.session, params).await;
+        session::sync::on_document_close(&self.session, params).await.unwrap();
     }
 
     async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
         try_handle!(provider::formatting::format_document(&self.session, params))
     }
 
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        try_handle!(provider::code_action::code_action(&self.session, params))
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        try_handle!(provider::code_lens::code_lens(&self.session, params))
+    }
+
     async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
         try_handle!(provider::completion::complete(&self.session, params))
     }
@@ -101,6 +157,10 @@ impl LanguageServer for YagTemplateLanguageServer {
         try_handle!(provider::folding_range::folding_range(&self.session, params))
     }
 
+    async fn document_symbol(&self, params: DocumentSymbolParams) -> Result<Option<DocumentSymbolResponse>> {
+        try_handle!(provider::document_symbol::document_symbol(&self.session, params))
+    }
+
     async fn goto_definition(&self, params: GotoDefinitionParams) -> Result<Option<GotoDefinitionResponse>> {
         try_handle!(provider::goto_definition::goto_definition(&self.session, params))
     }
@@ -117,6 +177,15 @@ impl LanguageServer for YagTemplateLanguageServer {
         try_handle!(provider::references::references(&self.session, params))
     }
 
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<LSPAny>> {
+        match params.command.as_str() {
+            provider::db_schema::DATABASE_SCHEMA_COMMAND => Ok(Some(LSPAny::String(
+                provider::db_schema::database_schema(&self.session),
+            ))),
+            _ => Err(jsonrpc::Error::method_not_found()),
+        }
+    }
+
     async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
         try_handle!(provider::rename::rename(&self.session, params))
     }
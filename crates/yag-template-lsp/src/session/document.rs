@@ -3,6 +3,8 @@ use std::collections::BTreeMap;
 use rowan::{TextRange, TextSize};
 use tower_lsp::lsp_types::{Location, Position, Range, Url};
 use yag_template_analysis::Analysis;
+use yag_template_analysis::db_keys::DbKeyUse;
+use yag_template_envdefs::TriggerKind;
 use yag_template_syntax::ast::ext::SyntaxNodeExt;
 use yag_template_syntax::parser::Parse;
 use yag_template_syntax::query::Query;
@@ -16,18 +18,25 @@ pub(crate) struct Document {
     pub(crate) parse: Parse,
     pub(crate) mapper: Mapper,
     pub(crate) analysis: Analysis,
+    /// The kinds of trigger the template was analyzed for in the absence of a
+    /// `yag-trigger` directive.
+    pub(crate) fallback_triggers: Vec<TriggerKind>,
 }
 
 impl Document {
     pub(crate) fn new(sess: &Session, uri: Url, src: &str) -> anyhow::Result<Self> {
         let parse = parser::parse(src);
         let root = SyntaxNode::new_root(parse.root.clone()).to::<ast::Root>();
+        let fallback_triggers = sess.workspace.trigger_kinds(&uri);
+        let mut config = sess.lint_config().clone();
+        config.set_fallback_triggers(fallback_triggers.clone());
         let document = Self {
             uri,
             source: src.to_owned(),
             parse: parse.clone(),
             mapper: Mapper::new(src),
-            analysis: yag_template_analysis::analyze(&sess.envdefs, root),
+            analysis: yag_template_analysis::analyze_with(&sess.envdefs, root, &sess.lint_registry, &config),
+            fallback_triggers,
         };
         Ok(document)
     }
@@ -40,6 +49,15 @@ impl Document {
         Query::at(&self.syntax(), self.mapper.offset(pos))
     }
 
+    /// The database key given by the argument at `pos`, if any.
+    pub(crate) fn db_key_use_at(&self, pos: Position) -> Option<&DbKeyUse> {
+        let offset = self.mapper.offset(pos);
+        self.analysis
+            .db_keys
+            .iter()
+            .find(|key_use| key_use.range.contains_inclusive(offset))
+    }
+
     pub(crate) fn location_for(&self, range: TextRange) -> Location {
         Location::new(self.uri.clone(), self.mapper.range(range))
     }
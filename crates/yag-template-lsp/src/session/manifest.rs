@@ -0,0 +1,175 @@
+//! Reading the project manifest, a TOML file listing each custom command along
+//! with its ID, the template file implementing it relative to the manifest, and
+//! its trigger:
+//!
+//! ```toml
+//! [[commands]]
+//! id = 12
+//! file = "commands/give.yag"
+//! trigger_type = "command"
+//! trigger = "give"
+//! ```
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs};
+
+use serde::Deserialize;
+use yag_template_analysis::manifest::{CustomCommand, Manifest, TriggerType};
+
+/// An error encountered while reading or interpreting a manifest.
+#[derive(Debug)]
+pub(crate) struct ManifestError {
+    path: PathBuf,
+    message: String,
+}
+
+impl ManifestError {
+    fn new(path: impl Into<PathBuf>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+impl Error for ManifestError {}
+
+/// Read the manifest at `path`.
+pub(crate) fn load(path: &Path) -> Result<Manifest, ManifestError> {
+    let source = fs::read_to_string(path).map_err(|error| ManifestError::new(path, error.to_string()))?;
+    let commands = parse(path, &source)?
+        .into_iter()
+        .map(|command| CustomCommand {
+            file: canonicalize(&command.file),
+            ..command
+        })
+        .collect();
+    Ok(Manifest::new(path.to_owned(), commands))
+}
+
+/// The custom command implemented by the template at `path`, if any.
+pub(crate) fn command_for_file<'m>(manifest: &'m Manifest, path: &Path) -> Option<&'m CustomCommand> {
+    manifest.command_for_file(&canonicalize(path))
+}
+
+/// Resolve symbolic links and relative components in `path`, which is left as
+/// is if it does not exist.
+fn canonicalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+/// Parse `source` as the manifest at `path`, resolving template files relative
+/// to its directory.
+fn parse(path: &Path, source: &str) -> Result<Vec<CustomCommand>, ManifestError> {
+    let file = toml::from_str::<FileManifest>(source).map_err(|error| ManifestError::new(path, error.to_string()))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut commands: Vec<CustomCommand> = Vec::with_capacity(file.commands.len());
+    for command in file.commands {
+        if commands.iter().any(|other| other.id == command.id) {
+            return Err(ManifestError::new(
+                path,
+                format!("custom command ID {} is listed more than once", command.id),
+            ));
+        }
+        let Some(trigger_type) = TriggerType::from_name(&command.trigger_type) else {
+            let expected: Vec<_> = TriggerType::ALL.iter().map(|ty| ty.name()).collect();
+            return Err(ManifestError::new(
+                path,
+                format!(
+                    "unknown trigger type {:?} for custom command {}; expected one of {}",
+                    command.trigger_type,
+                    command.id,
+                    expected.join(", ")
+                ),
+            ));
+        };
+        commands.push(CustomCommand {
+            id: command.id,
+            file: dir.join(command.file),
+            trigger_type,
+            trigger: command.trigger,
+        });
+    }
+    Ok(commands)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileManifest {
+    #[serde(default)]
+    commands: Vec<FileCommand>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileCommand {
+    id: i64,
+    file: PathBuf,
+    trigger_type: String,
+    trigger: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use yag_template_analysis::manifest::{Manifest, TriggerType};
+
+    use super::parse;
+
+    #[test]
+    fn commands_are_resolved_relative_to_the_manifest() {
+        let source = r#"
+            [[commands]]
+            id = 12
+            file = "commands/give.yag"
+            trigger_type = "command"
+            trigger = "give"
+
+            [[commands]]
+            id = 13
+            file = "timer.yag"
+            trigger_type = "interval"
+        "#;
+        let path = Path::new("/project/yagcc.toml");
+        let manifest = Manifest::new(PathBuf::from(path), parse(path, source).unwrap());
+        let give = manifest.command(12).unwrap();
+        assert_eq!(give.file, Path::new("/project/commands/give.yag"));
+        assert_eq!(give.trigger_type, TriggerType::Command);
+        assert_eq!(give.trigger.as_deref(), Some("give"));
+        assert_eq!(
+            manifest.command_for_file(Path::new("/project/timer.yag")).unwrap().id,
+            13
+        );
+        assert!(manifest.command(14).is_none());
+    }
+
+    #[test]
+    fn invalid_manifests_are_rejected() {
+        let path = Path::new("/project/yagcc.toml");
+        let unknown = parse(
+            path,
+            "[[commands]]\nid = 1\nfile = \"a.yag\"\ntrigger_type = \"sometimes\"\n",
+        );
+        assert!(
+            unknown
+                .unwrap_err()
+                .to_string()
+                .contains("unknown trigger type \"sometimes\" for custom command 1")
+        );
+
+        let duplicate = "[[commands]]\nid = 1\nfile = \"a.yag\"\ntrigger_type = \"none\"\n\
+                         [[commands]]\nid = 1\nfile = \"b.yag\"\ntrigger_type = \"none\"\n";
+        assert_eq!(
+            parse(path, duplicate).unwrap_err().to_string(),
+            "/project/yagcc.toml: custom command ID 1 is listed more than once"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::hash::RandomState;
+use std::sync::{RwLock, RwLockReadGuard};
 
 use anyhow::Context;
 use dashmap::DashMap;
@@ -7,14 +8,22 @@ use tower_lsp::Client;
 use tower_lsp::lsp_types::Url;
 
 pub(crate) mod document;
+pub(crate) mod manifest;
+pub(crate) mod settings;
 pub(crate) mod sync;
+pub(crate) mod workspace;
 
 pub(crate) use document::Document;
+use workspace::WorkspaceIndex;
+use yag_template_analysis::lint::{LintConfig, LintRegistry};
 use yag_template_envdefs::{EnvDefs, bundled_envdefs};
 
 pub(crate) struct Session {
     pub(crate) client: Client,
     pub(crate) envdefs: EnvDefs,
+    pub(crate) workspace: WorkspaceIndex,
+    pub(crate) lint_registry: LintRegistry,
+    lint_config: RwLock<LintConfig>,
     documents: DashMap<Url, Document>,
 }
 
@@ -23,20 +32,45 @@ impl Session {
         Self {
             client,
             envdefs: bundled_envdefs::load().expect("bundled envdefs should be valid"),
+            workspace: WorkspaceIndex::default(),
+            lint_registry: LintRegistry::default(),
+            lint_config: RwLock::new(LintConfig::default()),
             documents: DashMap::new(),
         }
     }
 
+    /// The lint configuration given by the client settings.
+    pub(crate) fn lint_config(&self) -> RwLockReadGuard<'_, LintConfig> {
+        self.lint_config.read().unwrap()
+    }
+
+    pub(crate) fn set_lint_config(&self, config: LintConfig) {
+        *self.lint_config.write().unwrap() = config;
+    }
+
     pub(crate) fn document(&self, uri: &Url) -> anyhow::Result<Ref<'_, Url, Document, RandomState>> {
         self.documents
             .get(uri)
             .with_context(|| format!("could not find document {uri}"))
     }
 
+    /// The URIs of all open documents.
+    pub(crate) fn document_uris(&self) -> Vec<Url> {
+        self.documents.iter().map(|entry| entry.key().clone()).collect()
+    }
+
     pub(crate) fn upsert_document(&self, uri: &Url, document: Document) {
         self.documents.insert(uri.clone(), document);
     }
 
+    /// Replace the document at `uri` with a reanalyzed version, unless it has
+    /// been closed in the meantime.
+    pub(crate) fn replace_document(&self, uri: &Url, document: Document) {
+        if let Some(mut entry) = self.documents.get_mut(uri) {
+            *entry = document;
+        }
+    }
+
     pub(crate) fn remove_document(&self, uri: &Url) {
         self.documents.remove(uri);
     }
@@ -0,0 +1,48 @@
+//! Settings sent by the client, either as initialization options or through
+//! `workspace/didChangeConfiguration`.
+
+use tower_lsp::lsp_types::LSPAny;
+use yag_template_analysis::lint::LintConfig;
+
+/// The section of the client configuration holding the server's settings.
+const SECTION: &str = "yag-template-lsp";
+
+/// Build the lint configuration given by `settings`, using the default for any
+/// setting that is missing or invalid.
+pub(crate) fn lint_config(settings: &LSPAny) -> LintConfig {
+    // Settings sent through `workspace/didChangeConfiguration` are nested under
+    // the section name, but initialization options are not.
+    let settings = settings.get(SECTION).unwrap_or(settings);
+    let mut config = LintConfig::new();
+    if let Some(limit) = settings.get("charLimit").and_then(LSPAny::as_u64) {
+        config.set_char_limit(limit as usize);
+    }
+    if let Some(premium) = settings.get("premium").and_then(LSPAny::as_bool) {
+        config.set_premium(premium);
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::LSPAny;
+
+    use super::lint_config;
+
+    fn char_limit(settings: &str) -> usize {
+        lint_config(&settings.parse::<LSPAny>().unwrap()).char_limit()
+    }
+
+    #[test]
+    fn char_limit_is_read_from_initialization_options_and_configuration_changes() {
+        assert_eq!(char_limit(r#"{"charLimit": 5000}"#), 5000);
+        assert_eq!(char_limit(r#"{"yag-template-lsp": {"charLimit": 20000}}"#), 20000);
+        assert_eq!(char_limit(r#"{"charLimit": "lots"}"#), 10_000);
+    }
+
+    #[test]
+    fn premium_is_read_from_settings() {
+        assert!(lint_config(&r#"{"premium": true}"#.parse::<LSPAny>().unwrap()).premium());
+        assert!(!lint_config(&"{}".parse::<LSPAny>().unwrap()).premium());
+    }
+}
@@ -1,13 +1,20 @@
-use tower_lsp::lsp_types::{DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams};
+use tower_lsp::lsp_types::{
+    DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+    DidChangeWatchedFilesRegistrationOptions, DidCloseTextDocumentParams, DidOpenTextDocumentParams, FileSystemWatcher,
+    GlobPattern, MessageType, Registration,
+};
+use yag_template_analysis::manifest::MANIFEST_FILE_NAME;
 
 use crate::provider;
-use crate::session::{Document, Session};
+use crate::session::workspace::template_glob;
+use crate::session::{Document, Session, settings};
 
 pub(crate) async fn on_document_open(sess: &Session, params: DidOpenTextDocumentParams) -> anyhow::Result<()> {
     let uri = params.text_document.uri;
     let doc = Document::new(sess, uri.clone(), &params.text_document.text)?;
+    sess.workspace.index_document(&doc);
     sess.upsert_document(&uri, doc);
-    provider::diagnostics::publish(sess, &uri).await
+    provider::diagnostics::publish_all(sess).await
 }
 
 pub(crate) async fn on_document_change(sess: &Session, params: DidChangeTextDocumentParams) -> anyhow::Result<()> {
@@ -15,12 +22,71 @@ pub(crate) async fn on_document_change(sess: &Session, params: DidChangeTextDocu
 
     // We're using TextDocumentSyncKind::FULL, so no incremental changes (for now.)
     let doc = Document::new(sess, uri.clone(), &params.content_changes[0].text)?;
+    sess.workspace.index_document(&doc);
     sess.upsert_document(&uri, doc);
-    provider::diagnostics::publish(sess, &uri).await
+    provider::diagnostics::publish_all(sess).await
 }
 
-pub(crate) async fn on_document_close(sess: &Session, params: DidCloseTextDocumentParams) {
+pub(crate) async fn on_document_close(sess: &Session, params: DidCloseTextDocumentParams) -> anyhow::Result<()> {
     let uri = params.text_document.uri;
     sess.remove_document(&uri);
+    sess.workspace.reindex_closed(&uri);
     provider::diagnostics::clear(sess, &uri).await;
+    provider::diagnostics::publish_all(sess).await
+}
+
+/// Ask the client to report changes to the manifest and templates on disk.
+pub(crate) async fn watch_files(sess: &Session) {
+    let watchers = [format!("**/{MANIFEST_FILE_NAME}"), template_glob()]
+        .into_iter()
+        .map(|glob| FileSystemWatcher {
+            glob_pattern: GlobPattern::String(glob),
+            kind: None,
+        })
+        .collect();
+    let options = DidChangeWatchedFilesRegistrationOptions { watchers };
+    let registration = Registration {
+        id: "watch-files".into(),
+        method: "workspace/didChangeWatchedFiles".into(),
+        register_options: Some(serde_json::to_value(options).expect("registration options should serialize")),
+    };
+    if let Err(error) = sess.client.register_capability(vec![registration]).await {
+        tracing::warn!("could not watch files: {error}");
+    }
+}
+
+/// Reload the project manifest and reindex templates that are not open when
+/// they change on disk.
+pub(crate) async fn on_watched_files_change(sess: &Session, params: DidChangeWatchedFilesParams) -> anyhow::Result<()> {
+    let mut manifest_changed = false;
+    for change in &params.changes {
+        if change.uri.path_segments().and_then(|mut segments| segments.next_back()) == Some(MANIFEST_FILE_NAME) {
+            manifest_changed = true;
+        } else if sess.document(&change.uri).is_err() {
+            // Open documents are indexed with their current contents instead.
+            sess.workspace.reindex_changed(&change.uri);
+        }
+    }
+    if manifest_changed && let Err(error) = sess.workspace.load_manifest() {
+        sess.client
+            .show_message(MessageType::ERROR, format!("Could not load project manifest: {error}"))
+            .await;
+    }
+    provider::diagnostics::publish_all(sess).await
+}
+
+/// Apply new client settings, reanalyzing all open documents accordingly.
+pub(crate) async fn on_configuration_change(
+    sess: &Session,
+    params: DidChangeConfigurationParams,
+) -> anyhow::Result<()> {
+    sess.set_lint_config(settings::lint_config(&params.settings));
+    for uri in sess.document_uris() {
+        let Ok(source) = sess.document(&uri).map(|doc| doc.source.clone()) else {
+            continue;
+        };
+        let doc = Document::new(sess, uri.clone(), &source)?;
+        sess.replace_document(&uri, doc);
+    }
+    provider::diagnostics::publish_all(sess).await
 }
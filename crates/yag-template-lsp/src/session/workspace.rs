@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::{fs, io};
+
+use dashmap::DashMap;
+use tower_lsp::lsp_types::{Range, Url};
+use yag_template_analysis::custom_commands::{self, CcCall};
+use yag_template_analysis::db_keys::{self, DbKeyUse, WorkspaceKeys};
+use yag_template_analysis::manifest::{MANIFEST_FILE_NAME, Manifest};
+use yag_template_analysis::scope;
+use yag_template_envdefs::TriggerKind;
+use yag_template_syntax::ast::ext::SyntaxNodeExt;
+use yag_template_syntax::{SyntaxNode, ast, parser};
+
+use super::Document;
+use super::document::Mapper;
+use super::manifest::{self, ManifestError};
+
+/// The file extensions of templates.
+const TEMPLATE_EXTENSIONS: &[&str] = &[".yag", ".yagcc", ".gotmpl", ".go.tmpl"];
+
+/// A glob pattern matching the templates in the workspace.
+pub(crate) fn template_glob() -> String {
+    format!("**/*{{{}}}", TEMPLATE_EXTENSIONS.join(","))
+}
+
+fn is_template(name: &str) -> bool {
+    TEMPLATE_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+/// Directories that are never scanned for templates.
+const IGNORED_DIRS: &[&str] = &["node_modules", "target"];
+
+/// A database key used by a template in the workspace.
+#[derive(Debug, Clone)]
+pub(crate) struct IndexedKeyUse {
+    pub(crate) uri: Url,
+    pub(crate) key_use: DbKeyUse,
+    pub(crate) range: Range,
+}
+
+/// The database keys used in the workspace.
+pub(crate) struct KeyIndex {
+    pub(crate) uses: Vec<IndexedKeyUse>,
+    pub(crate) keys: WorkspaceKeys,
+}
+
+/// An index of the templates in the workspace, including those that are not
+/// open, for features spanning several templates.
+#[derive(Default)]
+pub(crate) struct WorkspaceIndex {
+    roots: RwLock<Vec<PathBuf>>,
+    manifest: RwLock<Option<Arc<Manifest>>>,
+    db_keys: DashMap<Url, Vec<IndexedKeyUse>>,
+    /// Incremented whenever `db_keys` changes.
+    db_keys_generation: AtomicU64,
+    /// The key index built from `db_keys`, along with the generation it was
+    /// built at.
+    key_index: RwLock<Option<(u64, Arc<KeyIndex>)>>,
+    cc_calls: DashMap<Url, Vec<CcCall>>,
+}
+
+impl WorkspaceIndex {
+    pub(crate) fn set_roots(&self, roots: Vec<PathBuf>) {
+        *self.roots.write().unwrap() = roots;
+    }
+
+    /// The path of `uri` relative to the workspace root containing it, if any.
+    pub(crate) fn relative_path(&self, uri: &Url) -> Option<String> {
+        let path = uri.to_file_path().ok()?;
+        let roots = self.roots.read().unwrap();
+        let relative = roots.iter().find_map(|root| path.strip_prefix(root).ok())?;
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    /// Load the manifest from the first workspace root containing one.
+    pub(crate) fn load_manifest(&self) -> Result<(), ManifestError> {
+        let roots = self.roots.read().unwrap().clone();
+        let manifest = roots
+            .iter()
+            .map(|root| root.join(MANIFEST_FILE_NAME))
+            .find(|path| path.is_file())
+            .map(|path| manifest::load(&path).map(Arc::new))
+            .transpose();
+        // Forget the previous manifest even if the new one is invalid, lest
+        // diagnostics refer to a manifest that no longer exists.
+        *self.manifest.write().unwrap() = manifest.as_ref().ok().cloned().flatten();
+        manifest.map(|_| ())
+    }
+
+    pub(crate) fn manifest(&self) -> Option<Arc<Manifest>> {
+        self.manifest.read().unwrap().clone()
+    }
+
+    /// Index all templates under the workspace roots.
+    pub(crate) fn scan(&self) {
+        let roots = self.roots.read().unwrap().clone();
+        for root in roots {
+            if let Err(err) = self.scan_dir(&root) {
+                tracing::warn!("could not scan {}: {err}", root.display());
+            }
+        }
+    }
+
+    fn scan_dir(&self, dir: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type()?.is_dir() {
+                if !name.starts_with('.') && !IGNORED_DIRS.contains(&name.as_str()) {
+                    self.scan_dir(&path)?;
+                }
+            } else if is_template(&name) {
+                // Open documents are already indexed with their current contents.
+                if Url::from_file_path(&path).is_ok_and(|uri| !self.db_keys.contains_key(&uri)) {
+                    self.index_file(&path);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Index the template at `path` as it is on disk.
+    fn index_file(&self, path: &Path) {
+        let Ok(uri) = Url::from_file_path(path) else {
+            return;
+        };
+        match fs::read_to_string(path) {
+            Ok(src) => self.index_source(uri, &src),
+            Err(err) => tracing::warn!("could not read {}: {err}", path.display()),
+        }
+    }
+
+    fn index_source(&self, uri: Url, src: &str) {
+        let parse = parser::parse(src);
+        let root = SyntaxNode::new_root(parse.root).to::<ast::Root>();
+        let scope_info = scope::analyze(root.clone());
+        let usage = custom_commands::collect(&root, &scope_info);
+        self.cc_calls.insert(uri.clone(), usage.calls);
+
+        let mapper = Mapper::new(src);
+        let uses = db_keys::collect(&root)
+            .into_iter()
+            .map(|key_use| IndexedKeyUse {
+                uri: uri.clone(),
+                range: mapper.range(key_use.range),
+                key_use,
+            })
+            .collect();
+        self.db_keys.insert(uri, uses);
+        self.db_keys_generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// Index the current contents of an open document.
+    pub(crate) fn index_document(&self, doc: &Document) {
+        self.cc_calls
+            .insert(doc.uri.clone(), doc.analysis.custom_commands.calls.clone());
+        let uses = doc
+            .analysis
+            .db_keys
+            .iter()
+            .map(|key_use| IndexedKeyUse {
+                uri: doc.uri.clone(),
+                key_use: key_use.clone(),
+                range: doc.mapper.range(key_use.range),
+            })
+            .collect();
+        self.db_keys.insert(doc.uri.clone(), uses);
+        self.db_keys_generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// Restore the index of a document that has been closed to its contents
+    /// on disk, discarding any unsaved changes, or drop it if it lies outside
+    /// the workspace.
+    pub(crate) fn reindex_closed(&self, uri: &Url) {
+        self.db_keys.remove(uri);
+        self.db_keys_generation.fetch_add(1, Ordering::Release);
+        self.cc_calls.remove(uri);
+        if self.relative_path(uri).is_some()
+            && let Ok(path) = uri.to_file_path()
+            && path.is_file()
+        {
+            self.index_file(&path);
+        }
+    }
+
+    /// Update the index of a template that is not open after it changes on
+    /// disk.
+    pub(crate) fn reindex_changed(&self, uri: &Url) {
+        if uri
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .is_some_and(is_template)
+        {
+            self.reindex_closed(uri);
+        }
+    }
+
+    /// All database keys used in the workspace. The index is rebuilt only
+    /// after the templates change, so it is cheap to call repeatedly.
+    pub(crate) fn db_keys(&self) -> Arc<KeyIndex> {
+        let generation = self.db_keys_generation.load(Ordering::Acquire);
+        if let Some((built_at, index)) = &*self.key_index.read().unwrap()
+            && *built_at == generation
+        {
+            return Arc::clone(index);
+        }
+        let uses: Vec<_> = self.db_keys.iter().flat_map(|entry| entry.value().clone()).collect();
+        let keys = WorkspaceKeys::new(uses.iter().map(|indexed| &indexed.key_use));
+        let index = Arc::new(KeyIndex { uses, keys });
+        // A change made while building leaves the index stale, but it is then
+        // rebuilt on the next call since the generation no longer matches.
+        *self.key_index.write().unwrap() = Some((generation, Arc::clone(&index)));
+        index
+    }
+
+    /// The kinds of trigger the template at `uri` runs for according to the
+    /// manifest, including exec triggers if other templates execute it with
+    /// data.
+    pub(crate) fn trigger_kinds(&self, uri: &Url) -> Vec<TriggerKind> {
+        let manifest = self.manifest.read().unwrap();
+        let Some(command) = manifest
+            .as_ref()
+            .zip(uri.to_file_path().ok())
+            .and_then(|(manifest, path)| manifest::command_for_file(manifest, &path))
+        else {
+            return Vec::new();
+        };
+        // Executions with dynamic IDs may also execute this command.
+        let is_executed = self.cc_calls.iter().any(|entry| {
+            entry
+                .value()
+                .iter()
+                .any(|call| call.id.is_none_or(|id| id == command.id) && call.data.is_some())
+        });
+        command
+            .trigger_type
+            .kind()
+            .into_iter()
+            .chain(is_executed.then_some(TriggerKind::Exec))
+            .collect()
+    }
+
+    /// All executions of custom commands in the workspace.
+    pub(crate) fn cc_calls(&self) -> Vec<CcCall> {
+        self.cc_calls.iter().flat_map(|entry| entry.value().clone()).collect()
+    }
+}
@@ -74,6 +74,12 @@ define_ast_node! {
 }
 define_delim_accessors!(CommentAction);
 
+impl CommentAction {
+    pub fn comment(&self) -> Option<tokens::Comment> {
+        self.syntax.first_matching_token()
+    }
+}
+
 define_ast_node! {
     pub struct ActionList;
 }
@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 
+use rowan::{TextRange, TextSize};
 use unscanny::Scanner;
 
 use super::macros::define_ast_token;
@@ -83,6 +84,19 @@ impl RightDelim {
     }
 }
 
+define_ast_token! {
+    pub struct Comment;
+}
+
+impl Comment {
+    /// The text of the comment, excluding the `/*` and `*/` markers.
+    pub fn text(&self) -> &str {
+        let text = self.syntax.text();
+        let without_leading = text.strip_prefix("/*").unwrap_or(text);
+        without_leading.strip_suffix("*/").unwrap_or(without_leading)
+    }
+}
+
 define_ast_token! {
     pub struct Ident;
 }
@@ -193,6 +207,33 @@ impl AstToken for StringLiteral {
     }
 }
 
+impl StringLiteral {
+    pub fn get(&self) -> Cow<'_, str> {
+        match self {
+            Self::Interpreted(v) => v.get(),
+            Self::Raw(v) => Cow::Borrowed(v.get()),
+        }
+    }
+
+    /// Map a range within the value returned by [StringLiteral::get] to the
+    /// corresponding range in the source, accounting for escape sequences.
+    pub fn source_range(&self, value_range: TextRange) -> TextRange {
+        let text = self.syntax().text();
+        let content_start = self.syntax().text_range().start() + TextSize::of('"');
+        let (start, end) = match self {
+            Self::Interpreted(_) => {
+                let content = strip_quotes(text, '"');
+                (
+                    go_syntax::source_offset(content, value_range.start()),
+                    go_syntax::source_offset(content, value_range.end()),
+                )
+            }
+            Self::Raw(_) => (value_range.start(), value_range.end()),
+        };
+        TextRange::new(content_start + start, content_start + end)
+    }
+}
+
 define_ast_token! {
     pub struct InterpretedString;
 }
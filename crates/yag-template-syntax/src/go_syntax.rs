@@ -167,6 +167,30 @@ pub(crate) fn interpret_string_content(src: &str) -> Cow<'_, str> {
     Cow::Owned(out)
 }
 
+/// Map an offset into the string returned by [interpret_string_content] back to
+/// the corresponding offset in `src`. An offset falling within a character
+/// produced by an escape sequence maps to the start of the sequence.
+pub(crate) fn source_offset(src: &str, interpreted_offset: TextSize) -> TextSize {
+    let mut src_pos = TextSize::new(0);
+    let mut interpreted_pos = TextSize::new(0);
+    for (range, unescaped) in iter_escape_sequences(src, EscapeContext::StringLiteral) {
+        let plain_len = range.start() - src_pos;
+        if interpreted_offset <= interpreted_pos + plain_len {
+            return src_pos + (interpreted_offset - interpreted_pos);
+        }
+        interpreted_pos += plain_len;
+        src_pos = range.start();
+
+        let unescaped_len = TextSize::of(unescaped.unwrap_or('\u{FFFD}'));
+        if interpreted_offset < interpreted_pos + unescaped_len {
+            return src_pos;
+        }
+        interpreted_pos += unescaped_len;
+        src_pos = range.end();
+    }
+    src_pos + (interpreted_offset - interpreted_pos)
+}
+
 /// Make a best effort to parse the input as a Go integer literal.
 pub(crate) fn parse_int(input: &str) -> Result<i64, ()> {
     let (base, stripped) = prepare_for_parse(input);
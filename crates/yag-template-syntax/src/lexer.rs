@@ -29,6 +29,15 @@ impl<'s> Lexer<'s> {
         }
     }
 
+    /// Create a lexer that starts inside an action, as if the input were
+    /// preceded by `{{`. Useful for lexing standalone expressions.
+    pub fn new_in_action(input: &'s str) -> Lexer<'s> {
+        Lexer {
+            mode: LexMode::Action,
+            ..Lexer::new(input)
+        }
+    }
+
     pub fn input(&self) -> &'s str {
         self.s.string()
     }
@@ -6,6 +6,8 @@ pub mod lexer;
 pub mod parser;
 pub mod query;
 mod rowan_boundary;
+pub mod trivia;
+pub mod visit;
 
 pub use crate::error::SyntaxError;
 pub use crate::kind::SyntaxKind;
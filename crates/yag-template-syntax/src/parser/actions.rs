@@ -2,7 +2,7 @@ use rowan::TextRange;
 
 use super::expr::{arg, expr_pipeline};
 use super::token_set::{ACTION_DELIMS, LEFT_DELIMS, RIGHT_DELIMS, STRING_LITERALS, TokenSet};
-use super::{Parser, TokenPattern};
+use super::{MAX_NESTING_DEPTH, Parser, TokenPattern};
 use crate::SyntaxKind;
 
 impl Parser<'_> {
@@ -11,20 +11,70 @@ impl Parser<'_> {
     }
 }
 
-fn action_list(p: &mut Parser) {
-    const ACTION_LIST_TERMINATORS: TokenSet = TokenSet::new()
-        .add(SyntaxKind::Else)
-        .add(SyntaxKind::Catch)
-        .add(SyntaxKind::End);
+const ACTION_LIST_TERMINATORS: TokenSet = TokenSet::new()
+    .add(SyntaxKind::Else)
+    .add(SyntaxKind::Catch)
+    .add(SyntaxKind::End);
 
+const COMPOUND_ACTION_KEYWORDS: TokenSet = TokenSet::new()
+    .add(SyntaxKind::If)
+    .add(SyntaxKind::With)
+    .add(SyntaxKind::Range)
+    .add(SyntaxKind::While)
+    .add(SyntaxKind::Try)
+    .add(SyntaxKind::Define)
+    .add(SyntaxKind::Block);
+
+pub(crate) fn action_list(p: &mut Parser) {
     let action_list = p.start(SyntaxKind::ActionList);
-    // until EOF, `{{else`, `{{catch`, or `{{end`
-    while !p.at_eof() && !p.at_left_delim_and(ACTION_LIST_TERMINATORS) {
-        text_or_action(p);
+    if p.descend() {
+        // until EOF, `{{else`, `{{catch`, or `{{end`
+        while !p.at_eof() && !p.at_left_delim_and(ACTION_LIST_TERMINATORS) {
+            text_or_action(p);
+        }
+        p.ascend();
+    } else {
+        too_deeply_nested_action_list(p);
     }
     action_list.complete(p);
 }
 
+/// Skip the contents of an action list nested more deeply than
+/// [MAX_NESTING_DEPTH] without recursing into it. Compound actions within are
+/// matched with their `{{end}}` clauses so that the enclosing action still
+/// finds its own `{{else}}`, `{{catch}}`, or `{{end}}`.
+fn too_deeply_nested_action_list(p: &mut Parser) {
+    if p.at_eof() || p.at_left_delim_and(ACTION_LIST_TERMINATORS) {
+        // Nothing to skip.
+        return;
+    }
+
+    let start = p.cur_start();
+    let error = p.start(SyntaxKind::Error);
+    let mut open_actions = 0usize;
+    while !p.at_eof() {
+        if p.at(LEFT_DELIMS) {
+            let keyword = p.peek_ignore_space();
+            if COMPOUND_ACTION_KEYWORDS.contains(keyword) {
+                open_actions += 1;
+            } else if ACTION_LIST_TERMINATORS.contains(keyword) {
+                if open_actions == 0 {
+                    break;
+                }
+                if keyword == SyntaxKind::End {
+                    open_actions -= 1;
+                }
+            }
+        }
+        p.eat();
+    }
+    error.complete(p);
+    p.error(
+        format!("actions are nested too deeply (maximum depth is {MAX_NESTING_DEPTH})"),
+        TextRange::new(start, p.cur_start()),
+    );
+}
+
 pub(crate) fn text_or_action(p: &mut Parser) {
     if p.eat_if(SyntaxKind::Text) {
         return;
@@ -351,7 +401,8 @@ fn catch_clause(p: &mut Parser) {
     catch_clause.complete(p);
 }
 
-// FIXME: Need to reject template definitions not at top level:
+// NOTE: Template definitions not at the top level are accepted here and
+// reported during analysis instead.
 fn template_definition(p: &mut Parser) {
     let template_definition = p.start(SyntaxKind::TemplateDefinition);
     define_clause(p);
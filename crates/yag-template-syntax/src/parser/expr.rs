@@ -1,7 +1,7 @@
-use rowan::Checkpoint;
+use rowan::{Checkpoint, TextRange};
 
-use super::Parser;
 use super::token_set::{ACTION_DELIMS, LEFT_DELIMS, TokenSet};
+use super::{MAX_NESTING_DEPTH, Parser};
 use crate::SyntaxKind;
 
 /// Parse a pipeline of expressions. (If there is only one expression,
@@ -30,6 +30,10 @@ fn pipeline_stage(p: &mut Parser) {
 pub(crate) fn expr(p: &mut Parser, context: &str) {
     const EXPR_RECOVERY_SET: TokenSet = ACTION_DELIMS.add(SyntaxKind::RightParen);
 
+    if !p.descend() {
+        return too_deeply_nested(p);
+    }
+
     let c = p.checkpoint();
     let saw_dot = p.at(SyntaxKind::Dot);
     match p.cur() {
@@ -53,12 +57,17 @@ pub(crate) fn expr(p: &mut Parser, context: &str) {
     }
     trailing_field_chain(p, c);
     trailing_call_args(p, c);
+    p.ascend();
 }
 
 /// Parse an argument to a call.
 pub(crate) fn arg(p: &mut Parser) {
     const ARG_RECOVERY_SET: TokenSet = ACTION_DELIMS.add(SyntaxKind::RightParen);
 
+    if !p.descend() {
+        return too_deeply_nested(p);
+    }
+
     let saw_dot = p.at(SyntaxKind::Dot);
     let c = p.checkpoint();
     match p.cur() {
@@ -90,6 +99,30 @@ pub(crate) fn arg(p: &mut Parser) {
         p.error_here("expected field name after `.`");
     }
     trailing_field_chain(p, c);
+    p.ascend();
+}
+
+/// Skip an expression nested more deeply than [MAX_NESTING_DEPTH] without
+/// recursing into it: eat everything up to the closing parenthesis of the
+/// enclosing expression or the end of the action, whichever comes first.
+fn too_deeply_nested(p: &mut Parser) {
+    let start = p.cur_start();
+    let error = p.start(SyntaxKind::Error);
+    let mut open_parens = 0usize;
+    while !p.at_eof() && !p.at(ACTION_DELIMS) {
+        match p.cur() {
+            SyntaxKind::LeftParen => open_parens += 1,
+            SyntaxKind::RightParen if open_parens == 0 => break,
+            SyntaxKind::RightParen => open_parens -= 1,
+            _ => {}
+        }
+        p.eat();
+    }
+    error.complete(p);
+    p.error(
+        format!("expression is nested too deeply (maximum depth is {MAX_NESTING_DEPTH})"),
+        TextRange::new(start, p.cur_start()),
+    );
 }
 
 fn trailing_field_chain(p: &mut Parser, c: Checkpoint) {
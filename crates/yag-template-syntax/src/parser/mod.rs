@@ -2,14 +2,15 @@ mod actions;
 mod expr;
 mod token_set;
 
-use actions::text_or_action;
+use actions::{action_list, text_or_action};
 use drop_bomb::DropBomb;
 use rowan::{Checkpoint, GreenNode, GreenNodeBuilder, TextRange, TextSize};
 
-use crate::SyntaxKind;
+use crate::ast::ext::SyntaxNodeExt;
 use crate::error::SyntaxError;
 use crate::lexer::Lexer;
 use crate::parser::token_set::TokenSet;
+use crate::{SyntaxKind, SyntaxNode, ast};
 
 pub fn parse(input: &str) -> Parse {
     let mut p = Parser::new(input);
@@ -27,6 +28,85 @@ pub struct Parse {
     pub errors: Vec<SyntaxError>,
 }
 
+/// Parse a single expression, such as `add 1 (len $x)`, as if it appeared
+/// inside an action. Pipelines are not accepted; see [parse_pipeline].
+pub fn parse_expr(input: &str) -> FragmentParse<Option<ast::Expr>> {
+    parse_expr_fragment(input, expr::expr)
+}
+
+/// Parse a pipeline of expressions, such as `$x | printf "%d"`, as if it
+/// appeared inside an action.
+pub fn parse_pipeline(input: &str) -> FragmentParse<Option<ast::Expr>> {
+    parse_expr_fragment(input, expr::expr_pipeline)
+}
+
+fn parse_expr_fragment(input: &str, parser: fn(&mut Parser, &str)) -> FragmentParse<Option<ast::Expr>> {
+    let mut p = Parser::new_in_action(input);
+    let root = p.start(SyntaxKind::Root);
+    p.eat_trivia();
+    p.eat_whitespace();
+    parser(&mut p, "in input");
+    p.eat_whitespace();
+    if !p.at_eof() {
+        let msg = format!("unexpected {} after expression", p.cur());
+        p.wrap_err(
+            |p| {
+                while !p.at_eof() {
+                    p.eat();
+                }
+            },
+            msg,
+        );
+    }
+    root.complete(&mut p);
+
+    let parse = p.finish();
+    FragmentParse {
+        tree: SyntaxNode::new_root(parse.root).first_matching_child(),
+        errors: parse.errors,
+    }
+}
+
+/// Parse a list of actions and text outside of any compound action. Unlike
+/// [parse], the result is the action list itself rather than the root of a
+/// template; unmatched `{{else}}`, `{{catch}}`, and `{{end}}` clauses are
+/// reported as errors.
+pub fn parse_action_list(input: &str) -> FragmentParse<ast::ActionList> {
+    let mut p = Parser::new(input);
+    let root = p.start(SyntaxKind::Root);
+    action_list(&mut p);
+    while !p.at_eof() {
+        text_or_action(&mut p);
+    }
+    root.complete(&mut p);
+
+    let parse = p.finish();
+    FragmentParse {
+        tree: SyntaxNode::new_root(parse.root)
+            .first_matching_child()
+            .expect("action list should always be present"),
+        errors: parse.errors,
+    }
+}
+
+/// The result of parsing a fragment of a template with [parse_expr],
+/// [parse_pipeline], or [parse_action_list]. The fragment is wrapped in a
+/// [SyntaxKind::Root] node, which also holds any input that could not be
+/// parsed as part of the fragment.
+#[derive(Debug, Clone)]
+pub struct FragmentParse<T> {
+    pub tree: T,
+    pub errors: Vec<SyntaxError>,
+}
+
+/// The maximum depth to which action lists and expressions may be nested.
+///
+/// The parser is recursive descent, as are several passes over the resulting
+/// tree, so arbitrarily deep input (say, thousands of nested parentheses) would
+/// otherwise overflow the stack. Anything nested more deeply is skipped and
+/// reported as a syntax error instead of being parsed.
+pub const MAX_NESTING_DEPTH: usize = 128;
+
 #[derive(Debug)]
 pub(crate) struct Parser<'s> {
     green: GreenNodeBuilder<'static>,
@@ -34,11 +114,19 @@ pub(crate) struct Parser<'s> {
     errors: Vec<SyntaxError>,
     cur_start: TextSize,
     cur: SyntaxKind,
+    depth: usize,
 }
 
 impl Parser<'_> {
     pub(crate) fn new(input: &str) -> Parser<'_> {
-        let mut lexer = Lexer::new(input);
+        Parser::with_lexer(Lexer::new(input))
+    }
+
+    pub(crate) fn new_in_action(input: &str) -> Parser<'_> {
+        Parser::with_lexer(Lexer::new_in_action(input))
+    }
+
+    fn with_lexer(mut lexer: Lexer<'_>) -> Parser<'_> {
         let current = lexer.next();
         Parser {
             green: GreenNodeBuilder::new(),
@@ -46,6 +134,7 @@ impl Parser<'_> {
             errors: Vec::new(),
             cur_start: TextSize::new(0),
             cur: current,
+            depth: 0,
         }
     }
 
@@ -94,6 +183,25 @@ impl Parser<'_> {
     }
 }
 
+// Methods for tracking nesting depth.
+impl Parser<'_> {
+    /// Descend one level into the tree, returning `false` (without descending)
+    /// if doing so would exceed [MAX_NESTING_DEPTH]. Each successful call must
+    /// be paired with a call to [Parser::ascend].
+    #[must_use]
+    pub(crate) fn descend(&mut self) -> bool {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    pub(crate) fn ascend(&mut self) {
+        self.depth -= 1;
+    }
+}
+
 // Methods for accessing and consuming tokens. In general, all methods ignore
 // trivia but not whitespace (which can be significant) unless stated otherwise.
 impl<'s> Parser<'s> {
@@ -296,3 +404,155 @@ impl TokenPattern for TokenSet {
         self.contains(kind)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rowan::TextSize;
+
+    use super::{MAX_NESTING_DEPTH, parse, parse_action_list, parse_expr, parse_pipeline};
+    use crate::ast::ext::SyntaxNodeExt;
+    use crate::ast::{Action, AstNode, Expr, Root};
+    use crate::{SyntaxKind, SyntaxNode};
+
+    fn assert_lossless_with_nesting_error(input: &str) {
+        let parsed = parse(input);
+        assert!(
+            parsed
+                .errors
+                .iter()
+                .any(|err| err.message.contains("nested too deeply")),
+            "expected nesting error, got {:?}",
+            parsed.errors
+        );
+        assert_eq!(SyntaxNode::new_root(parsed.root).text().to_string(), input);
+    }
+
+    #[test]
+    fn deeply_nested_parentheses_are_rejected_gracefully() {
+        let depth = 20_000;
+        let input = format!("{{{{{}1{}}}}}", "(".repeat(depth), ")".repeat(depth));
+        assert_lossless_with_nesting_error(&input);
+    }
+
+    #[test]
+    fn deeply_nested_call_arguments_are_rejected_gracefully() {
+        let depth = 20_000;
+        let input = format!("{{{{{}1{}}}}}", "(add 1 ".repeat(depth), ")".repeat(depth));
+        assert_lossless_with_nesting_error(&input);
+    }
+
+    #[test]
+    fn deeply_nested_declarations_are_rejected_gracefully() {
+        let input = format!("{{{{{}1}}}}", "$x := ".repeat(20_000));
+        assert_lossless_with_nesting_error(&input);
+    }
+
+    #[test]
+    fn deeply_nested_actions_are_rejected_gracefully() {
+        let depth = 20_000;
+        let input = format!(
+            "{}x{}",
+            "{{if 1}}{{range .}}".repeat(depth),
+            "{{end}}{{end}}".repeat(depth)
+        );
+        assert_lossless_with_nesting_error(&input);
+    }
+
+    #[test]
+    fn actions_after_deeply_nested_input_are_still_parsed() {
+        let depth = MAX_NESTING_DEPTH * 2;
+        let input = format!(
+            "{}x{}{{{{add 1 2}}}}",
+            "{{if 1}}".repeat(depth),
+            "{{end}}".repeat(depth)
+        );
+        let parsed = parse(&input);
+        let root = SyntaxNode::new_root(parsed.root).to::<Root>();
+        assert_eq!(root.actions().count(), 2);
+        assert!(matches!(root.actions().last(), Some(Action::ExprAction(_))));
+        let trailing_action_start = TextSize::of(input.as_str()) - TextSize::of("{{add 1 2}}");
+        assert!(parsed.errors.iter().all(|err| err.range.end() <= trailing_action_start));
+    }
+
+    #[test]
+    fn moderately_nested_input_is_accepted() {
+        let depth = MAX_NESTING_DEPTH / 4;
+        let input = format!(
+            "{}{{{{{}1{}}}}}{}",
+            "{{if 1}}".repeat(depth),
+            "(".repeat(depth),
+            ")".repeat(depth),
+            "{{end}}".repeat(depth)
+        );
+        let parsed = parse(&input);
+        assert!(parsed.errors.is_empty(), "unexpected errors: {:?}", parsed.errors);
+    }
+
+    #[test]
+    fn long_else_if_chains_are_not_nested() {
+        let input = format!("{{{{if 1}}}}{}{{{{end}}}}", "{{else if 1}}".repeat(20_000));
+        let parsed = parse(&input);
+        assert!(parsed.errors.is_empty(), "unexpected errors: {:?}", parsed.errors);
+    }
+
+    #[test]
+    fn parse_expr_accepts_standalone_expressions() {
+        let parsed = parse_expr(" add 1 (len $x) ");
+        assert!(parsed.errors.is_empty(), "unexpected errors: {:?}", parsed.errors);
+        let Some(Expr::FuncCall(call)) = parsed.tree else {
+            panic!("expected function call, got {:?}", parsed.tree);
+        };
+        assert_eq!(call.args().count(), 2);
+        assert_eq!(call.syntax().text().to_string(), "add 1 (len $x)");
+    }
+
+    #[test]
+    fn parse_expr_rejects_pipelines_and_trailing_input() {
+        let parsed = parse_expr("$x | print");
+        assert!(matches!(parsed.tree, Some(Expr::VarAccess(_))));
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!(parsed.errors[0].message, "unexpected `|` after expression");
+
+        let parsed = parse_expr("1 }} text");
+        assert!(matches!(parsed.tree, Some(Expr::Literal(_))));
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!(parsed.errors[0].message, "unexpected `}}` after expression");
+    }
+
+    #[test]
+    fn parse_expr_reports_empty_input() {
+        let parsed = parse_expr("");
+        assert!(parsed.tree.is_none());
+        assert_eq!(parsed.errors.len(), 1);
+    }
+
+    #[test]
+    fn parse_pipeline_accepts_pipelines() {
+        let parsed = parse_pipeline("$x | printf \"%d\" | print");
+        assert!(parsed.errors.is_empty(), "unexpected errors: {:?}", parsed.errors);
+        let Some(Expr::Pipeline(pipeline)) = parsed.tree else {
+            panic!("expected pipeline, got {:?}", parsed.tree);
+        };
+        assert_eq!(pipeline.stages().count(), 2);
+    }
+
+    #[test]
+    fn parse_action_list_returns_action_list() {
+        let input = "{{$x := 1}} text {{if $x}}{{$x}}{{end}}";
+        let parsed = parse_action_list(input);
+        assert!(parsed.errors.is_empty(), "unexpected errors: {:?}", parsed.errors);
+        assert_eq!(parsed.tree.syntax().kind(), SyntaxKind::ActionList);
+        assert_eq!(parsed.tree.actions().count(), 2);
+        assert_eq!(parsed.tree.syntax().text().to_string(), input);
+    }
+
+    #[test]
+    fn parse_action_list_reports_unmatched_end() {
+        let input = "{{1}}{{end}}{{2}}";
+        let parsed = parse_action_list(input);
+        assert_eq!(parsed.tree.actions().count(), 1);
+        assert!(!parsed.errors.is_empty());
+        let root = parsed.tree.syntax().parent().unwrap();
+        assert_eq!(root.text().to_string(), input);
+    }
+}
@@ -71,6 +71,40 @@ impl Query {
         self.map(|tok| ast::Ident::cast(tok.clone()))
     }
 
+    /// The name of the template defined or invoked by the enclosing `define`,
+    /// `block`, or `template` action, if the cursor is on it.
+    pub fn template_name(&self) -> Option<ast::StringLiteral> {
+        self.map(|tok| {
+            let parent = tok.parent()?;
+            if matches!(
+                parent.kind(),
+                SyntaxKind::DefineClause | SyntaxKind::BlockClause | SyntaxKind::TemplateInvocation
+            ) {
+                ast::StringLiteral::cast(tok.clone())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The `.` of a context access, or the first field of a context field
+    /// chain (which begins with `.`), if the cursor is on it.
+    pub fn context_dot(&self) -> Option<SyntaxToken> {
+        self.map(|tok| {
+            let parent = tok.parent()?;
+            match tok.kind() {
+                SyntaxKind::Dot if parent.is::<ast::ContextAccess>() => Some(tok.clone()),
+                SyntaxKind::Field
+                    if parent.is::<ast::ContextFieldChain>()
+                        && parent.first_token().is_some_and(|first| &first == tok) =>
+                {
+                    Some(tok.clone())
+                }
+                _ => None,
+            }
+        })
+    }
+
     pub fn parent_expr(&self) -> Option<ast::Expr> {
         self.map(|tok| tok.parent_ancestors().find_map(ast::Expr::cast))
     }
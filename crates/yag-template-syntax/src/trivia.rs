@@ -0,0 +1,234 @@
+//! Attachment of comment actions to the actions they document.
+//!
+//! Comment actions such as `{{/* ... */}}` are ordinary siblings in the syntax
+//! tree. To let tools treat them as documentation, each comment is attached to
+//! the nearest non-comment action in the same action list according to the
+//! following line-adjacency rules:
+//!
+//! - A comment starting on the line on which the preceding action ends is a
+//!   _trailing_ comment of that action: `{{$x := 1}} {{/* counter */}}`.
+//! - Otherwise, a comment followed by an action with nothing but whitespace in
+//!   between, and no blank line, is a _leading_ comment of that action. A run of
+//!   such comments all lead the same action.
+//! - All other comments are detached.
+
+use std::collections::HashMap;
+
+use crate::ast::{self, ActionOrText, AstNode};
+use crate::{SyntaxKind, SyntaxNode};
+
+/// The position of an attached comment relative to its action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommentPlacement {
+    Leading,
+    Trailing,
+}
+
+/// A comment action attached to the action it documents.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AttachedComment {
+    pub comment: ast::CommentAction,
+    pub action: ast::Action,
+    pub placement: CommentPlacement,
+}
+
+/// The attachments of all comment actions in a tree.
+#[derive(Debug, Clone, Default)]
+pub struct CommentMap {
+    leading: HashMap<SyntaxNode, Vec<ast::CommentAction>>,
+    trailing: HashMap<SyntaxNode, Vec<ast::CommentAction>>,
+    owners: HashMap<SyntaxNode, (ast::Action, CommentPlacement)>,
+}
+
+impl CommentMap {
+    /// Attach all comment actions within `root`.
+    pub fn build(root: &SyntaxNode) -> Self {
+        let mut map = CommentMap::default();
+        let action_lists = root
+            .descendants()
+            .filter(|node| matches!(node.kind(), SyntaxKind::Root | SyntaxKind::ActionList));
+        for list in action_lists {
+            for attached in attach_comments(&list) {
+                let entries = match attached.placement {
+                    CommentPlacement::Leading => &mut map.leading,
+                    CommentPlacement::Trailing => &mut map.trailing,
+                };
+                entries
+                    .entry(attached.action.syntax().clone())
+                    .or_default()
+                    .push(attached.comment.clone());
+                map.owners
+                    .insert(attached.comment.syntax().clone(), (attached.action, attached.placement));
+            }
+        }
+        map
+    }
+
+    /// The comments preceding `action`, in source order.
+    pub fn leading(&self, action: &ast::Action) -> &[ast::CommentAction] {
+        self.leading.get(action.syntax()).map_or(&[], Vec::as_slice)
+    }
+
+    /// The comments following `action` on the same line, in source order.
+    pub fn trailing(&self, action: &ast::Action) -> &[ast::CommentAction] {
+        self.trailing.get(action.syntax()).map_or(&[], Vec::as_slice)
+    }
+
+    /// The action to which `comment` is attached, if any.
+    pub fn owner(&self, comment: &ast::CommentAction) -> Option<(&ast::Action, CommentPlacement)> {
+        self.owners
+            .get(comment.syntax())
+            .map(|(action, placement)| (action, *placement))
+    }
+}
+
+/// Return the leading comments of `action`, in source order.
+///
+/// This only examines the action list containing `action`, and so is cheaper
+/// than building a [CommentMap] when only a few actions are of interest.
+pub fn leading_comments(action: &ast::Action) -> Vec<ast::CommentAction> {
+    let Some(parent) = action.syntax().parent() else {
+        return Vec::new();
+    };
+    attach_comments(&parent)
+        .into_iter()
+        .filter(|attached| attached.placement == CommentPlacement::Leading && attached.action == *action)
+        .map(|attached| attached.comment)
+        .collect()
+}
+
+/// Render the text of `comments` as documentation. Comment markers,
+/// surrounding blank lines, and the common indentation of each comment are
+/// removed; separate comments are joined by newlines.
+pub fn doc_text(comments: &[ast::CommentAction]) -> String {
+    comments
+        .iter()
+        .filter_map(ast::CommentAction::comment)
+        .map(|comment| dedent(comment.text().trim_matches(|c| c == '\n' || c == '\r')))
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn dedent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|line| line.get(indent..).unwrap_or("").trim_end())
+        .collect();
+    lines.join("\n").trim().to_owned()
+}
+
+/// Attach the comment actions that are direct children of `parent`, which
+/// should be a root or action list.
+pub fn attach_comments(parent: &SyntaxNode) -> Vec<AttachedComment> {
+    let mut attached = Vec::new();
+    // The action ending on the current line, to which comments can trail.
+    let mut trailing_target: Option<ast::Action> = None;
+    // Comments that will lead the next action unless separated from it.
+    let mut pending: Vec<ast::CommentAction> = Vec::new();
+
+    for element in ast::ActionsWithText::new(parent) {
+        match element {
+            ActionOrText::Text(text) => {
+                let text = text.get();
+                let is_whitespace = text.chars().all(char::is_whitespace);
+                if !is_whitespace || text.contains('\n') {
+                    trailing_target = None;
+                }
+                if !is_whitespace || text.matches('\n').count() > 1 {
+                    pending.clear();
+                }
+            }
+            ActionOrText::Action(ast::Action::Comment(comment)) => match &trailing_target {
+                Some(action) => attached.push(AttachedComment {
+                    comment,
+                    action: action.clone(),
+                    placement: CommentPlacement::Trailing,
+                }),
+                None => pending.push(comment),
+            },
+            ActionOrText::Action(action) => {
+                attached.extend(pending.drain(..).map(|comment| AttachedComment {
+                    comment,
+                    action: action.clone(),
+                    placement: CommentPlacement::Leading,
+                }));
+                trailing_target = Some(action);
+            }
+        }
+    }
+    attached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommentMap, CommentPlacement, attach_comments, doc_text, leading_comments};
+    use crate::ast::ext::SyntaxNodeExt;
+    use crate::ast::{Action, AstNode, Root};
+    use crate::parser::parse;
+    use crate::{SyntaxKind, SyntaxNode};
+
+    fn parse_root(input: &str) -> SyntaxNode {
+        let parsed = parse(input);
+        assert!(parsed.errors.is_empty(), "unexpected errors: {:?}", parsed.errors);
+        SyntaxNode::new_root(parsed.root)
+    }
+
+    fn comment_texts(comments: &[crate::ast::CommentAction]) -> Vec<String> {
+        comments
+            .iter()
+            .map(|comment| comment.comment().unwrap().text().trim().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn comments_above_an_action_lead_it() {
+        let root = parse_root("{{/* a */}}\n{{/* b */}}\n{{define \"x\"}}{{end}}");
+        let define = root.to::<Root>().actions().last().unwrap();
+        assert!(matches!(define, Action::TemplateDefinition(_)));
+        assert_eq!(comment_texts(&leading_comments(&define)), ["a", "b"]);
+    }
+
+    #[test]
+    fn comments_on_the_same_line_trail_the_preceding_action() {
+        let root = parse_root("{{$x := 1}} {{/* counter */}}\n{{$y := 2}}");
+        let attached = attach_comments(&root);
+        assert_eq!(attached.len(), 1);
+        assert_eq!(attached[0].placement, CommentPlacement::Trailing);
+        assert_eq!(attached[0].action.syntax().text().to_string(), "{{$x := 1}}");
+    }
+
+    #[test]
+    fn blank_lines_and_text_detach_comments() {
+        let root = parse_root("{{/* a */}}\n\n{{1}}\n{{/* b */}} text {{2}}\n{{/* c */}}");
+        assert!(attach_comments(&root).is_empty());
+    }
+
+    #[test]
+    fn comments_are_attached_within_nested_action_lists() {
+        let root = parse_root("{{if 1}}\n\t{{/* a */}}\n\t{{2}}\n{{end}}");
+        let map = CommentMap::build(&root);
+        let inner = root
+            .descendants()
+            .filter(|node| node.kind() == SyntaxKind::ExprAction)
+            .last()
+            .unwrap()
+            .to::<Action>();
+        assert_eq!(comment_texts(map.leading(&inner)), ["a"]);
+        let comment = root.descendants().find_map(crate::ast::CommentAction::cast).unwrap();
+        assert_eq!(map.owner(&comment), Some((&inner, CommentPlacement::Leading)));
+    }
+
+    #[test]
+    fn doc_text_strips_markers_and_common_indentation() {
+        let root = parse_root("{{/* Greets a user. */}}\n{{/*\n\tArgs:\n\t  .User\n*/}}\n{{define \"x\"}}{{end}}");
+        let define = root.to::<Root>().actions().last().unwrap();
+        assert_eq!(doc_text(&leading_comments(&define)), "Greets a user.\nArgs:\n  .User");
+    }
+}
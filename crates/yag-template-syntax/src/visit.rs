@@ -0,0 +1,380 @@
+//! A generic traversal over the typed AST.
+//!
+//! Implement [Visitor], overriding only the hooks of interest, then pass it to
+//! [walk_root] (or [walk_action]/[walk_expr] for a subtree.) The walker calls
+//!
+//! - [Visitor::enter_action] and then the hook for the specific kind of action
+//!   (e.g., [Visitor::enter_if_action]) before visiting the children of an
+//!   action, and the corresponding `leave_*` hooks in reverse order afterward;
+//! - likewise for expressions; and
+//! - [Visitor::enter_scope] and [Visitor::leave_scope] around each region of
+//!   the template that introduces a new variable scope. The scope of a compound
+//!   action such as `{{if}}` encloses the action itself, so its `enter_scope`
+//!   call precedes `enter_action`.
+//!
+//! The per-kind hooks and the walker are generated from a single list of
+//! variants with exhaustive matches, so adding a variant to [ast::Action] or
+//! [ast::Expr] fails to compile until it is handled here.
+//!
+//! Syntax trees are immutable, so there is no separate mutable visitor: hooks
+//! receive shared references to (cheaply cloneable) nodes and accumulate
+//! whatever state they need in `self`.
+
+use rowan::TextRange;
+
+use crate::ast;
+use crate::ast::AstNode;
+
+/// A region of the template introducing a new variable scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScopeKind {
+    /// The entire template.
+    Root,
+    /// The body of an associated template definition or block. Variables
+    /// declared in enclosing scopes are not visible within.
+    Template,
+    /// An `if`, `with`, `range`, or `while` action, including its clause.
+    Action,
+    /// An `{{else}}` or `{{else if}}` branch of an `if` or `with` action. The
+    /// scope of each branch is nested within that of the previous branch.
+    ElseBranch,
+    /// The list of actions forming a body of a compound action.
+    Body,
+}
+
+macro_rules! define_visitor {
+    (
+        actions {
+            $($action_variant:ident($action_ty:ident) => $enter_action:ident, $leave_action:ident;)*
+        }
+        exprs {
+            $($expr_variant:ident($expr_ty:ident) => $enter_expr:ident, $leave_expr:ident;)*
+        }
+    ) => {
+        /// Hooks called while walking the typed AST. See the
+        /// [module documentation](self) for the order in which they are called.
+        #[allow(unused_variables)]
+        pub trait Visitor {
+            fn enter_scope(&mut self, kind: ScopeKind, range: TextRange) {}
+            fn leave_scope(&mut self, kind: ScopeKind, range: TextRange) {}
+
+            fn enter_action(&mut self, action: &ast::Action) {}
+            fn leave_action(&mut self, action: &ast::Action) {}
+            $(
+                fn $enter_action(&mut self, node: &ast::$action_ty) {}
+                fn $leave_action(&mut self, node: &ast::$action_ty) {}
+            )*
+
+            fn enter_expr(&mut self, expr: &ast::Expr) {}
+            fn leave_expr(&mut self, expr: &ast::Expr) {}
+            $(
+                fn $enter_expr(&mut self, node: &ast::$expr_ty) {}
+                fn $leave_expr(&mut self, node: &ast::$expr_ty) {}
+            )*
+        }
+
+        fn enter_action<V: Visitor + ?Sized>(v: &mut V, action: &ast::Action) {
+            v.enter_action(action);
+            match action {
+                $(ast::Action::$action_variant(node) => v.$enter_action(node),)*
+            }
+        }
+
+        fn leave_action<V: Visitor + ?Sized>(v: &mut V, action: &ast::Action) {
+            match action {
+                $(ast::Action::$action_variant(node) => v.$leave_action(node),)*
+            }
+            v.leave_action(action);
+        }
+
+        fn enter_expr<V: Visitor + ?Sized>(v: &mut V, expr: &ast::Expr) {
+            v.enter_expr(expr);
+            match expr {
+                $(ast::Expr::$expr_variant(node) => v.$enter_expr(node),)*
+            }
+        }
+
+        fn leave_expr<V: Visitor + ?Sized>(v: &mut V, expr: &ast::Expr) {
+            match expr {
+                $(ast::Expr::$expr_variant(node) => v.$leave_expr(node),)*
+            }
+            v.leave_expr(expr);
+        }
+    };
+}
+
+define_visitor! {
+    actions {
+        Comment(CommentAction) => enter_comment_action, leave_comment_action;
+        TemplateDefinition(TemplateDefinition) => enter_template_definition, leave_template_definition;
+        TemplateBlock(TemplateBlock) => enter_template_block, leave_template_block;
+        TemplateInvocation(TemplateInvocation) => enter_template_invocation, leave_template_invocation;
+        Return(ReturnAction) => enter_return_action, leave_return_action;
+        If(IfAction) => enter_if_action, leave_if_action;
+        With(WithAction) => enter_with_action, leave_with_action;
+        Range(RangeLoop) => enter_range_loop, leave_range_loop;
+        While(WhileLoop) => enter_while_loop, leave_while_loop;
+        Break(LoopBreak) => enter_loop_break, leave_loop_break;
+        Continue(LoopContinue) => enter_loop_continue, leave_loop_continue;
+        TryCatch(TryCatchAction) => enter_try_catch_action, leave_try_catch_action;
+        ExprAction(ExprAction) => enter_expr_action, leave_expr_action;
+    }
+    exprs {
+        FuncCall(FuncCall) => enter_func_call, leave_func_call;
+        ExprCall(ExprCall) => enter_expr_call, leave_expr_call;
+        Parenthesized(ParenthesizedExpr) => enter_parenthesized_expr, leave_parenthesized_expr;
+        Pipeline(Pipeline) => enter_pipeline, leave_pipeline;
+        ContextAccess(ContextAccess) => enter_context_access, leave_context_access;
+        ContextFieldChain(ContextFieldChain) => enter_context_field_chain, leave_context_field_chain;
+        ExprFieldChain(ExprFieldChain) => enter_expr_field_chain, leave_expr_field_chain;
+        VarAccess(VarAccess) => enter_var_access, leave_var_access;
+        VarDecl(VarDecl) => enter_var_decl, leave_var_decl;
+        VarAssign(VarAssign) => enter_var_assign, leave_var_assign;
+        Literal(Literal) => enter_literal, leave_literal;
+    }
+}
+
+/// Walk the entire template.
+pub fn walk_root<V: Visitor + ?Sized>(v: &mut V, root: &ast::Root) {
+    let range = root.text_range();
+    v.enter_scope(ScopeKind::Root, range);
+    walk_actions(v, root.actions());
+    v.leave_scope(ScopeKind::Root, range);
+}
+
+/// Walk an action and all its descendants.
+pub fn walk_action<V: Visitor + ?Sized>(v: &mut V, action: &ast::Action) {
+    use ast::Action::*;
+
+    let scope = match action {
+        If(_) | With(_) | Range(_) | While(_) => Some(action.text_range()),
+        _ => None,
+    };
+    if let Some(range) = scope {
+        v.enter_scope(ScopeKind::Action, range);
+    }
+    enter_action(v, action);
+
+    match action {
+        Comment(_) | Break(_) | Continue(_) => {}
+        TemplateDefinition(def) => walk_template_body(v, def.template_body()),
+        TemplateBlock(block) => {
+            walk_opt_expr(v, block.context_data());
+            walk_template_body(v, block.template_body());
+        }
+        TemplateInvocation(invocation) => walk_opt_expr(v, invocation.context_data()),
+        Return(ret) => walk_opt_expr(v, ret.expr()),
+        If(if_action) => {
+            walk_opt_expr(v, if_action.condition());
+            walk_body(v, if_action.body());
+            walk_else_branches(v, if_action.else_branches());
+        }
+        With(with_action) => {
+            walk_opt_expr(v, with_action.condition());
+            walk_body(v, with_action.body());
+            walk_else_branches(v, with_action.else_branches());
+        }
+        Range(range_loop) => {
+            walk_opt_expr(v, range_loop.range_expr());
+            walk_body(v, range_loop.body());
+            walk_body(v, range_loop.else_branch().and_then(|branch| branch.body()));
+        }
+        While(while_loop) => {
+            walk_opt_expr(v, while_loop.condition());
+            walk_body(v, while_loop.body());
+            walk_body(v, while_loop.else_branch().and_then(|branch| branch.body()));
+        }
+        TryCatch(try_catch) => {
+            walk_body(v, try_catch.try_body());
+            walk_body(v, try_catch.catch_body());
+        }
+        ExprAction(expr_action) => walk_opt_expr(v, expr_action.expr()),
+    }
+
+    leave_action(v, action);
+    if let Some(range) = scope {
+        v.leave_scope(ScopeKind::Action, range);
+    }
+}
+
+/// Walk an expression and all its descendants.
+pub fn walk_expr<V: Visitor + ?Sized>(v: &mut V, expr: &ast::Expr) {
+    use ast::Expr::*;
+
+    enter_expr(v, expr);
+    match expr {
+        FuncCall(call) => call.args().for_each(|arg| walk_expr(v, &arg)),
+        ExprCall(call) => {
+            walk_opt_expr(v, call.callee());
+            call.args().for_each(|arg| walk_expr(v, &arg));
+        }
+        Parenthesized(p) => walk_opt_expr(v, p.inner_expr()),
+        Pipeline(p) => {
+            walk_opt_expr(v, p.init_expr());
+            p.stages().for_each(|stage| walk_opt_expr(v, stage.call_expr()));
+        }
+        ContextAccess(_) | ContextFieldChain(_) | Literal(_) => {}
+        ExprFieldChain(chain) => walk_opt_expr(v, chain.base_expr()),
+        VarAccess(_) => {}
+        VarDecl(decl) => walk_opt_expr(v, decl.initializer()),
+        VarAssign(assign) => walk_opt_expr(v, assign.assign_expr()),
+    }
+    leave_expr(v, expr);
+}
+
+fn walk_actions<V: Visitor + ?Sized>(v: &mut V, actions: impl Iterator<Item = ast::Action>) {
+    for action in actions {
+        walk_action(v, &action);
+    }
+}
+
+fn walk_opt_expr<V: Visitor + ?Sized>(v: &mut V, expr: Option<ast::Expr>) {
+    if let Some(expr) = expr {
+        walk_expr(v, &expr);
+    }
+}
+
+fn walk_body<V: Visitor + ?Sized>(v: &mut V, body: Option<ast::ActionList>) {
+    if let Some(body) = body {
+        let range = body.text_range();
+        v.enter_scope(ScopeKind::Body, range);
+        walk_actions(v, body.actions());
+        v.leave_scope(ScopeKind::Body, range);
+    }
+}
+
+fn walk_template_body<V: Visitor + ?Sized>(v: &mut V, body: Option<ast::ActionList>) {
+    if let Some(body) = body {
+        let range = body.text_range();
+        v.enter_scope(ScopeKind::Template, range);
+        walk_actions(v, body.actions());
+        v.leave_scope(ScopeKind::Template, range);
+    }
+}
+
+fn walk_else_branches<V: Visitor + ?Sized>(v: &mut V, else_branches: impl Iterator<Item = ast::ElseBranch>) {
+    // Iterate rather than recurse so that long else-if chains cannot overflow
+    // the stack, but leave the scopes in reverse order once done.
+    let mut entered = Vec::new();
+    for else_branch in else_branches {
+        let range = else_branch.text_range();
+        v.enter_scope(ScopeKind::ElseBranch, range);
+        entered.push(range);
+        walk_opt_expr(v, else_branch.condition());
+        walk_body(v, else_branch.body());
+    }
+    for range in entered.into_iter().rev() {
+        v.leave_scope(ScopeKind::ElseBranch, range);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rowan::TextRange;
+
+    use super::{ScopeKind, Visitor, walk_root};
+    use crate::ast::ext::SyntaxNodeExt;
+    use crate::{SyntaxNode, ast, parser};
+
+    fn walk(source: &str, v: &mut impl Visitor) {
+        let parsed = parser::parse(source);
+        assert!(parsed.errors.is_empty(), "source did not parse: {:?}", parsed.errors);
+        walk_root(v, &SyntaxNode::new_root(parsed.root).to::<ast::Root>());
+    }
+
+    #[derive(Default)]
+    struct EventLog(Vec<String>);
+
+    impl Visitor for EventLog {
+        fn enter_scope(&mut self, kind: ScopeKind, _range: TextRange) {
+            self.0.push(format!("enter {kind:?}"));
+        }
+
+        fn leave_scope(&mut self, kind: ScopeKind, _range: TextRange) {
+            self.0.push(format!("leave {kind:?}"));
+        }
+
+        fn enter_if_action(&mut self, _node: &ast::IfAction) {
+            self.0.push("enter if".into());
+        }
+
+        fn leave_if_action(&mut self, _node: &ast::IfAction) {
+            self.0.push("leave if".into());
+        }
+
+        fn enter_func_call(&mut self, node: &ast::FuncCall) {
+            self.0.push(format!("call {}", node.func_name().unwrap().get()));
+        }
+
+        fn enter_var_decl(&mut self, node: &ast::VarDecl) {
+            self.0.push(format!("decl {}", node.var().unwrap().name()));
+        }
+    }
+
+    #[test]
+    fn hooks_and_scopes_are_called_in_tree_order() {
+        let mut log = EventLog::default();
+        walk(
+            r#"{{if f}}{{$x := g 1}}{{else if h}}{{else}}{{define "t"}}{{i}}{{end}}{{end}}"#,
+            &mut log,
+        );
+        assert_eq!(
+            log.0,
+            [
+                "enter Root",
+                "enter Action",
+                "enter if",
+                "call f",
+                "enter Body",
+                "decl $x",
+                "call g",
+                "leave Body",
+                "enter ElseBranch",
+                "call h",
+                "enter Body",
+                "leave Body",
+                "enter ElseBranch",
+                "enter Body",
+                "enter Template",
+                "call i",
+                "leave Template",
+                "leave Body",
+                "leave ElseBranch",
+                "leave ElseBranch",
+                "leave if",
+                "leave Action",
+                "leave Root",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_expression_is_visited() {
+        #[derive(Default)]
+        struct ExprCounter {
+            entered: usize,
+            left: usize,
+        }
+
+        impl Visitor for ExprCounter {
+            fn enter_expr(&mut self, _expr: &ast::Expr) {
+                self.entered += 1;
+            }
+
+            fn leave_expr(&mut self, _expr: &ast::Expr) {
+                self.left += 1;
+            }
+        }
+
+        let mut counter = ExprCounter::default();
+        walk(
+            r#"{{range $i, $v := .List | f (g $x.Y) "s"}}{{$v = (.A).B 1}}{{end}}{{template "t" .}}"#,
+            &mut counter,
+        );
+        // .List | f (g $x.Y) "s"  => pipeline, .List, f call, parenthesized, g call, $x.Y, $x, "s"
+        // $v = (.A).B 1           => assign, expr call, field chain, parenthesized, .A, 1
+        // .                       => context access
+        assert_eq!(counter.entered, 15);
+        assert_eq!(counter.left, counter.entered);
+    }
+}
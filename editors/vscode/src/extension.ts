@@ -1,6 +1,6 @@
 import { resolve } from 'path';
 import { commands, ExtensionContext, window, workspace, WorkspaceConfiguration } from 'vscode';
-import { LanguageClient, LanguageClientOptions, ServerOptions } from 'vscode-languageclient/node';
+import { ExecuteCommandRequest, LanguageClient, LanguageClientOptions, ServerOptions } from 'vscode-languageclient/node';
 
 let client: LanguageClient | undefined = undefined;
 
@@ -9,6 +9,7 @@ export async function activate(context: ExtensionContext) {
 	const config = workspace.getConfiguration('yag-template-lsp');
 
 	context.subscriptions.push(commands.registerCommand('yag-template-lsp.restartServer', restartServer));
+	context.subscriptions.push(commands.registerCommand('yag-template-lsp.showDatabaseSchema', showDatabaseSchema));
 	try {
 		await startClient(config);
 	} catch (error) {
@@ -25,6 +26,16 @@ function restartServer() {
 	void client?.restart();
 }
 
+async function showDatabaseSchema() {
+	if (!client) return;
+	const schema: string = await client.sendRequest(ExecuteCommandRequest.type, {
+		command: 'yag-template-lsp.databaseSchema',
+		arguments: [],
+	});
+	const doc = await workspace.openTextDocument({ language: 'markdown', content: schema });
+	await window.showTextDocument(doc);
+}
+
 async function startClient(config: WorkspaceConfiguration) {
 	const extraEnv = config.get<Record<string, string> | null>('server.extraEnv') ?? {};
 	const run = {
@@ -40,6 +51,9 @@ async function startClient(config: WorkspaceConfiguration) {
 	const clientOptions: LanguageClientOptions = {
 		documentSelector: [{ scheme: 'file', language: 'yag' }],
 		initializationOptions: config,
+		synchronize: {
+			configurationSection: 'yag-template-lsp',
+		},
 	};
 
 	client = new LanguageClient('yag-template-lsp', 'YAGPDB Template Language Server', serverOptions, clientOptions);